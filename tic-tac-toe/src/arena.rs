@@ -0,0 +1,398 @@
+// arena.rs
+
+//! Games between computer players, with no one at the keyboard.
+//!
+//! `play_game` plays one game between two brains. `play_match` plays a series of games,
+//! swapping who moves first every game so neither brain gets the first move advantage, and
+//! collects the results in a `MatchReport`.
+//!
+//! ```
+//! use tic_tac_toe::arena::play_match;
+//! use tic_tac_toe::{Board, BrainLevelOne, BrainLevelTwo, Player};
+//!
+//! let report = play_match(&BrainLevelTwo, &BrainLevelOne::new(1), &Board::new(Player::X), 10)?;
+//! assert_eq!(report.losses(), 0);
+//! println!("{}", report);
+//! # Ok::<(), tic_tac_toe::GameError>(())
+//! ```
+use crate::brains::TicTacToeBrain;
+use crate::error::GameError;
+use crate::tic_tac_toe::{Board, BoardState, Player};
+use std::fmt;
+
+/// How a game ended for one of the brains.
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub enum Outcome {
+    Win,
+    Draw,
+    Loss,
+}
+
+impl Outcome {
+    /// Returns the points the outcome is worth: 1 for a win, 0.5 for a draw and 0 for a loss.
+    pub fn score(&self) -> f64 {
+        match self {
+            Outcome::Win => 1.0,
+            Outcome::Draw => 0.5,
+            Outcome::Loss => 0.0,
+        }
+    }
+}
+
+/// One finished game of a match.
+#[derive(Debug, Clone)]
+pub struct GameRecord {
+    /// The finished board. Its history holds every move of the game.
+    pub board: Board,
+    /// The player brain A played in this game.
+    pub a_player: Player,
+}
+
+impl GameRecord {
+    /// Returns how the game ended for brain A.
+    pub fn outcome(&self) -> Outcome {
+        match self.board.get_state() {
+            BoardState::Ended(Some(winner)) if winner == self.a_player => Outcome::Win,
+            BoardState::Ended(Some(_)) => Outcome::Loss,
+            _ => Outcome::Draw,
+        }
+    }
+
+    /// Returns true if brain A played the board's first player.
+    pub fn a_moved_first(&self) -> bool {
+        self.board.first_player() == self.a_player
+    }
+
+    /// Returns the number of moves played.
+    pub fn length(&self) -> usize {
+        self.board.history().len()
+    }
+}
+
+/// The results of a match between brain A and brain B, from brain A's point of view.
+#[derive(Debug, Clone, Default)]
+pub struct MatchReport {
+    pub records: Vec<GameRecord>,
+}
+
+impl MatchReport {
+    /// Returns the number of games played.
+    pub fn games(&self) -> usize {
+        self.records.len()
+    }
+
+    /// Returns the number of games brain A won.
+    pub fn wins(&self) -> usize {
+        self.count(Outcome::Win)
+    }
+
+    /// Returns the number of drawn games.
+    pub fn draws(&self) -> usize {
+        self.count(Outcome::Draw)
+    }
+
+    /// Returns the number of games brain A lost, which brain B won.
+    pub fn losses(&self) -> usize {
+        self.count(Outcome::Loss)
+    }
+
+    fn count(&self, outcome: Outcome) -> usize {
+        self.records
+            .iter()
+            .filter(|record| record.outcome() == outcome)
+            .count()
+    }
+
+    /// Returns the average number of moves in a game, or 0 if no games were played.
+    pub fn average_length(&self) -> f64 {
+        if self.records.is_empty() {
+            return 0.0;
+        }
+
+        let total: usize = self.records.iter().map(GameRecord::length).sum();
+        total as f64 / self.games() as f64
+    }
+
+    /// Returns brain A's average points per game, counting a draw as half a win.
+    /// Returns 0.5 if no games were played.
+    pub fn score(&self) -> f64 {
+        if self.records.is_empty() {
+            return 0.5;
+        }
+
+        let total: f64 = self
+            .records
+            .iter()
+            .map(|record| record.outcome().score())
+            .sum();
+        total / self.games() as f64
+    }
+
+    /// Returns a 95% confidence interval for brain A's share of wins, as `(low, high)`.
+    ///
+    /// Uses the Wilson score interval, which stays between 0 and 1 and behaves well when
+    /// one brain wins every game.
+    pub fn win_rate_interval(&self) -> (f64, f64) {
+        wilson_interval(self.wins(), self.games())
+    }
+
+    /// Returns a 95% confidence interval for brain A's points per game (see `score`), as
+    /// `(low, high)`.
+    ///
+    /// Uses the normal approximation with the spread of the points actually scored.
+    pub fn score_interval(&self) -> (f64, f64) {
+        let games = self.games() as f64;
+        if self.records.is_empty() {
+            return (0.0, 1.0);
+        }
+
+        let mean = self.score();
+        let variance = self
+            .records
+            .iter()
+            .map(|record| (record.outcome().score() - mean).powi(2))
+            .sum::<f64>()
+            / games;
+        let margin = Z_95 * (variance / games).sqrt();
+        ((mean - margin).max(0.0), (mean + margin).min(1.0))
+    }
+}
+
+impl fmt::Display for MatchReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (win_low, win_high) = self.win_rate_interval();
+        let (score_low, score_high) = self.score_interval();
+
+        writeln!(f, "Games: {}", self.games())?;
+        writeln!(
+            f,
+            "A wins: {}, draws: {}, B wins: {}",
+            self.wins(),
+            self.draws(),
+            self.losses()
+        )?;
+        writeln!(f, "Average game length: {:.1} moves", self.average_length())?;
+        writeln!(
+            f,
+            "A win rate: {:.1}% (95% CI {:.1}% to {:.1}%)",
+            percent(wins_share(self.wins(), self.games())),
+            percent(win_low),
+            percent(win_high)
+        )?;
+        write!(
+            f,
+            "A score: {:.3} (95% CI {:.3} to {:.3})",
+            self.score(),
+            score_low,
+            score_high
+        )
+    }
+}
+
+/// The number of standard deviations either side of the mean that holds 95% of a normal
+/// distribution.
+const Z_95: f64 = 1.96;
+
+fn wins_share(wins: usize, games: usize) -> f64 {
+    if games == 0 {
+        0.0
+    } else {
+        wins as f64 / games as f64
+    }
+}
+
+fn percent(share: f64) -> f64 {
+    share * 100.0
+}
+
+/// Returns the 95% Wilson score interval for `successes` out of `trials`.
+fn wilson_interval(successes: usize, trials: usize) -> (f64, f64) {
+    if trials == 0 {
+        return (0.0, 1.0);
+    }
+
+    let n = trials as f64;
+    let p = successes as f64 / n;
+    let z2 = Z_95 * Z_95;
+    let centre = (p + z2 / (2.0 * n)) / (1.0 + z2 / n);
+    let margin = Z_95 * (p * (1.0 - p) / n + z2 / (4.0 * n * n)).sqrt() / (1.0 + z2 / n);
+    ((centre - margin).max(0.0), (centre + margin).min(1.0))
+}
+
+/// Plays a game to the end from `start`.
+///
+/// # Parameters
+///
+/// - `first`: The brain that plays `start.get_next_player()`.
+/// - `second`: The brain that plays the other side.
+/// - `start`: The board to start from, usually an empty one.
+///
+/// # Returns
+///
+/// - `Ok(Board)` with the finished game.
+/// - `Err(GameError)` if a brain fails to choose a move or chooses an illegal one.
+pub fn play_game(
+    first: &dyn TicTacToeBrain,
+    second: &dyn TicTacToeBrain,
+    start: &Board,
+) -> Result<Board, GameError> {
+    let mut board = start.clone();
+    let first_player = board.get_next_player();
+
+    while board.get_state() == BoardState::Ongoing {
+        let brain = if board.get_next_player() == first_player {
+            first
+        } else {
+            second
+        };
+        let player_move = brain.make_move(&board)?;
+        board.make_move(player_move)?;
+    }
+
+    Ok(board)
+}
+
+/// Plays `games` games between `brain_a` and `brain_b`, all starting from `start`.
+///
+/// Brain A makes the first move in the first game, then the brains take turns to go first.
+/// The first mover always plays the player to move in `start`, so brain A plays X in some
+/// games and O in others.
+///
+/// # Returns
+///
+/// - `Ok(MatchReport)` with every game played.
+/// - `Err(GameError::BrainFailure)` if a brain is too slow to play on `start`, checked
+///   before the first game.
+/// - `Err(GameError)` if `start` has already ended, or a brain fails to choose a move or
+///   chooses an illegal one.
+pub fn play_match(
+    brain_a: &dyn TicTacToeBrain,
+    brain_b: &dyn TicTacToeBrain,
+    start: &Board,
+    games: usize,
+) -> Result<MatchReport, GameError> {
+    if start.get_state() != BoardState::Ongoing {
+        return Err(GameError::GameOver);
+    }
+    for (name, brain) in [("A", brain_a), ("B", brain_b)] {
+        if !brain.can_play(start) {
+            return Err(GameError::BrainFailure(format!(
+                "brain {} is too slow to play on a {} board",
+                name,
+                start.size_text()
+            )));
+        }
+    }
+
+    let mut report = MatchReport::default();
+    let first_player = start.get_next_player();
+
+    for game in 0..games {
+        let a_moves_first = game.is_multiple_of(2);
+        let (first, second) = if a_moves_first {
+            (brain_a, brain_b)
+        } else {
+            (brain_b, brain_a)
+        };
+
+        report.records.push(GameRecord {
+            board: play_game(first, second, start)?,
+            a_player: if a_moves_first {
+                first_player
+            } else {
+                first_player.opponent()
+            },
+        });
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::brains::{BrainLevelOne, BrainLevelTwo};
+    use crate::tic_tac_toe::Move;
+
+    /// A brain that always plays the lowest empty slot.
+    struct FirstEmptySlot;
+
+    impl TicTacToeBrain for FirstEmptySlot {
+        fn make_move(&self, board: &Board) -> Result<Move, GameError> {
+            let position = (1..=board.get_number_of_slots())
+                .find(|&position| board.is_slot_empty(position))
+                .ok_or(GameError::GameOver)?;
+            Move::create(position, board.get_next_player())
+        }
+    }
+
+    #[test]
+    fn test_play_game() {
+        let board = play_game(&FirstEmptySlot, &FirstEmptySlot, &Board::new(Player::X)).unwrap();
+
+        // X fills 1, 3, 5 and 7, completing the anti-diagonal.
+        assert_eq!(board.get_state(), BoardState::Ended(Some(Player::X)));
+        assert_eq!(board.history().len(), 7);
+    }
+
+    #[test]
+    fn test_match_alternates_first_player() {
+        let report =
+            play_match(&FirstEmptySlot, &FirstEmptySlot, &Board::new(Player::X), 4).unwrap();
+
+        // Whoever moves first wins, so each brain wins the games it starts.
+        assert_eq!(report.games(), 4);
+        assert_eq!((report.wins(), report.draws(), report.losses()), (2, 0, 2));
+        assert!(report.records[0].a_moved_first());
+        assert!(!report.records[1].a_moved_first());
+        assert_eq!(report.records[1].a_player, Player::O);
+        assert_eq!(report.average_length(), 7.0);
+        assert_eq!(report.score(), 0.5);
+    }
+
+    #[test]
+    fn test_perfect_brain_never_loses() {
+        let report = play_match(
+            &BrainLevelTwo,
+            &BrainLevelOne::new(3),
+            &Board::new(Player::X),
+            20,
+        )
+        .unwrap();
+
+        assert_eq!(report.losses(), 0);
+        assert!(report.score() > 0.5);
+        let (low, high) = report.win_rate_interval();
+        assert!(low <= high && high <= 1.0);
+    }
+
+    #[test]
+    fn test_confidence_intervals() {
+        let (low, high) = wilson_interval(50, 100);
+        assert!((low - 0.404).abs() < 0.001 && (high - 0.596).abs() < 0.001);
+
+        let (low, high) = wilson_interval(10, 10);
+        assert!(low > 0.7 && high == 1.0);
+        assert_eq!(wilson_interval(0, 0), (0.0, 1.0));
+
+        let report = MatchReport::default();
+        assert_eq!(report.score_interval(), (0.0, 1.0));
+        assert_eq!(report.average_length(), 0.0);
+    }
+
+    #[test]
+    fn test_match_rejects_finished_board() {
+        let board = Board::from_position_str("XXX/OO1/3 O").unwrap();
+        assert!(play_match(&FirstEmptySlot, &FirstEmptySlot, &board, 1).is_err());
+    }
+
+    #[test]
+    fn test_match_rejects_slow_brains() {
+        let board = Board::with_size(Player::X, 4, 4, 4).unwrap();
+        assert_eq!(
+            play_match(&FirstEmptySlot, &BrainLevelTwo, &board, 1).unwrap_err(),
+            GameError::BrainFailure("brain B is too slow to play on a 4x4 board".to_string())
+        );
+        assert!(play_match(&FirstEmptySlot, &BrainLevelOne::new(1), &board, 1).is_ok());
+    }
+}
@@ -1,30 +1,103 @@
 // Here, I would like to build my AI. One AI that picks positions randomly, and another that uses an algorithm from CS50\
-use crate::tic_tac_toe::{Board, Move, BOARD_SIZE};
+use crate::error::GameError;
+use crate::random::{random_seed, seeded_rng, GameRng};
+use crate::tic_tac_toe::{Board, BoardState, Move, BOARD_SIZE};
 use rand::Rng;
+use std::sync::Mutex;
+
+mod alpha_beta;
+mod monte_carlo;
+mod transposition;
+
+pub use alpha_beta::BrainLevelThree;
+pub use monte_carlo::{BrainMonteCarlo, MoveStats};
+pub use transposition::{Bound, SharedTable, TableEntry, TranspositionTable};
+
+/// How many positions `BrainLevelTwo` remembers while choosing a move. Every position of a
+/// 3x3 board fits many times over.
+const MINIMAX_TABLE_SIZE: usize = 1 << 16;
+
+/// The most open slots `BrainLevelTwo` searches from. An empty 3x3 board takes a moment, and
+/// every extra slot multiplies the work.
+const MINIMAX_MAX_OPEN_SLOTS: usize = BOARD_SIZE * BOARD_SIZE;
+
 pub trait TicTacToeBrain {
-    fn make_move(&self, board: &Board) -> Result<Move, String>;
+    /// Chooses a move for the next player on `board`.
+    ///
+    /// Returns `GameError::GameOver` if the game has already ended.
+    fn make_move(&self, board: &Board) -> Result<Move, GameError>;
+
+    /// Returns false if choosing a move on `board` would take too long, so the brain should
+    /// not be asked to. Brains that keep to a time budget can play on any board.
+    fn can_play(&self, _board: &Board) -> bool {
+        true
+    }
+}
+
+/// A brain that plays a random empty slot.
+///
+/// Its moves come from its own `GameRng`, so two brains created with the same seed play the
+/// same moves on the same boards.
+pub struct BrainLevelOne {
+    rng: Mutex<GameRng>,
+}
+
+/// A brain that plays perfectly using the minimax algorithm.
+///
+/// It searches every possible continuation of the game, so it never loses on a 3x3 board.
+/// Positions that can be reached in more than one way, or that are rotated or reflected
+/// copies of each other, are only scored once. The search still grows very quickly with the
+/// board size, so it is only practical for small boards.
+pub struct BrainLevelTwo;
+
+impl BrainLevelOne {
+    /// Creates a brain whose moves are chosen with a generator seeded with `seed`.
+    pub fn new(seed: u64) -> Self {
+        BrainLevelOne::from_rng(seeded_rng(seed))
+    }
+
+    /// Creates a brain whose moves are chosen with `rng`.
+    pub fn from_rng(rng: GameRng) -> Self {
+        BrainLevelOne {
+            rng: Mutex::new(rng),
+        }
+    }
 }
 
-pub struct BrainLevelOne;
-// pub struct BrainLevelTwo;
+impl Default for BrainLevelOne {
+    /// Creates a brain with a random seed.
+    fn default() -> Self {
+        BrainLevelOne::new(random_seed())
+    }
+}
 
 impl TicTacToeBrain for BrainLevelOne {
-    fn make_move(&self, board: &Board) -> Result<Move, String> {
+    fn make_move(&self, board: &Board) -> Result<Move, GameError> {
         // Get all empty positions
         // Pick randomly from said positions
         // Make a move.
+        if board.get_state() != BoardState::Ongoing {
+            return Err(GameError::GameOver);
+        }
+
         let mut empty_positions: Vec<usize> = vec![];
 
-        for position in 1..=BOARD_SIZE.pow(2) {
+        for position in 1..=board.get_number_of_slots() {
             if board.is_slot_empty(position) {
                 empty_positions.push(position);
             }
         }
 
         if empty_positions.is_empty() {
-            return Err("No available positions for AI to play. ".to_string());
+            return Err(GameError::BrainFailure(
+                "No available positions for AI to play".to_string(),
+            ));
         }
-        let position = rand::thread_rng().gen_range(0..empty_positions.len());
+        let position = self
+            .rng
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .gen_range(0..empty_positions.len());
         let chosen_position = empty_positions[position];
         // Should I give the Brain it's own Player when initialized or
         // use Board::get_next_player()
@@ -32,3 +105,223 @@ impl TicTacToeBrain for BrainLevelOne {
         Move::create(chosen_position, board.get_next_player())
     }
 }
+
+impl BrainLevelTwo {
+    /// Finds the best move for the next player and how good it is.
+    ///
+    /// The evaluation is from the point of view of the player about to move:
+    ///
+    /// - A positive number means the player can force a win. Quicker wins score higher.
+    /// - `0` means best play from both sides ends in a tie.
+    /// - A negative number means the opponent can force a win. Slower losses score higher.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok((Move, evaluation))` with the best move found.
+    /// - `Err(GameError::GameOver)` if the game has already ended.
+    /// - `Err(GameError::BrainFailure)` if there are no available positions.
+    pub fn evaluate(&self, board: &Board) -> Result<(Move, i32), GameError> {
+        if board.get_state() != BoardState::Ongoing {
+            return Err(GameError::GameOver);
+        }
+
+        let player = board.get_next_player();
+        let mut best: Option<(Move, i32)> = None;
+        let mut table = TranspositionTable::new(MINIMAX_TABLE_SIZE);
+
+        for position in 1..=board.get_number_of_slots() {
+            if !board.is_slot_empty(position) {
+                continue;
+            }
+
+            let player_move = Move::create(position, player)?;
+            let mut next_board = board.clone();
+            let score = Self::score_move(&mut next_board, player_move, &mut table)?;
+
+            if best.is_none_or(|(_, best_score)| score > best_score) {
+                best = Some((player_move, score));
+            }
+        }
+
+        best.ok_or_else(|| {
+            GameError::BrainFailure("No available positions for AI to play".to_string())
+        })
+    }
+
+    /// Plays `player_move` on `board` and scores it for the player making the move.
+    fn score_move(
+        board: &mut Board,
+        player_move: Move,
+        table: &mut TranspositionTable,
+    ) -> Result<i32, GameError> {
+        let score = match board.make_move(player_move)? {
+            // Winning with more open slots left means winning sooner.
+            BoardState::Ended(Some(winner)) if winner == player_move.player() => {
+                board.get_number_of_open_slots() as i32 + 1
+            }
+            // Under the misère rule, completing a line loses.
+            BoardState::Ended(Some(_)) => -(board.get_number_of_open_slots() as i32 + 1),
+            BoardState::Ended(None) => 0,
+            BoardState::Ongoing => -Self::minimax(board, table)?,
+        };
+
+        Ok(score)
+    }
+
+    /// Returns the best score the next player can reach from this board.
+    ///
+    /// Scores are exact, so they are stored in `table` and reused for any board with the
+    /// same canonical hash.
+    fn minimax(board: &Board, table: &mut TranspositionTable) -> Result<i32, GameError> {
+        let hash = board.canonical_hash();
+        if let Some(entry) = table.get(hash) {
+            return Ok(entry.score);
+        }
+
+        let player = board.get_next_player();
+        let mut best_score = i32::MIN;
+
+        for position in 1..=board.get_number_of_slots() {
+            if !board.is_slot_empty(position) {
+                continue;
+            }
+
+            let mut next_board = board.clone();
+            let score = Self::score_move(&mut next_board, Move::create(position, player)?, table)?;
+            best_score = best_score.max(score);
+        }
+
+        table.insert(TableEntry {
+            hash,
+            depth: board.get_number_of_open_slots(),
+            score: best_score,
+            bound: Bound::Exact,
+        });
+        Ok(best_score)
+    }
+}
+
+impl TicTacToeBrain for BrainLevelTwo {
+    fn make_move(&self, board: &Board) -> Result<Move, GameError> {
+        self.evaluate(board).map(|(player_move, _)| player_move)
+    }
+
+    /// Only boards with at most as many open slots as an empty 3x3 board can be searched
+    /// in reasonable time.
+    fn can_play(&self, board: &Board) -> bool {
+        board.get_number_of_open_slots() <= MINIMAX_MAX_OPEN_SLOTS
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::tic_tac_toe::Player;
+
+    /// Plays every possible opponent reply against `brain` and
+    /// asserts that the opponent never wins.
+    pub(crate) fn assert_never_loses(
+        brain: &dyn TicTacToeBrain,
+        board: &Board,
+        brain_player: Player,
+    ) {
+        if board.get_next_player() == brain_player {
+            let mut next_board = board.clone();
+            let player_move = brain.make_move(board).unwrap();
+            match next_board.make_move(player_move).unwrap() {
+                BoardState::Ended(winner) => assert_ne!(winner, Some(brain_player.opponent())),
+                BoardState::Ongoing => assert_never_loses(brain, &next_board, brain_player),
+            }
+            return;
+        }
+
+        for position in 1..=board.get_number_of_slots() {
+            if !board.is_slot_empty(position) {
+                continue;
+            }
+
+            let mut next_board = board.clone();
+            let player_move = Move::create(position, board.get_next_player()).unwrap();
+            match next_board.make_move(player_move).unwrap() {
+                BoardState::Ended(winner) => assert_ne!(winner, Some(brain_player.opponent())),
+                BoardState::Ongoing => assert_never_loses(brain, &next_board, brain_player),
+            }
+        }
+    }
+
+    #[test]
+    fn test_level_two_never_loses_going_first() {
+        assert_never_loses(&BrainLevelTwo, &Board::new(Player::X), Player::X);
+    }
+
+    #[test]
+    fn test_level_two_never_loses_going_second() {
+        assert_never_loses(&BrainLevelTwo, &Board::new(Player::X), Player::O);
+    }
+
+    #[test]
+    fn test_level_two_takes_winning_move() {
+        let board = Board::from_position_str("XX1/OO1/3 X").unwrap();
+
+        let (player_move, evaluation) = BrainLevelTwo.evaluate(&board).unwrap();
+        assert_eq!(player_move.position(), 3);
+        assert!(evaluation > 0);
+    }
+
+    #[test]
+    fn test_level_two_blocks_opponent() {
+        let board = Board::from_position_str("XX1/1O1/3 O").unwrap();
+
+        let (player_move, _) = BrainLevelTwo.evaluate(&board).unwrap();
+        assert_eq!(player_move.position(), 3);
+    }
+
+    #[test]
+    fn test_level_two_never_loses_misere() {
+        let board = Board::new(Player::X).with_misere(true);
+        assert_never_loses(&BrainLevelTwo, &board, Player::X);
+        assert_never_loses(&BrainLevelTwo, &board, Player::O);
+    }
+
+    #[test]
+    fn test_level_two_avoids_completing_line_in_misere() {
+        let board = Board::from_position_str("XX1/OO1/3 X 3 misere").unwrap();
+
+        let (player_move, _) = BrainLevelTwo.evaluate(&board).unwrap();
+        assert_ne!(player_move.position(), 3);
+    }
+
+    #[test]
+    fn test_level_one_replays_from_seed() {
+        let play_game = |seed| {
+            let brain = BrainLevelOne::new(seed);
+            let mut board = Board::with_size(Player::X, 5, 5, 4).unwrap();
+            while board.get_state() == BoardState::Ongoing {
+                let player_move = brain.make_move(&board).unwrap();
+                board.make_move(player_move).unwrap();
+            }
+            board.history().to_vec()
+        };
+
+        assert_eq!(play_game(5), play_game(5));
+        assert_ne!(play_game(5), play_game(6));
+    }
+
+    #[test]
+    fn test_brains_refuse_finished_games() {
+        let board = Board::from_position_str("XXX/OO1/3 O").unwrap();
+
+        assert_eq!(
+            BrainLevelOne::new(0).make_move(&board),
+            Err(GameError::GameOver)
+        );
+        assert_eq!(BrainLevelTwo.make_move(&board), Err(GameError::GameOver));
+    }
+
+    #[test]
+    fn test_level_two_evaluates_empty_board_as_tie() {
+        let board = Board::new(Player::X);
+        let (_, evaluation) = BrainLevelTwo.evaluate(&board).unwrap();
+        assert_eq!(evaluation, 0);
+    }
+}
@@ -0,0 +1,535 @@
+// alpha_beta.rs
+
+use super::transposition::{Bound, SharedTable, TableEntry, TranspositionTable};
+use super::TicTacToeBrain;
+use crate::error::GameError;
+use crate::tic_tac_toe::{Board, BoardState, Move, Player};
+use std::time::{Duration, Instant};
+
+/// Score of a won game. Wins found sooner score higher, so the score is reduced by the
+/// number of moves it takes to get there.
+const WIN_SCORE: i32 = 1_000_000;
+
+/// Scores further from 0 than this are forced wins or losses. Estimates always stay closer.
+const FORCED_SCORE: i32 = WIN_SCORE / 2;
+
+/// How many positions the table made for each call to `evaluate` can hold, when the brain
+/// has no shared table.
+const LOCAL_TABLE_SIZE: usize = 1 << 16;
+
+/// Boards with more slots than this only consider moves near existing marks.
+const NEARBY_MOVES_THRESHOLD: usize = 25;
+
+/// A brain that searches ahead with alpha-beta pruning.
+///
+/// It searches one move deeper at a time (iterative deepening) until it reaches `max_depth`
+/// or runs out of `time_limit`, and plays the best move from the deepest finished search.
+/// When the search stops before the end of the game, positions are scored by counting the
+/// lines each player can still complete. Promising moves (wins, blocks, the centre and
+/// corners) are searched first so more of the tree can be pruned.
+///
+/// Scores of positions already searched are kept in a transposition table, so positions
+/// reached by a different move order, or that are rotated or reflected copies of each other,
+/// are only searched once. By default each call to `evaluate` starts with an empty table;
+/// `with_table` keeps one between moves, or shares it with other `BrainLevelThree`s.
+///
+/// Unlike `BrainLevelTwo`, it stays responsive on large boards. With enough depth it
+/// plays perfectly on a 3x3 board.
+pub struct BrainLevelThree {
+    max_depth: usize,
+    time_limit: Duration,
+    table: Option<SharedTable>,
+}
+
+impl BrainLevelThree {
+    /// Creates a brain that searches at most `max_depth` moves ahead, and stops starting
+    /// deeper searches once `time_limit` has passed.
+    pub fn new(max_depth: usize, time_limit: Duration) -> Self {
+        BrainLevelThree {
+            max_depth: max_depth.max(1),
+            time_limit,
+            table: None,
+        }
+    }
+
+    /// Makes the brain remember searched positions in `table` instead of starting with an
+    /// empty table on every move.
+    ///
+    /// Only share a table between `BrainLevelThree`s, other brains score positions differently.
+    pub fn with_table(mut self, table: SharedTable) -> Self {
+        self.table = Some(table);
+        self
+    }
+
+    /// Finds the best move for the next player and how good it is.
+    ///
+    /// The evaluation is from the point of view of the player about to move. Forced wins
+    /// are scored close to `1_000_000` and forced losses close to `-1_000_000`. Any other
+    /// score is an estimate: positive numbers favour the player about to move.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok((Move, evaluation))` with the best move found.
+    /// - `Err(GameError::GameOver)` if the game has already ended.
+    pub fn evaluate(&self, board: &Board) -> Result<(Move, i32), GameError> {
+        if board.get_state() != BoardState::Ongoing {
+            return Err(GameError::GameOver);
+        }
+
+        let mut local_table;
+        let mut shared_table;
+        let table = match &self.table {
+            Some(table) => {
+                // A brain that panicked while holding the lock leaves the table usable.
+                shared_table = table.lock().unwrap_or_else(|err| err.into_inner());
+                &mut *shared_table
+            }
+            None => {
+                local_table = TranspositionTable::new(LOCAL_TABLE_SIZE);
+                &mut local_table
+            }
+        };
+
+        let mut search = Search::new(board, table, Instant::now() + self.time_limit);
+        let player = board.get_next_player();
+        let max_depth = self.max_depth.min(board.get_number_of_open_slots());
+
+        // Depth 1 always finishes so there is a move to play even with no time left.
+        let mut best = search.root(1, None, player);
+        for depth in 2..=max_depth {
+            if best.1.abs() > FORCED_SCORE {
+                // A forced win or loss was found, searching deeper will not change it.
+                break;
+            }
+
+            match search.root(depth, Some(best.0), player) {
+                result if !search.timed_out => best = result,
+                _ => break,
+            }
+        }
+
+        Ok((Move::create(best.0, player)?, best.1))
+    }
+}
+
+impl Default for BrainLevelThree {
+    /// Searches up to 9 moves ahead for at most one second.
+    fn default() -> Self {
+        BrainLevelThree::new(9, Duration::from_secs(1))
+    }
+}
+
+impl TicTacToeBrain for BrainLevelThree {
+    fn make_move(&self, board: &Board) -> Result<Move, GameError> {
+        self.evaluate(board).map(|(player_move, _)| player_move)
+    }
+}
+
+/// The state of one call to `BrainLevelThree::evaluate`.
+struct Search<'a> {
+    board: Board,
+    table: &'a mut TranspositionTable,
+    /// Every group of `win_length` cells in a row, column or diagonal, as positions.
+    windows: Vec<Vec<usize>>,
+    /// For each position, the indexes of the windows it is part of.
+    cell_windows: Vec<Vec<usize>>,
+    deadline: Instant,
+    nodes: u64,
+    timed_out: bool,
+}
+
+impl<'a> Search<'a> {
+    fn new(board: &Board, table: &'a mut TranspositionTable, deadline: Instant) -> Self {
+        let windows = board.lines();
+        let mut cell_windows = vec![Vec::new(); board.get_number_of_slots() + 1];
+        for (index, window) in windows.iter().enumerate() {
+            for &position in window {
+                cell_windows[position].push(index);
+            }
+        }
+
+        Search {
+            board: board.clone(),
+            table,
+            windows,
+            cell_windows,
+            deadline,
+            nodes: 0,
+            timed_out: false,
+        }
+    }
+
+    /// Searches every move for `player` to `depth` and returns the best position and score.
+    ///
+    /// `first` is searched before any other move, so the best move from the previous
+    /// iteration keeps its place if the search runs out of time.
+    fn root(&mut self, depth: usize, first: Option<usize>, player: Player) -> (usize, i32) {
+        let mut moves = self.ordered_moves(player);
+        if let Some(first) = first {
+            if let Some(index) = moves.iter().position(|&position| position == first) {
+                let position = moves.remove(index);
+                moves.insert(0, position);
+            }
+        }
+
+        let mut best = (moves[0], -WIN_SCORE - 1);
+        let mut alpha = -WIN_SCORE - 1;
+        for position in moves {
+            let score = self.score_move(position, player, depth, alpha, WIN_SCORE + 1, 0);
+            if self.timed_out && depth > 1 {
+                break;
+            }
+            if score > best.1 {
+                best = (position, score);
+            }
+            alpha = alpha.max(score);
+        }
+
+        best
+    }
+
+    /// Plays `position` for `player` and returns its score for `player`.
+    fn score_move(
+        &mut self,
+        position: usize,
+        player: Player,
+        depth: usize,
+        alpha: i32,
+        beta: i32,
+        ply: usize,
+    ) -> i32 {
+        let player_move = Move::create(position, player).expect("Positions start at 1");
+        let state = self
+            .board
+            .make_move(player_move)
+            .expect("Searched moves are always legal");
+
+        let score = match state {
+            BoardState::Ended(Some(winner)) if winner == player => WIN_SCORE - (ply as i32 + 1),
+            // Under the misère rule, completing a line loses.
+            BoardState::Ended(Some(_)) => -WIN_SCORE + (ply as i32 + 1),
+            BoardState::Ended(None) => 0,
+            BoardState::Ongoing if depth <= 1 => -self.static_score(player.opponent()),
+            BoardState::Ongoing => {
+                -self.negamax(player.opponent(), depth - 1, -beta, -alpha, ply + 1)
+            }
+        };
+
+        self.board.undo();
+        score
+    }
+
+    /// Returns the best score `player` can reach searching `depth` moves ahead.
+    fn negamax(
+        &mut self,
+        player: Player,
+        depth: usize,
+        mut alpha: i32,
+        mut beta: i32,
+        ply: usize,
+    ) -> i32 {
+        self.nodes += 1;
+        if self.nodes.is_multiple_of(1024) && Instant::now() >= self.deadline {
+            self.timed_out = true;
+        }
+        if self.timed_out {
+            return 0;
+        }
+
+        let hash = self.board.canonical_hash();
+        if let Some(entry) = self.table.get(hash).filter(|entry| entry.depth >= depth) {
+            let score = from_table_score(entry.score, ply);
+            match entry.bound {
+                Bound::Exact => return score,
+                Bound::Lower => alpha = alpha.max(score),
+                Bound::Upper => beta = beta.min(score),
+            }
+            if alpha >= beta {
+                return score;
+            }
+        }
+
+        let original_alpha = alpha;
+        let mut best = -WIN_SCORE - 1;
+        for position in self.ordered_moves(player) {
+            let score = self.score_move(position, player, depth, alpha, beta, ply);
+            best = best.max(score);
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        if !self.timed_out {
+            let bound = if best <= original_alpha {
+                Bound::Upper
+            } else if best >= beta {
+                Bound::Lower
+            } else {
+                Bound::Exact
+            };
+            self.table.insert(TableEntry {
+                hash,
+                depth,
+                score: to_table_score(best, ply),
+                bound,
+            });
+        }
+
+        best
+    }
+
+    /// Estimates how good the board is for `player` by counting the windows each player
+    /// could still complete. Windows closer to being complete count for much more.
+    ///
+    /// Under the misère rule, the windows a player could complete are a danger to them, so
+    /// the score is turned around.
+    fn static_score(&self, player: Player) -> i32 {
+        let mut score = 0;
+        for window in &self.windows {
+            let (mine, theirs) = self.count_window(window, player);
+            if theirs == 0 && mine > 0 {
+                score += window_value(mine);
+            } else if mine == 0 && theirs > 0 {
+                score -= window_value(theirs);
+            }
+        }
+
+        if self.board.is_misere() {
+            score = -score;
+        }
+        score.clamp(-FORCED_SCORE, FORCED_SCORE)
+    }
+
+    /// Returns how many of the window's cells hold `player`'s marks and the opponent's marks.
+    fn count_window(&self, window: &[usize], player: Player) -> (usize, usize) {
+        window.iter().fold((0, 0), |(mine, theirs), &position| {
+            match self.board.get_player_at(position) {
+                Some(mark) if mark == player => (mine + 1, theirs),
+                Some(_) => (mine, theirs + 1),
+                None => (mine, theirs),
+            }
+        })
+    }
+
+    /// Returns the moves worth searching for `player`, most promising first.
+    ///
+    /// Moves that win come first, then moves that stop the opponent winning, then moves
+    /// that build or block the most lines, preferring the centre and corners on ties.
+    /// Under the misère rule the lines count the other way, so moves that complete a line
+    /// come last.
+    fn ordered_moves(&self, player: Player) -> Vec<usize> {
+        let board = &self.board;
+        let win_length = board.win_length();
+        let empty: Vec<usize> = (1..=board.get_number_of_slots())
+            .filter(|&position| board.is_slot_empty(position))
+            .collect();
+        let nearby: Vec<usize> = empty
+            .iter()
+            .copied()
+            .filter(|&position| self.has_nearby_mark(position, 2))
+            .collect();
+        // Far away moves are only skipped on large boards, and only while there are nearby ones.
+        let candidates =
+            if board.get_number_of_slots() > NEARBY_MOVES_THRESHOLD && !nearby.is_empty() {
+                nearby
+            } else {
+                empty
+            };
+
+        let mut moves: Vec<(i64, usize)> = candidates
+            .into_iter()
+            .map(|position| {
+                let mut lines: i64 = 0;
+                for &window in &self.cell_windows[position] {
+                    let (mine, theirs) = self.count_window(&self.windows[window], player);
+                    if theirs == 0 {
+                        if mine + 1 == win_length {
+                            lines += 1 << 40;
+                        }
+                        lines += window_value(mine + 1) as i64;
+                    }
+                    if mine == 0 {
+                        if theirs + 1 == win_length {
+                            lines += 1 << 30;
+                        }
+                        lines += window_value(theirs) as i64;
+                    }
+                }
+                let mut priority = if board.is_misere() { -lines } else { lines };
+
+                // Prefer the centre, then corners.
+                let (layer, row, col) = board
+                    .coordinates(position)
+                    .expect("Candidate moves are on the board");
+                let sides = [
+                    (layer, board.layers()),
+                    (row, board.height()),
+                    (col, board.width()),
+                ];
+                let distance: usize = sides
+                    .iter()
+                    .map(|&(coordinate, size)| (2 * coordinate).abs_diff(size + 1))
+                    .sum();
+                let corner = sides
+                    .iter()
+                    .all(|&(coordinate, size)| coordinate == 1 || coordinate == size);
+                priority -= distance as i64;
+                if corner {
+                    priority += 1;
+                }
+
+                (priority, position)
+            })
+            .collect();
+
+        moves.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        moves.into_iter().map(|(_, position)| position).collect()
+    }
+
+    /// Returns true if any cell within `distance` layers, rows and columns of `position`
+    /// holds a mark.
+    fn has_nearby_mark(&self, position: usize, distance: usize) -> bool {
+        let board = &self.board;
+        let (layer, row, col) = board
+            .coordinates(position)
+            .expect("Candidate moves are on the board");
+        let near = |coordinate: usize| coordinate.saturating_sub(distance)..=coordinate + distance;
+
+        near(layer).any(|nearby_layer| {
+            near(row).any(|nearby_row| {
+                near(col).any(|nearby_col| {
+                    board
+                        .position_at(nearby_layer, nearby_row, nearby_col)
+                        .is_some_and(|nearby| board.get_player_at(nearby).is_some())
+                })
+            })
+        })
+    }
+}
+
+/// Converts a score counted from the start of the search into one counted from the
+/// position `ply` moves in, so it can be stored and found again at any depth.
+fn to_table_score(score: i32, ply: usize) -> i32 {
+    match score {
+        score if score > FORCED_SCORE => score + ply as i32,
+        score if score < -FORCED_SCORE => score - ply as i32,
+        score => score,
+    }
+}
+
+/// Undoes `to_table_score` for a position `ply` moves into the search.
+fn from_table_score(score: i32, ply: usize) -> i32 {
+    match score {
+        score if score > FORCED_SCORE => score - ply as i32,
+        score if score < -FORCED_SCORE => score + ply as i32,
+        score => score,
+    }
+}
+
+/// Returns how much a window holding `marks` of one player's marks and none of the
+/// opponent's is worth.
+fn window_value(marks: usize) -> i32 {
+    match marks {
+        0 => 0,
+        marks => 4_i32.saturating_pow(marks as u32 - 1).min(WIN_SCORE / 1000),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::brains::tests::assert_never_loses;
+
+    #[test]
+    fn test_level_three_never_loses_on_3x3() {
+        let brain = BrainLevelThree::default();
+        assert_never_loses(&brain, &Board::new(Player::X), Player::X);
+        assert_never_loses(&brain, &Board::new(Player::X), Player::O);
+    }
+
+    #[test]
+    fn test_level_three_never_loses_misere() {
+        let brain = BrainLevelThree::default();
+        let board = Board::new(Player::X).with_misere(true);
+        assert_never_loses(&brain, &board, Player::X);
+        assert_never_loses(&brain, &board, Player::O);
+    }
+
+    #[test]
+    fn test_level_three_avoids_completing_line_in_misere() {
+        let board = Board::from_position_str("X4/X4/X4/5/OOO2 X 4 misere").unwrap();
+        let brain = BrainLevelThree::new(4, Duration::from_secs(5));
+
+        let (player_move, evaluation) = brain.evaluate(&board).unwrap();
+        assert_ne!(player_move.position(), 16);
+        assert!(evaluation > -FORCED_SCORE);
+    }
+
+    #[test]
+    fn test_level_three_with_shared_table() {
+        let table = TranspositionTable::shared(1 << 12);
+        let brain = BrainLevelThree::default().with_table(table.clone());
+        assert_never_loses(&brain, &Board::new(Player::X), Player::O);
+        assert!(!table.lock().unwrap().is_empty());
+
+        // A second brain using the filled table still finds the win.
+        let board = Board::from_position_str("X4/X4/X4/5/OOO2 X 4").unwrap();
+        let other = BrainLevelThree::default().with_table(table);
+        assert_eq!(other.make_move(&board).unwrap().position(), 16);
+    }
+
+    #[test]
+    fn test_level_three_plays_qubic() {
+        // X has three of the four cells on the diagonal through the cube.
+        let board = Board::from_position_str("XOO1/O3/4/4|4/1X2/4/4|4/4/2X1/4|4/4/4/4 X").unwrap();
+        let brain = BrainLevelThree::new(4, Duration::from_secs(5));
+        assert_eq!(brain.make_move(&board).unwrap().position(), 64);
+
+        // O has to block it.
+        let board = Board::from_position_str("XOO1/4/4/4|4/1X2/4/4|4/4/2X1/4|4/4/4/4 O").unwrap();
+        assert_eq!(brain.make_move(&board).unwrap().position(), 64);
+    }
+
+    #[test]
+    fn test_level_three_takes_win_on_large_board() {
+        let board =
+            Board::from_position_str("O14/15/15/15/15/5XXXX6/15/5OOO7/15/15/15/15/15/15/15 X 5")
+                .unwrap();
+        let brain = BrainLevelThree::new(4, Duration::from_secs(5));
+
+        let (player_move, evaluation) = brain.evaluate(&board).unwrap();
+        assert!(player_move.position() == 80 || player_move.position() == 85);
+        assert_eq!(evaluation, WIN_SCORE - 1);
+    }
+
+    #[test]
+    fn test_level_three_blocks_open_three() {
+        // O must stop X making an open four on row 2.
+        let board = Board::from_position_str("7/7/2XXX2/7/3O3/1O5/7 O 5").unwrap();
+        let brain = BrainLevelThree::new(3, Duration::from_secs(5));
+
+        let player_move = brain.make_move(&board).unwrap();
+        assert!(player_move.position() == 16 || player_move.position() == 20);
+    }
+
+    #[test]
+    fn test_level_three_wins_when_it_can() {
+        let board = Board::from_position_str("X4/X4/X4/5/OOO2 X 4").unwrap();
+        let brain = BrainLevelThree::default();
+
+        let (player_move, evaluation) = brain.evaluate(&board).unwrap();
+        assert_eq!(player_move.position(), 16);
+        assert_eq!(evaluation, WIN_SCORE - 1);
+    }
+
+    #[test]
+    fn test_level_three_respects_time_limit() {
+        let board = Board::with_size(Player::X, 15, 15, 5).unwrap();
+        let brain = BrainLevelThree::new(20, Duration::from_millis(200));
+
+        let start = Instant::now();
+        brain.make_move(&board).unwrap();
+        assert!(start.elapsed() < Duration::from_secs(3));
+    }
+}
@@ -0,0 +1,326 @@
+// monte_carlo.rs
+
+use super::TicTacToeBrain;
+use crate::error::GameError;
+use crate::random::{random_seed, seeded_rng, GameRng};
+use crate::tic_tac_toe::{Board, BoardState, Move, Player};
+use rand::Rng;
+use std::sync::Mutex;
+
+/// A brain that uses Monte Carlo tree search (UCT).
+///
+/// Each iteration walks down a tree of moves, picking the move that balances how well it has
+/// done so far against how little it has been tried, adds one new move to the tree, and
+/// finishes the game with random moves like `BrainLevelOne`. The result is counted for every
+/// move on the way. After all iterations the most visited move is played.
+///
+/// It needs no knowledge of the game apart from the rules, so it works on any board size.
+/// More iterations play better but take longer.
+pub struct BrainMonteCarlo {
+    iterations: usize,
+    exploration: f64,
+    rng: Mutex<GameRng>,
+}
+
+/// What the search found out about one move.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct MoveStats {
+    pub player_move: Move,
+    /// How many iterations started with this move.
+    pub visits: u32,
+    /// How many of those iterations the player making the move won. Ties count as half a win.
+    pub wins: f64,
+}
+
+impl MoveStats {
+    /// Returns the share of iterations through this move that the player making it won,
+    /// from 0.0 to 1.0. Ties count as half a win.
+    pub fn win_rate(&self) -> f64 {
+        if self.visits == 0 {
+            0.0
+        } else {
+            self.wins / self.visits as f64
+        }
+    }
+}
+
+/// One move in the search tree.
+struct Node {
+    /// The position played to reach this node, or 0 for the root.
+    position: usize,
+    /// The player who made the move, whose wins are counted here.
+    player: Player,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    /// Positions not yet added as children.
+    untried: Vec<usize>,
+    visits: u32,
+    wins: f64,
+}
+
+impl BrainMonteCarlo {
+    /// Creates a brain that runs `iterations` iterations per move (at least one).
+    ///
+    /// # Parameters
+    ///
+    /// - `iterations`: How many games to play out for each move.
+    /// - `exploration`: How much to favour moves that have been tried less. `2f64.sqrt()` is
+    ///   the usual choice; 0 only follows the moves that have done best so far.
+    /// - `seed`: Seed for the random moves. Brains with the same seed play the same moves.
+    pub fn new(iterations: usize, exploration: f64, seed: u64) -> Self {
+        BrainMonteCarlo {
+            iterations: iterations.max(1),
+            exploration,
+            rng: Mutex::new(seeded_rng(seed)),
+        }
+    }
+
+    /// Makes the brain use `rng` for its random moves instead of the one created from the seed.
+    pub fn with_rng(mut self, rng: GameRng) -> Self {
+        self.rng = Mutex::new(rng);
+        self
+    }
+
+    /// Runs the search from `board` and returns what it found about each move for the next
+    /// player, most visited first.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(Vec<MoveStats>)` with one entry for every empty slot that was tried.
+    /// - `Err(GameError::GameOver)` if the game has already ended.
+    pub fn analyse(&self, board: &Board) -> Result<Vec<MoveStats>, GameError> {
+        if board.get_state() != BoardState::Ongoing {
+            return Err(GameError::GameOver);
+        }
+
+        let mut rng = self.rng.lock().unwrap_or_else(|err| err.into_inner());
+        let mut board = board.clone();
+        let mut tree = vec![Node {
+            position: 0,
+            // The root's counts are not used, it only needs a player.
+            player: board.get_next_player().opponent(),
+            parent: None,
+            children: Vec::new(),
+            untried: untried_positions(&board),
+            visits: 0,
+            wins: 0.0,
+        }];
+
+        for _ in 0..self.iterations {
+            let mut node = 0;
+            let mut moves_played = 0;
+
+            // Selection: follow the best child until a node has untried moves.
+            while tree[node].untried.is_empty() && !tree[node].children.is_empty() {
+                node = self.select_child(&tree, node);
+                play(&mut board, tree[node].position);
+                moves_played += 1;
+            }
+
+            // Expansion: add one untried move, unless the game is over here.
+            if board.get_state() == BoardState::Ongoing && !tree[node].untried.is_empty() {
+                let index = rng.gen_range(0..tree[node].untried.len());
+                let position = tree[node].untried.swap_remove(index);
+                let player = board.get_next_player();
+                play(&mut board, position);
+                moves_played += 1;
+
+                tree.push(Node {
+                    position,
+                    player,
+                    parent: Some(node),
+                    children: Vec::new(),
+                    untried: untried_positions(&board),
+                    visits: 0,
+                    wins: 0.0,
+                });
+                let child = tree.len() - 1;
+                tree[node].children.push(child);
+                node = child;
+            }
+
+            // Simulation: finish the game with random moves.
+            let mut empty = empty_positions(&board);
+            while board.get_state() == BoardState::Ongoing {
+                let position = empty.swap_remove(rng.gen_range(0..empty.len()));
+                play(&mut board, position);
+                moves_played += 1;
+            }
+            let winner = match board.get_state() {
+                BoardState::Ended(winner) => winner,
+                BoardState::Ongoing => unreachable!("The playout runs until the game ends"),
+            };
+
+            // Backpropagation: count the result for every move on the way.
+            let mut current = Some(node);
+            while let Some(index) = current {
+                let node = &mut tree[index];
+                node.visits += 1;
+                node.wins += match winner {
+                    Some(winner) if winner == node.player => 1.0,
+                    Some(_) => 0.0,
+                    None => 0.5,
+                };
+                current = node.parent;
+            }
+
+            for _ in 0..moves_played {
+                board.undo();
+            }
+        }
+
+        let player = board.get_next_player();
+        let mut stats = tree[0]
+            .children
+            .iter()
+            .map(|&child| {
+                Ok(MoveStats {
+                    player_move: Move::create(tree[child].position, player)?,
+                    visits: tree[child].visits,
+                    wins: tree[child].wins,
+                })
+            })
+            .collect::<Result<Vec<MoveStats>, GameError>>()?;
+        stats.sort_by(|a, b| {
+            b.visits
+                .cmp(&a.visits)
+                .then(b.wins.total_cmp(&a.wins))
+                .then(a.player_move.position().cmp(&b.player_move.position()))
+        });
+
+        Ok(stats)
+    }
+
+    /// Returns the child of `node` with the highest UCT score.
+    fn select_child(&self, tree: &[Node], node: usize) -> usize {
+        let parent_visits = (tree[node].visits.max(1) as f64).ln();
+        let uct = |child: usize| {
+            let child = &tree[child];
+            let visits = child.visits as f64;
+            child.wins / visits + self.exploration * (parent_visits / visits).sqrt()
+        };
+
+        *tree[node]
+            .children
+            .iter()
+            .max_by(|&&a, &&b| uct(a).total_cmp(&uct(b)))
+            .expect("Only nodes with children are selected from")
+    }
+}
+
+impl Default for BrainMonteCarlo {
+    /// Runs 10,000 iterations per move with the usual exploration constant, seeded randomly.
+    fn default() -> Self {
+        BrainMonteCarlo::new(10_000, 2f64.sqrt(), random_seed())
+    }
+}
+
+impl TicTacToeBrain for BrainMonteCarlo {
+    fn make_move(&self, board: &Board) -> Result<Move, GameError> {
+        self.analyse(board)?
+            .first()
+            .map(|stats| stats.player_move)
+            .ok_or_else(|| {
+                GameError::BrainFailure("No available positions for AI to play".to_string())
+            })
+    }
+}
+
+/// Returns the empty positions on the board.
+fn empty_positions(board: &Board) -> Vec<usize> {
+    (1..=board.get_number_of_slots())
+        .filter(|&position| board.is_slot_empty(position))
+        .collect()
+}
+
+/// Returns the moves that can be played from the board, none if the game has ended.
+fn untried_positions(board: &Board) -> Vec<usize> {
+    match board.get_state() {
+        BoardState::Ongoing => empty_positions(board),
+        BoardState::Ended(_) => Vec::new(),
+    }
+}
+
+/// Plays `position` for the next player during the search.
+fn play(board: &mut Board, position: usize) {
+    let player_move =
+        Move::create(position, board.get_next_player()).expect("Positions start at 1");
+    board
+        .make_move(player_move)
+        .expect("Searched moves are always legal");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_monte_carlo_takes_winning_move() {
+        let board = Board::from_position_str("XX1/OO1/3 X").unwrap();
+        let brain = BrainMonteCarlo::new(2_000, 2f64.sqrt(), 1);
+
+        assert_eq!(brain.make_move(&board).unwrap().position(), 3);
+    }
+
+    #[test]
+    fn test_monte_carlo_blocks_opponent() {
+        let board = Board::from_position_str("XX1/1O1/3 O").unwrap();
+        let brain = BrainMonteCarlo::new(2_000, 2f64.sqrt(), 1);
+
+        assert_eq!(brain.make_move(&board).unwrap().position(), 3);
+    }
+
+    #[test]
+    fn test_monte_carlo_avoids_completing_line_in_misere() {
+        let board = Board::from_position_str("XX1/OO1/3 X 3 misere").unwrap();
+        let brain = BrainMonteCarlo::new(2_000, 2f64.sqrt(), 1);
+
+        assert_ne!(brain.make_move(&board).unwrap().position(), 3);
+    }
+
+    #[test]
+    fn test_monte_carlo_stats() {
+        let board = Board::new(Player::X);
+        let brain = BrainMonteCarlo::new(1_000, 2f64.sqrt(), 7);
+        let stats = brain.analyse(&board).unwrap();
+
+        assert_eq!(stats.len(), 9);
+        assert_eq!(stats.iter().map(|stats| stats.visits).sum::<u32>(), 1_000);
+        assert!(stats
+            .windows(2)
+            .all(|pair| pair[0].visits >= pair[1].visits));
+        assert!(stats
+            .iter()
+            .all(|stats| (0.0..=1.0).contains(&stats.win_rate())));
+        // The centre is the strongest first move.
+        assert_eq!(stats[0].player_move.position(), 5);
+    }
+
+    #[test]
+    fn test_monte_carlo_same_seed_same_moves() {
+        let board = Board::with_size(Player::X, 6, 6, 4).unwrap();
+        let brain_1 = BrainMonteCarlo::new(300, 1.0, 42);
+        let brain_2 = BrainMonteCarlo::new(300, 1.0, 42);
+
+        for _ in 0..3 {
+            assert_eq!(brain_1.analyse(&board), brain_2.analyse(&board));
+        }
+    }
+
+    #[test]
+    fn test_monte_carlo_on_large_board() {
+        let board = Board::from_position_str("X8/9/9/9/9/9/9/9/9 O 5").unwrap();
+        let brain = BrainMonteCarlo::new(200, 2f64.sqrt(), 3);
+        let player_move = brain.make_move(&board).unwrap();
+
+        assert!(board.is_slot_empty(player_move.position()));
+    }
+
+    #[test]
+    fn test_monte_carlo_refuses_finished_games() {
+        let board = Board::from_position_str("XXX/OO1/3 O").unwrap();
+        let brain = BrainMonteCarlo::new(10, 1.0, 0);
+
+        assert_eq!(brain.make_move(&board), Err(GameError::GameOver));
+    }
+}
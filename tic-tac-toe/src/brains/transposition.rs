@@ -0,0 +1,154 @@
+// transposition.rs
+
+use std::sync::{Arc, Mutex};
+
+/// How a stored score relates to the real score of a position.
+///
+/// Alpha-beta search stops looking at a position as soon as it knows the position will not
+/// be chosen, so it does not always find the exact score.
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub enum Bound {
+    /// The score is exact.
+    Exact,
+    /// The real score is at least this high.
+    Lower,
+    /// The real score is at most this high.
+    Upper,
+}
+
+/// A score remembered for a position.
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub struct TableEntry {
+    /// The hash of the position, used to check the entry is for the right position.
+    pub hash: u64,
+    /// How many moves ahead the search looked. Deeper entries are more reliable.
+    pub depth: usize,
+    /// The score for the player about to move.
+    pub score: i32,
+    /// Whether `score` is exact or only a bound on the real score.
+    pub bound: Bound,
+}
+
+/// Remembers scores of positions that have already been searched.
+///
+/// The same position can be reached by playing moves in a different order, or as a rotated
+/// or reflected copy of another position. Looking positions up by `Board::canonical_hash`
+/// lets a search score each of them only once.
+///
+/// The table has a fixed number of slots. When two positions want the same slot, the one
+/// searched deeper is kept. A table only makes sense for brains that score positions the
+/// same way, so share one between brains of the same kind, e.g. with `SharedTable`.
+#[derive(Debug, Clone)]
+pub struct TranspositionTable {
+    slots: Vec<Option<TableEntry>>,
+    len: usize,
+}
+
+/// A transposition table that several brains can use at once.
+pub type SharedTable = Arc<Mutex<TranspositionTable>>;
+
+impl TranspositionTable {
+    /// Creates an empty table with room for `capacity` positions (at least one).
+    pub fn new(capacity: usize) -> Self {
+        TranspositionTable {
+            slots: vec![None; capacity.max(1)],
+            len: 0,
+        }
+    }
+
+    /// Creates an empty table wrapped so it can be shared between brains and threads.
+    pub fn shared(capacity: usize) -> SharedTable {
+        Arc::new(Mutex::new(TranspositionTable::new(capacity)))
+    }
+
+    /// Returns the entry stored for `hash`, if there is one.
+    pub fn get(&self, hash: u64) -> Option<TableEntry> {
+        self.slots[self.slot_index(hash)].filter(|entry| entry.hash == hash)
+    }
+
+    /// Stores `entry`, unless its slot holds a deeper search of a different position.
+    pub fn insert(&mut self, entry: TableEntry) {
+        let index = self.slot_index(entry.hash);
+        match self.slots[index] {
+            Some(existing) if existing.hash != entry.hash && existing.depth > entry.depth => {}
+            Some(_) => self.slots[index] = Some(entry),
+            None => {
+                self.slots[index] = Some(entry);
+                self.len += 1;
+            }
+        }
+    }
+
+    /// Returns the number of positions stored.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if no positions are stored.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Removes every stored position.
+    pub fn clear(&mut self) {
+        self.slots.iter_mut().for_each(|slot| *slot = None);
+        self.len = 0;
+    }
+
+    fn slot_index(&self, hash: u64) -> usize {
+        (hash % self.slots.len() as u64) as usize
+    }
+}
+
+impl Default for TranspositionTable {
+    /// Creates a table with room for about a quarter of a million positions.
+    fn default() -> Self {
+        TranspositionTable::new(1 << 18)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(hash: u64, depth: usize) -> TableEntry {
+        TableEntry {
+            hash,
+            depth,
+            score: depth as i32,
+            bound: Bound::Exact,
+        }
+    }
+
+    #[test]
+    fn test_insert_and_get() {
+        let mut table = TranspositionTable::new(16);
+        assert!(table.is_empty());
+
+        table.insert(entry(3, 2));
+        assert_eq!(table.get(3), Some(entry(3, 2)));
+        // 19 shares a slot with 3 but is a different position.
+        assert_eq!(table.get(19), None);
+        assert_eq!(table.len(), 1);
+
+        table.clear();
+        assert_eq!(table.get(3), None);
+        assert!(table.is_empty());
+    }
+
+    #[test]
+    fn test_keeps_deeper_entry() {
+        let mut table = TranspositionTable::new(16);
+        table.insert(entry(3, 5));
+        table.insert(entry(19, 2));
+        assert_eq!(table.get(3), Some(entry(3, 5)));
+
+        table.insert(entry(19, 7));
+        assert_eq!(table.get(19), Some(entry(19, 7)));
+
+        // The same position is always replaced, so newer results are used.
+        table.insert(entry(19, 1));
+        assert_eq!(table.get(19), Some(entry(19, 1)));
+        assert_eq!(table.len(), 1);
+    }
+}
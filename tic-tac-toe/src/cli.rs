@@ -0,0 +1,660 @@
+// cli.rs
+
+//! Command line flags for the interactive game.
+//!
+//! Every flag is optional. Anything not given on the command line is asked for interactively.
+use tic_tac_toe::Player;
+
+pub const USAGE: &str = "\
+Usage: tic-tac-toe [OPTIONS] [FILE]
+
+Options:
+  --first X|O          Player who moves first
+  --mode MODE          `computer`, `human` or `watch` (computer against computer)
+  --level N            Computer difficulty (1 to 4), implies `--mode computer`
+  --computer X|O       Side the computer plays, implies `--mode computer`
+  --size N|WxH|WxHxL   Board size, e.g. `4`, `5x5`, or `4x4x4` for 3D Qubic
+  --win-length K       How many in a row are needed to win
+  --misere             Play by the misère rule, where completing a line loses
+  --watch X,O          Watch computer level X play level O, implies `--mode watch`
+  --delay MS           Pause after each move when watching (default 500)
+  --load FILE          Resume a saved game or position string (same as FILE)
+  --tui                Play full screen, moving a cursor with the arrow keys or hjkl
+  --ultimate           Play Ultimate Tic Tac Toe, two players on nine boards
+  --wild               Play Wild Tic Tac Toe, two players who may each place X or O
+  --notakto BOARDS     Play Notakto on BOARDS boards, where both players place X and
+                       whoever kills the last board loses. Use with `--mode`
+  --seed N             Seed for the computer's random choices, to replay a game
+  --match A,B          Play computer level A against level B and print statistics
+  --tournament LEVELS  Play a round robin between computer levels, e.g. `1,2,3,3`
+  --swiss ROUNDS       Pair a `--tournament` Swiss style for ROUNDS rounds instead
+  --games N            Games in a `--match` (default 100), or per pairing in a
+                       `--tournament` (default 10)
+  --records FILE       Write every `--tournament` game to FILE
+  --host ADDRESS       Host a game over the network, e.g. `0.0.0.0:7878`. You play X
+  --join ADDRESS       Join a game hosted at ADDRESS, e.g. `192.168.1.5:7878`. You play O
+  --server ADDRESS     Run a server hosting many games with a lobby. `--level` sets the
+                       level of its computer player (default 3)
+  --lobby ADDRESS      Connect to the lobby of a `--server`
+  --web ADDRESS        Run an HTTP JSON API with WebSocket updates, e.g. `127.0.0.1:8080`
+  -h, --help           Print this help";
+
+/// Who the second player is.
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub enum ModeOption {
+    Computer,
+    Human,
+    /// Two computers play each other while the user watches.
+    Watch,
+}
+
+/// The options given on the command line. `None` means the flag was not given.
+#[derive(Eq, PartialEq, Debug, Default)]
+pub struct Options {
+    pub first_player: Option<Player>,
+    pub mode: Option<ModeOption>,
+    pub level: Option<usize>,
+    pub computer_player: Option<Player>,
+    /// The computer levels playing X and O in watch mode.
+    pub watch: Option<(usize, usize)>,
+    /// Milliseconds to pause after each move in watch mode.
+    pub delay: Option<u64>,
+    /// The width, height and number of layers of the board.
+    pub size: Option<(usize, usize, usize)>,
+    pub win_length: Option<usize>,
+    /// Completing a line loses the game instead of winning it.
+    pub misere: bool,
+    pub load: Option<String>,
+    pub seed: Option<u64>,
+    /// The two computer levels to play against each other.
+    pub bot_match: Option<(usize, usize)>,
+    pub games: Option<usize>,
+    /// The computer levels taking part in a tournament.
+    pub tournament: Option<Vec<usize>>,
+    pub swiss_rounds: Option<usize>,
+    pub records: Option<String>,
+    /// The address to host a network game on.
+    pub host: Option<String>,
+    /// The address of a network game to join.
+    pub join: Option<String>,
+    /// The address to run a lobby server on.
+    pub server: Option<String>,
+    /// The address of a lobby server to connect to.
+    pub lobby: Option<String>,
+    /// The address to serve the HTTP API on.
+    pub web: Option<String>,
+    /// Play in the full-screen terminal UI instead of at a prompt.
+    pub tui: bool,
+    /// Play Ultimate Tic Tac Toe instead of a single board.
+    pub ultimate: bool,
+    /// Play Wild Tic Tac Toe, where each player chooses the mark they place.
+    pub wild: bool,
+    /// Play Notakto on this many boards.
+    pub notakto: Option<usize>,
+    pub help: bool,
+}
+
+/// Parses the command line arguments, not including the program name.
+///
+/// # Returns
+///
+/// - `Ok(Options)` with the flags that were given.
+/// - `Err` with a message if a flag is unknown, is missing its value, or has an invalid value.
+pub fn parse_args<I>(args: I) -> Result<Options, String>
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut options = Options::default();
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        let mut value = |flag: &str| {
+            args.next()
+                .ok_or_else(|| format!("Missing value for {}", flag))
+        };
+
+        match arg.as_str() {
+            "-h" | "--help" => options.help = true,
+            "--tui" => options.tui = true,
+            "--ultimate" => options.ultimate = true,
+            "--misere" => options.misere = true,
+            "--wild" => options.wild = true,
+            "--notakto" => {
+                let boards = value(&arg)?;
+                options.notakto = Some(
+                    boards
+                        .parse()
+                        .map_err(|_| format!("Invalid number of boards `{}`", boards))?,
+                );
+            }
+            "--first" => options.first_player = Some(parse_player(&value(&arg)?)?),
+            "--mode" => {
+                options.mode = match value(&arg)?.to_ascii_lowercase().as_str() {
+                    "computer" => Some(ModeOption::Computer),
+                    "human" => Some(ModeOption::Human),
+                    "watch" => Some(ModeOption::Watch),
+                    other => return Err(format!("Invalid mode `{}`", other)),
+                }
+            }
+            "--level" => {
+                let level = value(&arg)?;
+                options.level = Some(
+                    level
+                        .parse()
+                        .map_err(|_| format!("Invalid level `{}`", level))?,
+                );
+            }
+            "--computer" => options.computer_player = Some(parse_player(&value(&arg)?)?),
+            "--size" => options.size = Some(parse_size(&value(&arg)?)?),
+            "--win-length" => {
+                let win_length = value(&arg)?;
+                options.win_length = Some(
+                    win_length
+                        .parse()
+                        .map_err(|_| format!("Invalid win length `{}`", win_length))?,
+                );
+            }
+            "--watch" => options.watch = Some(parse_levels(&value(&arg)?)?),
+            "--delay" => {
+                let delay = value(&arg)?;
+                options.delay = Some(
+                    delay
+                        .parse()
+                        .map_err(|_| format!("Invalid delay `{}`", delay))?,
+                );
+            }
+            "--load" => options.load = Some(value(&arg)?),
+            "--seed" => {
+                let seed = value(&arg)?;
+                options.seed = Some(
+                    seed.parse()
+                        .map_err(|_| format!("Invalid seed `{}`", seed))?,
+                );
+            }
+            "--match" => options.bot_match = Some(parse_levels(&value(&arg)?)?),
+            "--tournament" => {
+                let levels = value(&arg)?;
+                options.tournament = Some(
+                    levels
+                        .split(',')
+                        .map(|level| level.trim().parse())
+                        .collect::<Result<Vec<usize>, _>>()
+                        .map_err(|_| {
+                            format!("Invalid levels `{}`, expected e.g. `1,2,3`", levels)
+                        })?,
+                );
+            }
+            "--swiss" => {
+                let rounds = value(&arg)?;
+                options.swiss_rounds = Some(
+                    rounds
+                        .parse()
+                        .map_err(|_| format!("Invalid number of rounds `{}`", rounds))?,
+                );
+            }
+            "--records" => options.records = Some(value(&arg)?),
+            "--host" => options.host = Some(value(&arg)?),
+            "--join" => options.join = Some(value(&arg)?),
+            "--server" => options.server = Some(value(&arg)?),
+            "--lobby" => options.lobby = Some(value(&arg)?),
+            "--web" => options.web = Some(value(&arg)?),
+            "--games" => {
+                let games = value(&arg)?;
+                options.games = Some(
+                    games
+                        .parse()
+                        .map_err(|_| format!("Invalid number of games `{}`", games))?,
+                );
+            }
+            flag if flag.starts_with('-') => return Err(format!("Unknown option `{}`", flag)),
+            _ if options.load.is_none() => options.load = Some(arg),
+            _ => return Err(format!("Unexpected argument `{}`", arg)),
+        }
+    }
+
+    // The server only takes the level of its computer player and a seed, and the lobby
+    // client takes nothing else at all.
+    if let Some(server) = &options.server {
+        let server_options = Options {
+            server: Some(server.clone()),
+            level: options.level,
+            seed: options.seed,
+            help: options.help,
+            ..Options::default()
+        };
+        if options != server_options {
+            return Err("--server can only be used with --level and --seed".to_string());
+        }
+        return Ok(options);
+    }
+
+    if let Some(web) = &options.web {
+        let web_options = Options {
+            web: Some(web.clone()),
+            seed: options.seed,
+            help: options.help,
+            ..Options::default()
+        };
+        if options != web_options {
+            return Err("--web can only be used with --seed".to_string());
+        }
+        return Ok(options);
+    }
+
+    if let Some(lobby) = &options.lobby {
+        let lobby_options = Options {
+            lobby: Some(lobby.clone()),
+            help: options.help,
+            ..Options::default()
+        };
+        if options != lobby_options {
+            return Err("--lobby cannot be used with other options".to_string());
+        }
+        return Ok(options);
+    }
+
+    // Ultimate Tic Tac Toe is always played by two people on its own boards.
+    if options.ultimate {
+        let ultimate_options = Options {
+            ultimate: true,
+            first_player: options.first_player,
+            help: options.help,
+            ..Options::default()
+        };
+        if options != ultimate_options {
+            return Err("--ultimate can only be used with --first".to_string());
+        }
+        return Ok(options);
+    }
+
+    // Wild Tic Tac Toe is played by two people, and either of them can place either mark, so
+    // there is no first mark to choose.
+    if options.wild {
+        let wild_options = Options {
+            wild: true,
+            size: options.size,
+            win_length: options.win_length,
+            help: options.help,
+            ..Options::default()
+        };
+        if options != wild_options {
+            return Err("--wild can only be used with --size and --win-length".to_string());
+        }
+        if options.size.is_some_and(|(_, _, layers)| layers > 1) {
+            return Err("--wild cannot be used with boards with layers".to_string());
+        }
+        return Ok(options);
+    }
+
+    // Notakto has its own computer player, and both players place X, so there is no level or
+    // side to choose.
+    if let Some(boards) = options.notakto {
+        let notakto_options = Options {
+            notakto: Some(boards),
+            mode: options.mode,
+            seed: options.seed,
+            help: options.help,
+            ..Options::default()
+        };
+        if options != notakto_options {
+            return Err("--notakto can only be used with --mode and --seed".to_string());
+        }
+        if options.mode == Some(ModeOption::Watch) {
+            return Err("--notakto cannot be used with `--mode watch`".to_string());
+        }
+        return Ok(options);
+    }
+
+    if options
+        .mode
+        .is_some_and(|mode| mode != ModeOption::Computer)
+        && (options.level.is_some() || options.computer_player.is_some())
+    {
+        return Err("--level and --computer can only be used with `--mode computer`".to_string());
+    }
+
+    if options.level.is_some() || options.computer_player.is_some() {
+        options.mode = Some(ModeOption::Computer);
+    }
+
+    if options.watch.is_some() {
+        if options.mode.is_some_and(|mode| mode != ModeOption::Watch) {
+            return Err("--watch can only be used with `--mode watch`".to_string());
+        }
+        options.mode = Some(ModeOption::Watch);
+    }
+
+    if options.delay.is_some() && options.mode != Some(ModeOption::Watch) {
+        return Err("--delay can only be used with `--mode watch`".to_string());
+    }
+
+    let computer_only = options.bot_match.is_some() || options.tournament.is_some();
+    if computer_only
+        && (options.mode.is_some() || options.level.is_some() || options.computer_player.is_some())
+    {
+        return Err(
+            "--mode, --level and --computer cannot be used with --match or --tournament"
+                .to_string(),
+        );
+    }
+
+    if options.tui && (computer_only || options.host.is_some() || options.join.is_some()) {
+        return Err(
+            "--tui cannot be used with --match, --tournament, --host or --join".to_string(),
+        );
+    }
+
+    // The full-screen UI only has a cursor for flat boards.
+    if options.tui && options.size.is_some_and(|(_, _, layers)| layers > 1) {
+        return Err("--tui cannot be used with boards with layers".to_string());
+    }
+
+    if options.bot_match.is_some() && options.tournament.is_some() {
+        return Err("--match and --tournament cannot be used together".to_string());
+    }
+
+    if options.games.is_some() && !computer_only {
+        return Err("--games can only be used with --match or --tournament".to_string());
+    }
+
+    if (options.swiss_rounds.is_some() || options.records.is_some()) && options.tournament.is_none()
+    {
+        return Err("--swiss and --records can only be used with --tournament".to_string());
+    }
+
+    let network = options.host.is_some() || options.join.is_some();
+    if network && (computer_only || options.mode.is_some() || options.seed.is_some()) {
+        return Err("--host and --join cannot be used with computer players or --seed".to_string());
+    }
+
+    if options.host.is_some() && options.join.is_some() {
+        return Err("--host and --join cannot be used together".to_string());
+    }
+
+    // The host chooses the board, and the joining player gets it when they connect.
+    if options.join.is_some()
+        && (options.load.is_some()
+            || options.first_player.is_some()
+            || options.size.is_some()
+            || options.win_length.is_some()
+            || options.misere)
+    {
+        return Err(
+            "--first, --size, --win-length, --misere and saved games cannot be used with --join"
+                .to_string(),
+        );
+    }
+
+    if options.load.is_some()
+        && (options.first_player.is_some()
+            || options.size.is_some()
+            || options.win_length.is_some()
+            || options.misere)
+    {
+        return Err(
+            "--first, --size, --win-length and --misere cannot be used with a saved game"
+                .to_string(),
+        );
+    }
+
+    Ok(options)
+}
+
+/// Parses a player written as `X` or `O`.
+fn parse_player(value: &str) -> Result<Player, String> {
+    let mut chars = value.chars();
+    match (
+        chars.next().and_then(Player::get_player_enum_from_char),
+        chars.next(),
+    ) {
+        (Some(player), None) => Ok(player),
+        _ => Err(format!("Invalid player `{}`, choose X or O", value)),
+    }
+}
+
+/// Parses two computer levels written as `A,B`.
+fn parse_levels(value: &str) -> Result<(usize, usize), String> {
+    let error = || format!("Invalid levels `{}`, expected e.g. `1,2`", value);
+
+    match value.split_once(',') {
+        Some((a, b)) => match (a.trim().parse(), b.trim().parse()) {
+            (Ok(a), Ok(b)) => Ok((a, b)),
+            _ => Err(error()),
+        },
+        None => Err(error()),
+    }
+}
+
+/// Parses a board size written as `N`, `WIDTHxHEIGHT` or `WIDTHxHEIGHTxLAYERS`.
+///
+/// The board size prompt uses it too, so it accepts the same sizes as `--size`.
+pub fn parse_size(value: &str) -> Result<(usize, usize, usize), String> {
+    let error = || format!("Invalid board size `{}`", value);
+    let sides: Vec<usize> = value
+        .to_ascii_lowercase()
+        .split('x')
+        .map(|side| side.trim().parse())
+        .collect::<Result<_, _>>()
+        .map_err(|_| error())?;
+
+    match sides[..] {
+        [size] => Ok((size, size, 1)),
+        [width, height] => Ok((width, height, 1)),
+        [width, height, layers] => Ok((width, height, layers)),
+        _ => Err(error()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_no_flags() {
+        assert_eq!(parse(&[]), Ok(Options::default()));
+    }
+
+    #[test]
+    fn test_all_flags() {
+        let options = parse(&[
+            "--first",
+            "o",
+            "--level",
+            "2",
+            "--computer",
+            "X",
+            "--size",
+            "5x4",
+            "--win-length",
+            "4",
+            "--seed",
+            "123",
+        ])
+        .unwrap();
+
+        assert_eq!(options.first_player, Some(Player::O));
+        assert_eq!(options.mode, Some(ModeOption::Computer));
+        assert_eq!(options.level, Some(2));
+        assert_eq!(options.computer_player, Some(Player::X));
+        assert_eq!(options.size, Some((5, 4, 1)));
+        assert_eq!(options.win_length, Some(4));
+        assert_eq!(options.seed, Some(123));
+    }
+
+    #[test]
+    fn test_square_size_and_load() {
+        let options = parse(&["--mode", "human", "--size", "4"]).unwrap();
+        assert_eq!(options.mode, Some(ModeOption::Human));
+        assert_eq!(options.size, Some((4, 4, 1)));
+        let options = parse(&["--level", "3", "--size", "4x4x4"]).unwrap();
+        assert_eq!(options.size, Some((4, 4, 4)));
+        assert!(parse(&["--size", "4x4x4x4"]).is_err());
+        assert_eq!(parse_size("5 x 4"), Ok((5, 4, 1)));
+
+        assert_eq!(
+            parse(&["game.txt"]).unwrap().load,
+            Some("game.txt".to_string())
+        );
+        assert_eq!(
+            parse(&["--load", "game.txt"]).unwrap().load,
+            Some("game.txt".to_string())
+        );
+    }
+
+    #[test]
+    fn test_network_flags() {
+        let options = parse(&["--host", "127.0.0.1:7878", "--size", "4"]).unwrap();
+        assert_eq!(options.host, Some("127.0.0.1:7878".to_string()));
+        assert_eq!(options.mode, None);
+        assert_eq!(
+            parse(&["--join", "localhost:7878"]).unwrap().join,
+            Some("localhost:7878".to_string())
+        );
+
+        assert!(parse(&["--host", "a:1", "--join", "b:1"]).is_err());
+        assert!(parse(&["--host", "a:1", "--level", "2"]).is_err());
+        assert!(parse(&["--join", "a:1", "--size", "4"]).is_err());
+        assert!(parse(&["--join", "a:1", "game.txt"]).is_err());
+    }
+
+    #[test]
+    fn test_server_flags() {
+        let options = parse(&["--server", "0.0.0.0:7878", "--level", "3"]).unwrap();
+        assert_eq!(options.server, Some("0.0.0.0:7878".to_string()));
+        assert_eq!(options.level, Some(3));
+        assert_eq!(options.mode, None);
+        assert!(parse(&["--lobby", "localhost:7878"]).is_ok());
+
+        assert!(parse(&["--server", "a:1", "--size", "4"]).is_err());
+        assert!(parse(&["--server", "a:1", "--lobby", "a:1"]).is_err());
+        assert!(parse(&["--lobby", "a:1", "--level", "2"]).is_err());
+        assert!(parse(&["--web", "a:1", "--seed", "4"]).is_ok());
+        assert!(parse(&["--web", "a:1", "--server", "b:1"]).is_err());
+        assert!(parse(&["--web", "a:1", "--tui"]).is_err());
+    }
+
+    #[test]
+    fn test_misere_flag() {
+        let options = parse(&["--misere", "--level", "3", "--size", "4"]).unwrap();
+        assert!(options.misere);
+        assert!(parse(&["--misere", "--match", "2,3"]).is_ok());
+        assert!(parse(&["--misere", "game.txt"]).is_err());
+        assert!(parse(&["--misere", "--join", "a:1"]).is_err());
+        assert!(parse(&["--misere", "--ultimate"]).is_err());
+    }
+
+    #[test]
+    fn test_ultimate_flag() {
+        let options = parse(&["--ultimate", "--first", "o"]).unwrap();
+        assert!(options.ultimate);
+        assert_eq!(options.first_player, Some(Player::O));
+
+        assert!(parse(&["--ultimate", "--level", "2"]).is_err());
+        assert!(parse(&["--ultimate", "--size", "5"]).is_err());
+        assert!(parse(&["--tui", "--ultimate"]).is_err());
+    }
+
+    #[test]
+    fn test_wild_flag() {
+        let options = parse(&["--wild", "--size", "4", "--win-length", "3"]).unwrap();
+        assert!(options.wild);
+        assert_eq!(options.size, Some((4, 4, 1)));
+        assert!(parse(&["--wild", "--first", "x"]).is_err());
+        assert!(parse(&["--wild", "--level", "2"]).is_err());
+        assert!(parse(&["--wild", "--size", "3x3x3"]).is_err());
+        assert!(parse(&["--wild", "--ultimate"]).is_err());
+    }
+
+    #[test]
+    fn test_notakto_flag() {
+        let options = parse(&["--notakto", "3", "--mode", "human"]).unwrap();
+        assert_eq!(options.notakto, Some(3));
+        assert_eq!(options.mode, Some(ModeOption::Human));
+        assert!(parse(&["--notakto", "many"]).is_err());
+        assert!(parse(&["--notakto", "2", "--level", "2"]).is_err());
+        assert!(parse(&["--notakto", "2", "--mode", "watch"]).is_err());
+        assert!(parse(&["--notakto", "2", "--wild"]).is_err());
+    }
+
+    #[test]
+    fn test_tui_flag() {
+        let options = parse(&["--tui", "--level", "3", "--size", "5"]).unwrap();
+        assert!(options.tui);
+        assert_eq!(options.mode, Some(ModeOption::Computer));
+        assert!(parse(&["--tui", "--watch", "1,2"]).unwrap().tui);
+
+        assert!(parse(&["--tui", "--match", "1,2"]).is_err());
+        assert!(parse(&["--tui", "--host", "a:1"]).is_err());
+        assert!(parse(&["--tui", "--size", "4x4x4"]).is_err());
+    }
+
+    #[test]
+    fn test_match_flags() {
+        let options = parse(&["--match", "2,1", "--games", "50", "--size", "4"]).unwrap();
+        assert_eq!(options.bot_match, Some((2, 1)));
+        assert_eq!(options.games, Some(50));
+        assert_eq!(options.mode, None);
+
+        assert!(parse(&["--match", "2"]).is_err());
+        assert!(parse(&["--match", "1,2", "--level", "1"]).is_err());
+        assert!(parse(&["--games", "10"]).is_err());
+    }
+
+    #[test]
+    fn test_tournament_flags() {
+        let options = parse(&[
+            "--tournament",
+            "1, 2,3",
+            "--swiss",
+            "4",
+            "--records",
+            "games.tsv",
+        ])
+        .unwrap();
+        assert_eq!(options.tournament, Some(vec![1, 2, 3]));
+        assert_eq!(options.swiss_rounds, Some(4));
+        assert_eq!(options.records, Some("games.tsv".to_string()));
+
+        assert!(parse(&["--tournament", "1,two"]).is_err());
+        assert!(parse(&["--tournament", "1,2", "--match", "1,2"]).is_err());
+        assert!(parse(&["--swiss", "3"]).is_err());
+        assert!(parse(&["--records", "games.tsv"]).is_err());
+    }
+
+    #[test]
+    fn test_watch_flags() {
+        let options = parse(&["--watch", "3,4", "--delay", "0"]).unwrap();
+        assert_eq!(options.mode, Some(ModeOption::Watch));
+        assert_eq!(options.watch, Some((3, 4)));
+        assert_eq!(options.delay, Some(0));
+
+        assert_eq!(
+            parse(&["--mode", "watch"]).unwrap().mode,
+            Some(ModeOption::Watch)
+        );
+        assert!(parse(&["--watch", "1,2", "--level", "1"]).is_err());
+        assert!(parse(&["--watch", "1,2", "--mode", "human"]).is_err());
+        assert!(parse(&["--mode", "watch", "--computer", "X"]).is_err());
+        assert!(parse(&["--delay", "100"]).is_err());
+        assert!(parse(&["--watch", "1,2", "--match", "1,2"]).is_err());
+    }
+
+    #[test]
+    fn test_invalid_flags() {
+        assert!(parse(&["--first"]).is_err());
+        assert!(parse(&["--first", "Y"]).is_err());
+        assert!(parse(&["--mode", "robot"]).is_err());
+        assert!(parse(&["--level", "hard"]).is_err());
+        assert!(parse(&["--seed", "-1"]).is_err());
+        assert!(parse(&["--size", "4by4"]).is_err());
+        assert!(parse(&["--colour", "red"]).is_err());
+        assert!(parse(&["--mode", "human", "--level", "1"]).is_err());
+        assert!(parse(&["game.txt", "--size", "4"]).is_err());
+        assert!(parse(&["--first", "X", "--load", "game.txt"]).is_err());
+        assert!(parse(&["one.txt", "two.txt"]).is_err());
+    }
+}
@@ -0,0 +1,108 @@
+// error.rs
+
+use crate::tic_tac_toe::{format_size, Player, PositionError, MAX_BOARD_CELLS, MAX_BOARD_SIDE};
+use std::fmt;
+
+/// Errors returned by the game engine and the brains.
+///
+/// Each variant describes one reason a move or a game could not be made, so callers can
+/// react to it instead of reading the message.
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub enum GameError {
+    /// The position is not on the board. Positions start at 1.
+    PositionOutOfRange(usize),
+    /// The slot at this position already holds a mark.
+    SlotOccupied(usize),
+    /// The move was made by the wrong player.
+    WrongPlayer { expected: Player, found: Player },
+    /// The game has already been won or tied.
+    GameOver,
+    /// The board size or win length is not valid.
+    InvalidBoardSize {
+        width: usize,
+        height: usize,
+        layers: usize,
+        win_length: usize,
+    },
+    /// A brain could not choose a move.
+    BrainFailure(String),
+    /// A saved game could not be read.
+    InvalidNotation(String),
+    /// A position string could not be read.
+    InvalidPosition(PositionError),
+    /// A networked game lost its connection or received an invalid message.
+    Network(String),
+    /// In Ultimate Tic Tac Toe, the move was made on another board than the one the player
+    /// was sent to.
+    WrongBoard { expected: usize, found: usize },
+    /// In Ultimate Tic Tac Toe, the move was made on a board that has already been won or tied.
+    /// In Notakto, it was made on a dead board.
+    BoardFinished(usize),
+    /// A game on several boards was given this many boards, which is not allowed.
+    InvalidBoardCount(usize),
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameError::PositionOutOfRange(position) => {
+                write!(f, "Invalid position {}. It is not on the board", position)
+            }
+            GameError::SlotOccupied(position) => {
+                write!(f, "Invalid move: position {} is already taken", position)
+            }
+            GameError::WrongPlayer { expected, found } => write!(
+                f,
+                "Invalid move: it is {}'s turn, not {}'s",
+                Player::get_player_char_from_enum(expected),
+                Player::get_player_char_from_enum(found)
+            ),
+            GameError::GameOver => write!(f, "Game has already ended"),
+            GameError::InvalidBoardSize {
+                width,
+                height,
+                layers,
+                win_length,
+            } => write!(
+                f,
+                "Invalid board size {} with {} in a row. Width, height and layers should be between 1 and {}, with at most {} cells, and the win length should be between 1 and the longest of them",
+                format_size(*width, *height, *layers),
+                win_length,
+                MAX_BOARD_SIDE,
+                MAX_BOARD_CELLS
+            ),
+            GameError::BrainFailure(reason) => write!(f, "The computer could not move: {}", reason),
+            GameError::InvalidNotation(reason) => write!(f, "Invalid saved game: {}", reason),
+            GameError::InvalidPosition(err) => write!(f, "Invalid position string: {}", err),
+            GameError::Network(reason) => write!(f, "Network error: {}", reason),
+            GameError::WrongBoard { expected, found } => write!(
+                f,
+                "Invalid move: you must play on board {}, not board {}",
+                expected, found
+            ),
+            GameError::BoardFinished(board) => {
+                write!(f, "Invalid move: board {} is already finished", board)
+            }
+            GameError::InvalidBoardCount(boards) => write!(
+                f,
+                "Invalid number of boards {}. There should be at least 1",
+                boards
+            ),
+        }
+    }
+}
+
+impl std::error::Error for GameError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            GameError::InvalidPosition(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<PositionError> for GameError {
+    fn from(err: PositionError) -> Self {
+        GameError::InvalidPosition(err)
+    }
+}
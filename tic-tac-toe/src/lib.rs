@@ -0,0 +1,41 @@
+//! Tic Tac Toe game engine and computer players.
+//!
+//! The engine is a [`Board`] that checks every [`Move`] and reports the [`BoardState`] of the
+//! game. Computer players implement [`TicTacToeBrain`]. Nothing here reads from or prints to
+//! the terminal, so the engine can be used by any front end.
+//!
+//! ```
+//! use tic_tac_toe::{Board, BoardState, BrainLevelTwo, Move, Player, TicTacToeBrain};
+//!
+//! let mut board = Board::new(Player::X);
+//! board.make_move(Move::create(5, Player::X)?)?;
+//!
+//! let reply = BrainLevelTwo.make_move(&board)?;
+//! assert_eq!(board.make_move(reply)?, BoardState::Ongoing);
+//! println!("{}", board.display());
+//! # Ok::<(), tic_tac_toe::GameError>(())
+//! ```
+pub mod arena;
+pub mod brains;
+pub mod error;
+pub mod network;
+pub mod notakto;
+pub mod notation;
+pub mod random;
+pub mod server;
+pub mod tic_tac_toe;
+pub mod tournament;
+pub mod ultimate;
+pub mod web;
+pub mod wild;
+mod zobrist;
+
+pub use brains::{
+    BrainLevelOne, BrainLevelThree, BrainLevelTwo, BrainMonteCarlo, MoveStats, SharedTable,
+    TicTacToeBrain, TranspositionTable,
+};
+pub use error::GameError;
+pub use tic_tac_toe::{
+    Board, BoardDisplay, BoardState, Move, Player, PositionError, WinningLine, BOARD_SIZE,
+    MAX_BOARD_CELLS, MAX_BOARD_SIDE, QUBIC_SIZE,
+};
@@ -1,21 +1,150 @@
+use cli::{ModeOption, Options};
 use colored::*;
-use std::io;
-mod brains;
-use brains::{BrainLevelOne, TicTacToeBrain};
-mod tic_tac_toe;
 use rand::Rng;
-use tic_tac_toe::{Board, BoardState, Move, Player, BOARD_SIZE};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+use std::{env, fs, io, process, thread};
+use tic_tac_toe::arena;
+use tic_tac_toe::network::{HostSession, JoinSession, ServerMessage};
+use tic_tac_toe::notakto::{NotaktoBoard, NotaktoBrain, NotaktoMove};
+use tic_tac_toe::random::{random_seed, seeded_rng, GameRng};
+use tic_tac_toe::server::Server;
+use tic_tac_toe::tournament::{Pairing, Tournament};
+use tic_tac_toe::ultimate::{UltimateBoard, UltimateMove};
+use tic_tac_toe::web::WebServer;
+use tic_tac_toe::wild::{WildBoard, WildMove};
+use tic_tac_toe::{
+    Board, BoardState, BrainLevelOne, BrainLevelThree, BrainLevelTwo, BrainMonteCarlo, GameError,
+    Move, Player, TicTacToeBrain, BOARD_SIZE,
+};
+
+mod cli;
+mod tui;
+
 enum GameMode {
     AgainstComputer(Player, Box<dyn TicTacToeBrain>),
     AgainstHuman,
+    /// The first brain plays X and the second plays O, pausing for the delay after each move.
+    ComputerVsComputer(Box<dyn TicTacToeBrain>, Box<dyn TicTacToeBrain>, Duration),
+}
+
+/// What a human player typed on their turn.
+enum PlayerInput {
+    Position(usize),
+    Undo,
+    Redo,
+    Save(String),
+    ShowPosition,
+}
+
+/// What a player of Ultimate Tic Tac Toe typed on their turn.
+enum UltimateInput {
+    /// A board and a cell on it.
+    Move(usize, usize),
+    Undo,
+    Redo,
+}
+
+/// What a player of Wild Tic Tac Toe typed on their turn.
+enum WildInput {
+    /// A cell and the mark to place in it.
+    Move(usize, Player),
+    Undo,
+    Redo,
+}
+
+/// What a player of Notakto typed on their turn.
+enum NotaktoInput {
+    /// A board and a cell on it.
+    Move(usize, usize),
+    Undo,
+    Redo,
 }
 
 fn print_error(error: &str) {
     eprintln!("{}", error.red());
 }
 
-fn ask_for_game_mode() -> GameMode {
-    let result = loop {
+/// Prints an error and exits without starting a game.
+fn exit_with_error(error: &str) -> ! {
+    print_error(error);
+    process::exit(1);
+}
+
+/// Sets up the game mode from the command line options, asking for anything missing.
+///
+/// Computer players are checked against the `board` the game starts from, so a level that
+/// is too slow for it is refused before the game starts. Random choices, like the
+/// computer's side and its moves, are made with `rng`.
+fn ask_for_game_mode(options: &Options, board: &Board, rng: &mut GameRng) -> GameMode {
+    let mode = match options.mode {
+        Some(mode) => mode,
+        None => loop {
+            println!("Choose game mode");
+            println!("1. Play Against Computer");
+            println!("2. Play Against Human");
+            println!("3. Watch Computer Against Computer");
+
+            let mut buf = String::new();
+            io::stdin()
+                .read_line(&mut buf)
+                .expect("An error occurred while reading your string");
+
+            match buf.trim() {
+                "1" => break ModeOption::Computer,
+                "2" => break ModeOption::Human,
+                "3" => break ModeOption::Watch,
+                _ => print_error("Invalid option selected"),
+            }
+        },
+    };
+
+    match mode {
+        ModeOption::Computer => {
+            let brain_seed = rng.gen();
+            let brain = match options.level {
+                Some(level) => brain_for_board(level, brain_seed, board)
+                    .unwrap_or_else(|err| exit_with_error(&err)),
+                None => ask_for_difficulty(brain_seed, board),
+            };
+
+            let chosen_player = options.computer_player.unwrap_or_else(|| {
+                let player_variants = [Player::O, Player::X];
+                let index = rng.gen_range(0..player_variants.len());
+                player_variants[index]
+            });
+
+            GameMode::AgainstComputer(chosen_player, brain)
+        }
+        ModeOption::Human => GameMode::AgainstHuman,
+        ModeOption::Watch => {
+            let (x_seed, o_seed) = (rng.gen(), rng.gen());
+            let (x_brain, o_brain) = match options.watch {
+                Some((x_level, o_level)) => {
+                    let brain = |level, seed| {
+                        brain_for_board(level, seed, board)
+                            .unwrap_or_else(|err| exit_with_error(&err))
+                    };
+                    (brain(x_level, x_seed), brain(o_level, o_seed))
+                }
+                None => {
+                    println!("Computer playing X:");
+                    let x_brain = ask_for_difficulty(x_seed, board);
+                    println!("Computer playing O:");
+                    (x_brain, ask_for_difficulty(o_seed, board))
+                }
+            };
+
+            let delay = Duration::from_millis(options.delay.unwrap_or(500));
+            GameMode::ComputerVsComputer(x_brain, o_brain, delay)
+        }
+    }
+}
+
+/// Asks whether a Notakto game is played against the computer or another person.
+fn ask_for_notakto_mode() -> ModeOption {
+    loop {
         println!("Choose game mode");
         println!("1. Play Against Computer");
         println!("2. Play Against Human");
@@ -25,18 +154,71 @@ fn ask_for_game_mode() -> GameMode {
             .read_line(&mut buf)
             .expect("An error occurred while reading your string");
 
-        let player_variants = [Player::O, Player::X];
-        let index = rand::thread_rng().gen_range(0..player_variants.len());
-        let chosen_player = player_variants[index];
-
         match buf.trim() {
-            "1" => break GameMode::AgainstComputer(chosen_player, Box::new(BrainLevelOne)),
-            "2" => break GameMode::AgainstHuman,
+            "1" => return ModeOption::Computer,
+            "2" => return ModeOption::Human,
             _ => print_error("Invalid option selected"),
         }
-    };
+    }
+}
+
+/// Returns the brain for a difficulty level, or `None` if there is no such level.
+///
+/// Brains that make random choices are seeded with `seed`.
+fn brain_for_level(level: usize, seed: u64) -> Option<Box<dyn TicTacToeBrain + Send + Sync>> {
+    match level {
+        1 => Some(Box::new(BrainLevelOne::new(seed))),
+        2 => Some(Box::new(BrainLevelTwo)),
+        3 => Some(Box::new(BrainLevelThree::default())),
+        4 => Some(Box::new(BrainMonteCarlo::new(10_000, 2f64.sqrt(), seed))),
+        _ => None,
+    }
+}
 
-    result
+/// Returns the brain for a difficulty level if it can play on `board`.
+///
+/// # Returns
+///
+/// - `Ok(brain)` with the brain, seeded with `seed`.
+/// - `Err(message)` if there is no such level, or if it is too slow to play on `board`.
+fn brain_for_board(
+    level: usize,
+    seed: u64,
+    board: &Board,
+) -> Result<Box<dyn TicTacToeBrain + Send + Sync>, String> {
+    let brain = brain_for_level(level, seed).ok_or_else(|| format!("Invalid level {}", level))?;
+    if !brain.can_play(board) {
+        return Err(format!(
+            "Level {} is too slow to play on a {} board",
+            level,
+            board.size_text()
+        ));
+    }
+    Ok(brain)
+}
+
+/// Asks for a difficulty level until one that can play on `board` is chosen.
+fn ask_for_difficulty(seed: u64, board: &Board) -> Box<dyn TicTacToeBrain + Send + Sync> {
+    loop {
+        println!("Choose difficulty");
+        println!("1. Level One (random moves)");
+        println!("2. Level Two (never loses, 3x3 boards only)");
+        println!("3. Level Three (searches ahead, fast on large boards)");
+        println!("4. Level Four (Monte Carlo tree search)");
+
+        let mut buf = String::new();
+        io::stdin()
+            .read_line(&mut buf)
+            .expect("An error occurred while reading your string");
+
+        match buf.trim().parse() {
+            Ok(level) => match brain_for_board(level, seed, board) {
+                Ok(brain) => return brain,
+                Err(err) => print_error(&err),
+            },
+            Err(_) => print_error("Invalid option selected"),
+        }
+    }
 }
 
 fn ask_for_starting_player() -> Player {
@@ -68,31 +250,195 @@ fn ask_for_starting_player() -> Player {
     player
 }
 
-fn ask_for_move_position(player: &Player) -> Result<usize, &'static str> {
+fn ask_for_board(player_1: Player) -> Board {
+    loop {
+        println!(
+            "Choose board size as N, WIDTHxHEIGHT, or WIDTHxHEIGHTxLAYERS for 3D (press enter for {}x{})",
+            BOARD_SIZE, BOARD_SIZE
+        );
+
+        let mut buf = String::new();
+        io::stdin()
+            .read_line(&mut buf)
+            .expect("An error occurred while reading your string");
+
+        if buf.trim().is_empty() {
+            return Board::new(player_1);
+        }
+
+        // The same sizes as `--size`, e.g. `4`, `5x5` or `4x4x4`.
+        let (width, height, layers) = match cli::parse_size(buf.trim()) {
+            Ok(size) => size,
+            Err(err) => {
+                print_error(&err);
+                continue;
+            }
+        };
+
+        let default_win_length = default_win_length(width, height, layers);
+        println!(
+            "How many in a row are needed to win? (press enter for {})",
+            default_win_length
+        );
+
+        let mut buf = String::new();
+        io::stdin()
+            .read_line(&mut buf)
+            .expect("An error occurred while reading your string");
+
+        let win_length = match buf.trim() {
+            "" => default_win_length,
+            input => match input.parse() {
+                Ok(win_length) => win_length,
+                Err(_) => {
+                    print_error("Invalid win length");
+                    continue;
+                }
+            },
+        };
+
+        match Board::with_layers(player_1, width, height, layers, win_length) {
+            Ok(board) => return board,
+            Err(err) => print_error(&err.to_string()),
+        }
+    }
+}
+
+fn ask_for_move_position(player: &Player, board: &Board) -> Result<PlayerInput, &'static str> {
     println!(
-        "{}: Select your move ({} a number between {} and {} then press enter)",
+        "{}: Select your move ({} a number between {} and {} then press enter, or type {}, {}, {} or {})",
         Player::get_player_char_from_enum(player),
         "press".blue(),
         "1".blue(),
-        "9".blue()
+        board.get_number_of_slots().to_string().blue(),
+        "undo".blue(),
+        "redo".blue(),
+        "save FILE".blue(),
+        "position".blue()
     );
+    if board.layers() > 1 {
+        println!(
+            "You can also type a cell as {}, e.g. {} for the top left cell of layer 2",
+            "LAYER ROW COLUMN".blue(),
+            "2 1 1".blue()
+        );
+    }
 
     let mut buffer = String::new();
     io::stdin()
         .read_line(&mut buffer)
         .expect("An error occured while reading your string");
 
-    let position: usize = match buffer.trim().parse() {
-        Ok(position) => position,
-        Err(_) => return Err("Invalid Position Selected"),
+    match buffer.trim().to_ascii_lowercase().as_str() {
+        "undo" => return Ok(PlayerInput::Undo),
+        "redo" => return Ok(PlayerInput::Redo),
+        "position" => return Ok(PlayerInput::ShowPosition),
+        _ => {}
+    }
+
+    if let Some(path) = buffer.trim().strip_prefix("save ") {
+        return Ok(PlayerInput::Save(path.trim().to_string()));
+    }
+
+    match buffer.trim().parse() {
+        Ok(position) => Ok(PlayerInput::Position(position)),
+        Err(_) => match board.parse_position(&buffer) {
+            Some(position) => Ok(PlayerInput::Position(position)),
+            None => Err("Invalid Position Selected"),
+        },
+    }
+}
+
+/// Returns true if it is a human's turn to play.
+fn is_human_turn(board: &Board, game_mode: &GameMode) -> bool {
+    match game_mode {
+        GameMode::AgainstComputer(computer_player, _) => {
+            board.get_next_player() != *computer_player
+        }
+        GameMode::AgainstHuman => true,
+        GameMode::ComputerVsComputer(..) => false,
+    }
+}
+
+/// Takes back moves until it is a human's turn again, so the computer's reply
+/// is undone together with the move it answered.
+///
+/// Returns an error message if there is nothing to undo.
+fn undo_turn(board: &mut Board, game_mode: &GameMode) -> Result<(), &'static str> {
+    if board.undo().is_none() {
+        return Err("There are no moves to undo");
+    }
+
+    while !is_human_turn(board, game_mode) && board.undo().is_some() {}
+    Ok(())
+}
+
+/// Replays undone moves until it is a human's turn again.
+///
+/// Returns an error message if there is nothing to redo.
+fn redo_turn(board: &mut Board, game_mode: &GameMode) -> Result<(), &'static str> {
+    if board.redo().is_none() {
+        return Err("There are no moves to redo");
+    }
+
+    while !is_human_turn(board, game_mode) && board.get_state() == BoardState::Ongoing {
+        if board.redo().is_none() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Lets the brain whose turn it is move, shows the board and waits for `delay`.
+///
+/// Returns true if the game has ended, or if a brain could not move.
+fn watch_turn(
+    board: &mut Board,
+    x_brain: &dyn TicTacToeBrain,
+    o_brain: &dyn TicTacToeBrain,
+    delay: Duration,
+) -> bool {
+    let player = board.get_next_player();
+    let brain = match player {
+        Player::X => x_brain,
+        Player::O => o_brain,
+    };
+
+    let state = match brain
+        .make_move(board)
+        .and_then(|player_move| board.make_move(player_move))
+    {
+        Ok(state) => state,
+        Err(err) => {
+            print_error(&format!(
+                "Computer {} could not move: {}",
+                Player::get_player_char_from_enum(&player),
+                err
+            ));
+            return true;
+        }
     };
 
-    Ok(position)
+    let last_move = board.history().last().expect("A move was just made");
+    println!(
+        "Computer {} played: {}",
+        Player::get_player_char_from_enum(&player),
+        last_move.position()
+    );
+    // `announce_game_end` shows the board of a won game.
+    if !matches!(state, BoardState::Ended(Some(_))) {
+        println!("{}", board.display());
+    }
+
+    if state == BoardState::Ongoing {
+        thread::sleep(delay);
+    }
+    announce_game_end(board, state)
 }
 
 fn play_turn(board: &mut Board, game_mode: &GameMode) -> bool {
     println!("Current board: ");
-    board.display();
+    println!("{}", board.display());
     let player = board.get_next_player();
     let player_move: Move;
     let position: usize;
@@ -101,17 +447,45 @@ fn play_turn(board: &mut Board, game_mode: &GameMode) -> bool {
         GameMode::AgainstComputer(computer_player, brain) if player == *computer_player => {
             // Computer player's turn
             player_move = match brain.make_move(board) {
-                Ok(player_move) => player_move,
+                Ok(player_move) => {
+                    println!("Computer played: {}", player_move.position());
+                    player_move
+                }
                 Err(err) => {
-                    print_error(&err);
+                    print_error(&err.to_string());
                     return false;
                 }
             };
         }
         _ => {
             // Human player's turn
-            position = match ask_for_move_position(&player) {
-                Ok(num) if num > 0 && num <= BOARD_SIZE.pow(2) => num,
+            position = match ask_for_move_position(&player, board) {
+                Ok(PlayerInput::Position(num)) if num > 0 && num <= board.get_number_of_slots() => {
+                    num
+                }
+                Ok(PlayerInput::Undo) => {
+                    if let Err(err) = undo_turn(board, game_mode) {
+                        print_error(err);
+                    }
+                    return false;
+                }
+                Ok(PlayerInput::Redo) => {
+                    if let Err(err) = redo_turn(board, game_mode) {
+                        print_error(err);
+                    }
+                    return announce_game_end(board, board.get_state());
+                }
+                Ok(PlayerInput::Save(path)) => {
+                    match fs::write(&path, board.to_notation()) {
+                        Ok(()) => println!("Game saved to {}", path),
+                        Err(err) => print_error(&format!("Could not save the game: {}", err)),
+                    }
+                    return false;
+                }
+                Ok(PlayerInput::ShowPosition) => {
+                    println!("Position: {}", board.to_position_str());
+                    return false;
+                }
                 _ => {
                     print_error("Invalid Position selected");
                     return false;
@@ -121,7 +495,7 @@ fn play_turn(board: &mut Board, game_mode: &GameMode) -> bool {
             player_move = match Move::create(position, player) {
                 Ok(player_move) => player_move,
                 Err(err) => {
-                    print_error(err.as_str());
+                    print_error(&err.to_string());
                     return false;
                 }
             };
@@ -129,49 +503,942 @@ fn play_turn(board: &mut Board, game_mode: &GameMode) -> bool {
     }
 
     match board.make_move(player_move) {
+        Err(GameError::SlotOccupied(position)) => {
+            print_error(&format!(
+                "Position {} is already taken, choose another one",
+                position
+            ));
+            false
+        }
+        Err(GameError::GameOver) => announce_game_end(board, board.get_state()),
         Err(msg) => {
             print_error(format!("An error occurred while making your move.\n{}", msg).as_str());
             false
         }
-        Ok(state) => match state {
-            BoardState::Ended(player) => {
-                match player {
-                    Some(winner) => {
-                        println!(
-                            "{} {} {}",
-                            "Player".green(),
-                            Player::get_player_char_from_enum(&winner)
-                                .to_string()
-                                .green(),
-                            "won the game!!".green()
-                        );
-                        board.display();
-                    }
-                    None => println!("Tie Game!"),
+        Ok(state) => announce_game_end(board, state),
+    }
+}
+
+/// Prints the result if the game has ended.
+///
+/// Returns true if the game has ended.
+fn announce_game_end(board: &Board, state: BoardState) -> bool {
+    match state {
+        BoardState::Ended(player) => {
+            match player {
+                Some(winner) => {
+                    println!(
+                        "{} {} {}",
+                        "Player".green(),
+                        Player::get_player_char_from_enum(&winner)
+                            .to_string()
+                            .green(),
+                        "won the game!!".green()
+                    );
+                    println!("{}", board.display());
                 }
-                println!("------------------------------------------------------------");
-                true
+                None => println!("Tie Game!"),
             }
-            _ => false,
-        },
+            let moves: Vec<String> = board
+                .history()
+                .iter()
+                .map(|player_move| player_move.position().to_string())
+                .collect();
+            println!("Moves played: {}", moves.join(", "));
+            println!("------------------------------------------------------------");
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Asks the local player of a network game for their move, until they choose a slot.
+///
+/// Undo and redo are not available, since the other player would have to agree to them.
+fn ask_for_network_move(player: &Player, board: &Board) -> usize {
+    loop {
+        match ask_for_move_position(player, board) {
+            Ok(PlayerInput::Position(position)) => return position,
+            Ok(PlayerInput::Undo) | Ok(PlayerInput::Redo) => {
+                print_error("Undo and redo are not available in network games")
+            }
+            Ok(PlayerInput::Save(path)) => match fs::write(&path, board.to_notation()) {
+                Ok(()) => println!("Game saved to {}", path),
+                Err(err) => print_error(&format!("Could not save the game: {}", err)),
+            },
+            Ok(PlayerInput::ShowPosition) => println!("Position: {}", board.to_position_str()),
+            Err(err) => print_error(err),
+        }
+    }
+}
+
+/// Asks the next player of an Ultimate Tic Tac Toe game for their move.
+///
+/// A move is a board and a cell on it, e.g. `5 3`. A player who has been sent to a board can
+/// type just the cell.
+fn ask_for_ultimate_move(
+    player: &Player,
+    game: &UltimateBoard,
+) -> Result<UltimateInput, &'static str> {
+    let player = Player::get_player_char_from_enum(player);
+    match game.required_board() {
+        Some(board) => println!(
+            "{}: Select a cell on board {} ({} a number between {} and {} then press enter, or type {} or {})",
+            player,
+            board,
+            "press".blue(),
+            "1".blue(),
+            "9".blue(),
+            "undo".blue(),
+            "redo".blue()
+        ),
+        None => println!(
+            "{}: Select a board and a cell on it (e.g. {} for the top right cell of the middle board, or type {} or {})",
+            player,
+            "5 3".blue(),
+            "undo".blue(),
+            "redo".blue()
+        ),
+    }
+
+    let mut buffer = String::new();
+    io::stdin()
+        .read_line(&mut buffer)
+        .expect("An error occured while reading your string");
+
+    match buffer.trim().to_ascii_lowercase().as_str() {
+        "undo" => return Ok(UltimateInput::Undo),
+        "redo" => return Ok(UltimateInput::Redo),
+        _ => {}
+    }
+
+    let numbers: Vec<usize> = buffer
+        .split_whitespace()
+        .map(str::parse)
+        .collect::<Result<_, _>>()
+        .map_err(|_| "Invalid move selected")?;
+
+    match (numbers.as_slice(), game.required_board()) {
+        ([board, cell], _) => Ok(UltimateInput::Move(*board, *cell)),
+        ([cell], Some(board)) => Ok(UltimateInput::Move(board, *cell)),
+        _ => Err("Invalid move selected"),
+    }
+}
+
+/// Prints the result if the Ultimate Tic Tac Toe game has ended.
+///
+/// Returns true if the game has ended.
+fn announce_ultimate_end(game: &UltimateBoard) -> bool {
+    let winner = match game.get_state() {
+        BoardState::Ended(winner) => winner,
+        BoardState::Ongoing => return false,
+    };
+
+    println!("{}", game.display());
+    match winner {
+        Some(winner) => println!(
+            "{} {} {}",
+            "Player".green(),
+            Player::get_player_char_from_enum(&winner)
+                .to_string()
+                .green(),
+            "won the game!!".green()
+        ),
+        None => println!("Tie Game!"),
+    }
+    let moves: Vec<String> = game
+        .history()
+        .iter()
+        .map(|player_move| format!("{} {}", player_move.board(), player_move.position()))
+        .collect();
+    println!("Moves played: {}", moves.join(", "));
+    println!("------------------------------------------------------------");
+    true
+}
+
+/// Plays a game of Ultimate Tic Tac Toe between two people at the same terminal.
+fn run_ultimate(player_1: Player) {
+    let mut game = UltimateBoard::new(player_1);
+
+    loop {
+        println!("Current board: ");
+        println!("{}", game.display());
+        let player = game.get_next_player();
+
+        let (board, cell) = match ask_for_ultimate_move(&player, &game) {
+            Ok(UltimateInput::Move(board, cell)) => (board, cell),
+            Ok(UltimateInput::Undo) => {
+                if game.undo().is_none() {
+                    print_error("There are no moves to undo");
+                }
+                continue;
+            }
+            Ok(UltimateInput::Redo) => {
+                if game.redo().is_none() {
+                    print_error("There are no moves to redo");
+                }
+                if announce_ultimate_end(&game) {
+                    return;
+                }
+                continue;
+            }
+            Err(err) => {
+                print_error(err);
+                continue;
+            }
+        };
+
+        let result = UltimateMove::create(board, cell, player)
+            .and_then(|player_move| game.make_move(player_move));
+        match result {
+            Ok(_) if announce_ultimate_end(&game) => return,
+            Ok(_) => {}
+            Err(err) => print_error(&err.to_string()),
+        }
+    }
+}
+
+/// Returns the name of `player` in a Wild Tic Tac Toe game, where the marks do not say who
+/// is who: `Player 1` moves first and `Player 2` second.
+fn wild_player_name(player: Player, game: &WildBoard) -> String {
+    if player == game.first_player() {
+        "Player 1".to_string()
+    } else {
+        "Player 2".to_string()
+    }
+}
+
+/// Asks the next player of a Wild Tic Tac Toe game for a cell and the mark to place in it,
+/// e.g. `5 O`.
+fn ask_for_wild_move(game: &WildBoard) -> Result<WildInput, &'static str> {
+    println!(
+        "{}: Select a cell and a mark (e.g. {} to put an O in the centre, or type {} or {})",
+        wild_player_name(game.get_next_player(), game),
+        "5 O".blue(),
+        "undo".blue(),
+        "redo".blue()
+    );
+
+    let mut buffer = String::new();
+    io::stdin()
+        .read_line(&mut buffer)
+        .expect("An error occured while reading your string");
+
+    let text = buffer.trim().to_ascii_lowercase();
+    match text.as_str() {
+        "undo" => return Ok(WildInput::Undo),
+        "redo" => return Ok(WildInput::Redo),
+        _ => {}
+    }
+
+    let (position, mark) = text.split_at(
+        text.find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(text.len()),
+    );
+    let position = position.parse().map_err(|_| "Invalid move selected")?;
+    let mut mark = mark.trim().chars();
+    match (
+        mark.next().and_then(Player::get_player_enum_from_char),
+        mark.next(),
+    ) {
+        (Some(mark), None) => Ok(WildInput::Move(position, mark)),
+        _ => Err("Choose the mark to place, X or O, after the cell"),
+    }
+}
+
+/// Prints the result if the Wild Tic Tac Toe game has ended.
+///
+/// Returns true if the game has ended.
+fn announce_wild_end(game: &WildBoard) -> bool {
+    let winner = match game.get_state() {
+        BoardState::Ended(winner) => winner,
+        BoardState::Ongoing => return false,
+    };
+
+    println!("{}", game.display());
+    match winner {
+        Some(winner) => println!(
+            "{} {}",
+            wild_player_name(winner, game).green(),
+            "won the game!!".green()
+        ),
+        None => println!("Tie Game!"),
+    }
+    let moves: Vec<String> = game
+        .history()
+        .iter()
+        .map(|player_move| {
+            format!(
+                "{} {}",
+                player_move.position(),
+                Player::get_player_char_from_enum(&player_move.mark())
+            )
+        })
+        .collect();
+    println!("Moves played: {}", moves.join(", "));
+    println!("------------------------------------------------------------");
+    true
+}
+
+/// Plays a game of Wild Tic Tac Toe between two people at the same terminal.
+fn run_wild(mut game: WildBoard) {
+    println!(
+        "Playing on a {} board, whoever gets {} X's or {} O's in a row wins",
+        game.board().size_text(),
+        game.board().win_length(),
+        game.board().win_length()
+    );
+
+    loop {
+        println!("Current board: ");
+        println!("{}", game.display());
+
+        let (position, mark) = match ask_for_wild_move(&game) {
+            Ok(WildInput::Move(position, mark)) => (position, mark),
+            Ok(WildInput::Undo) => {
+                if game.undo().is_none() {
+                    print_error("There are no moves to undo");
+                }
+                continue;
+            }
+            Ok(WildInput::Redo) => {
+                if game.redo().is_none() {
+                    print_error("There are no moves to redo");
+                }
+                if announce_wild_end(&game) {
+                    return;
+                }
+                continue;
+            }
+            Err(err) => {
+                print_error(err);
+                continue;
+            }
+        };
+
+        let result = WildMove::create(position, mark, game.get_next_player())
+            .and_then(|player_move| game.make_move(player_move));
+        match result {
+            Ok(_) if announce_wild_end(&game) => return,
+            Ok(_) => {}
+            Err(err) => print_error(&err.to_string()),
+        }
+    }
+}
+
+/// Returns the name of `player` in a Notakto game, where both players place X: `Player 1`
+/// moves first and `Player 2` second. The player in `computer`'s seat is `Computer`.
+fn notakto_player_name(player: Player, game: &NotaktoBoard, computer: Option<Player>) -> String {
+    if Some(player) == computer {
+        "Computer".to_string()
+    } else if player == game.first_player() {
+        "Player 1".to_string()
+    } else {
+        "Player 2".to_string()
+    }
+}
+
+/// Asks the next player of a Notakto game for a board and a cell on it, e.g. `2 5`. When only
+/// one board is left, the player can type just the cell.
+fn ask_for_notakto_move(
+    game: &NotaktoBoard,
+    computer: Option<Player>,
+) -> Result<NotaktoInput, &'static str> {
+    let live_boards = game.live_boards();
+    println!(
+        "{}: Select a board and a cell on it (e.g. {} for the centre of board 1, or type {} or {})",
+        notakto_player_name(game.get_next_player(), game, computer),
+        "1 5".blue(),
+        "undo".blue(),
+        "redo".blue()
+    );
+
+    let mut buffer = String::new();
+    io::stdin()
+        .read_line(&mut buffer)
+        .expect("An error occured while reading your string");
+
+    match buffer.trim().to_ascii_lowercase().as_str() {
+        "undo" => return Ok(NotaktoInput::Undo),
+        "redo" => return Ok(NotaktoInput::Redo),
+        _ => {}
+    }
+
+    let numbers: Vec<usize> = buffer
+        .split_whitespace()
+        .map(str::parse)
+        .collect::<Result<_, _>>()
+        .map_err(|_| "Invalid move selected")?;
+
+    match (numbers.as_slice(), live_boards.as_slice()) {
+        ([board, cell], _) => Ok(NotaktoInput::Move(*board, *cell)),
+        ([cell], [board]) => Ok(NotaktoInput::Move(*board, *cell)),
+        _ => Err("Invalid move selected"),
+    }
+}
+
+/// Prints the result if the Notakto game has ended.
+///
+/// Returns true if the game has ended.
+fn announce_notakto_end(game: &NotaktoBoard, computer: Option<Player>) -> bool {
+    let winner = match game.get_state() {
+        BoardState::Ended(Some(winner)) => winner,
+        _ => return false,
+    };
+
+    println!("{}", game.display());
+    println!(
+        "{} {}",
+        notakto_player_name(winner, game, computer).green(),
+        "won the game!!".green()
+    );
+    let moves: Vec<String> = game
+        .history()
+        .iter()
+        .map(|player_move| format!("{} {}", player_move.board(), player_move.position()))
+        .collect();
+    println!("Moves played: {}", moves.join(", "));
+    println!("------------------------------------------------------------");
+    true
+}
+
+/// Plays a game of Notakto. If `computer` is set, `NotaktoBrain` plays that player's moves.
+fn run_notakto(mut game: NotaktoBoard, computer: Option<Player>) {
+    println!(
+        "Playing Notakto on {} boards. Both players place X, and whoever completes three in a row on the last board loses",
+        game.board_count()
+    );
+
+    loop {
+        println!("Current board: ");
+        println!("{}", game.display());
+        let player = game.get_next_player();
+
+        if Some(player) == computer {
+            let result = NotaktoBrain.make_move(&game).and_then(|player_move| {
+                println!(
+                    "Computer played: {} {}",
+                    player_move.board(),
+                    player_move.position()
+                );
+                game.make_move(player_move)
+            });
+            match result {
+                Ok(_) if announce_notakto_end(&game, computer) => return,
+                Ok(_) => {}
+                Err(err) => {
+                    print_error(&err.to_string());
+                    return;
+                }
+            }
+            continue;
+        }
+
+        let (board, cell) = match ask_for_notakto_move(&game, computer) {
+            Ok(NotaktoInput::Move(board, cell)) => (board, cell),
+            Ok(NotaktoInput::Undo) => {
+                if game.undo().is_none() {
+                    print_error("There are no moves to undo");
+                }
+                // The computer's reply is taken back together with the move it answered.
+                while computer == Some(game.get_next_player()) && game.undo().is_some() {}
+                continue;
+            }
+            Ok(NotaktoInput::Redo) => {
+                if game.redo().is_none() {
+                    print_error("There are no moves to redo");
+                }
+                while computer == Some(game.get_next_player()) && game.redo().is_some() {}
+                if announce_notakto_end(&game, computer) {
+                    return;
+                }
+                continue;
+            }
+            Err(err) => {
+                print_error(err);
+                continue;
+            }
+        };
+
+        let result = NotaktoMove::create(board, cell, player)
+            .and_then(|player_move| game.make_move(player_move));
+        match result {
+            Ok(_) if announce_notakto_end(&game, computer) => return,
+            Ok(_) => {}
+            Err(err) => print_error(&err.to_string()),
+        }
+    }
+}
+
+/// Hosts a game on `address`. The host plays X and the player who joins plays O.
+fn run_host(address: &str, board: Board) {
+    let listener = TcpListener::bind(address).unwrap_or_else(|err| {
+        exit_with_error(&format!("Could not listen on {}: {}", address, err))
+    });
+    println!(
+        "Waiting for a player to join on {}",
+        listener
+            .local_addr()
+            .map_or(address.to_string(), |addr| addr.to_string())
+    );
+
+    let mut session = HostSession::accept(&listener, board, Player::O)
+        .unwrap_or_else(|err| exit_with_error(&err.to_string()));
+    println!(
+        "Player O joined. Playing on a {} board, get {} in a row to win",
+        session.board().size_text(),
+        session.board().win_length()
+    );
+
+    let mut state = session.board().get_state();
+    while !announce_game_end(session.board(), state) {
+        println!("Current board: ");
+        println!("{}", session.board().display());
+
+        let result = if session.board().get_next_player() == session.local_player() {
+            let position = ask_for_network_move(&session.local_player(), session.board());
+            session.play_local(position)
+        } else {
+            println!("Waiting for O to move...");
+            session.wait_for_remote().map(|(position, state)| {
+                println!("O played: {}", position);
+                state
+            })
+        };
+
+        match result {
+            Ok(new_state) => state = new_state,
+            Err(GameError::Network(reason)) => exit_with_error(&format!("Game over: {}", reason)),
+            Err(err) => print_error(&err.to_string()),
+        }
+    }
+}
+
+/// Joins the game hosted on `address`.
+fn run_join(address: &str) {
+    let mut session = JoinSession::connect(address)
+        .unwrap_or_else(|err| exit_with_error(&format!("Could not join {}: {}", address, err)));
+    let player = session.player();
+    println!(
+        "Joined as {}. Playing on a {} board, get {} in a row to win",
+        Player::get_player_char_from_enum(&player),
+        session.board().size_text(),
+        session.board().win_length()
+    );
+    println!("Current board: ");
+    println!("{}", session.board().display());
+
+    loop {
+        if session.is_my_turn() {
+            let position = ask_for_network_move(&player, session.board());
+            if let Err(err) = session.send_move(position) {
+                exit_with_error(&err.to_string());
+            }
+        } else {
+            println!("Waiting for the host to move...");
+        }
+
+        // Read the host's answer up to the next board, or the rejection of our move.
+        loop {
+            match session.receive() {
+                Ok(ServerMessage::Moved { player, slot }) => println!(
+                    "{} played: {}",
+                    Player::get_player_char_from_enum(&player),
+                    slot
+                ),
+                Ok(ServerMessage::State(_))
+                    if session.board().get_state() == BoardState::Ongoing =>
+                {
+                    println!("Current board: ");
+                    println!("{}", session.board().display());
+                    break;
+                }
+                // The game is over, and `END` comes next.
+                Ok(ServerMessage::State(_)) => {}
+                Ok(ServerMessage::Error(reason)) => {
+                    print_error(&reason);
+                    break;
+                }
+                Ok(ServerMessage::End(winner)) => {
+                    announce_game_end(session.board(), BoardState::Ended(winner));
+                    return;
+                }
+                Ok(ServerMessage::Welcome { .. }) => {}
+                Err(err) => exit_with_error(&format!("Game over: {}", err)),
+            }
+        }
+    }
+}
+
+/// Runs a lobby server on `address` until it is stopped.
+fn run_server(address: &str, level: Option<usize>, seed: u64) {
+    let level = level.unwrap_or(Server::DEFAULT_BRAIN_LEVEL);
+    if brain_for_level(level, seed).is_none() {
+        exit_with_error(&format!("Invalid level {}", level));
+    }
+
+    let listener = TcpListener::bind(address).unwrap_or_else(|err| {
+        exit_with_error(&format!("Could not listen on {}: {}", address, err))
+    });
+    println!(
+        "Serving games on {} with a level {} computer player",
+        listener
+            .local_addr()
+            .map_or(address.to_string(), |addr| addr.to_string()),
+        level
+    );
+    println!("Random seed: {} (replay with --seed {})", seed, seed);
+
+    let server = Server::new(brain_for_level, seed).with_brain_level(level);
+    if let Err(err) = server.serve(listener) {
+        exit_with_error(&err.to_string());
+    }
+}
+
+/// Serves the HTTP API on `address` until it is stopped. Every computer level can be asked
+/// for a move as `level1` to `level4`.
+fn run_web(address: &str, rng: &mut GameRng) {
+    let mut server = WebServer::new();
+    for level in 1..=4 {
+        let brain = brain_for_level(level, rng.gen()).expect("Levels 1 to 4 exist");
+        server.register(&format!("level{}", level), brain);
+    }
+
+    let listener = TcpListener::bind(address).unwrap_or_else(|err| {
+        exit_with_error(&format!("Could not listen on {}: {}", address, err))
+    });
+    println!(
+        "Serving the HTTP API on http://{}",
+        listener
+            .local_addr()
+            .map_or(address.to_string(), |addr| addr.to_string())
+    );
+    if let Err(err) = server.serve(listener) {
+        exit_with_error(&err.to_string());
+    }
+}
+
+/// Connects to the lobby server on `address`. Lines typed are sent to the server as they
+/// are, except a plain number, which is sent as a move. Boards the server sends are drawn.
+fn run_lobby(address: &str) {
+    let stream = TcpStream::connect(address).unwrap_or_else(|err| {
+        exit_with_error(&format!("Could not connect to {}: {}", address, err))
+    });
+    let reader = stream
+        .try_clone()
+        .unwrap_or_else(|err| exit_with_error(&err.to_string()));
+    let mut writer = stream;
+
+    println!("Connected to {}", address);
+    println!(
+        "Commands: {}, {}, {}, {}, {}, {} (or just the number), {} and {}",
+        "LIST".blue(),
+        "CREATE size=3 win=3 first=X side=X level=N".blue(),
+        "PLAY".blue(),
+        "JOIN id".blue(),
+        "WATCH id".blue(),
+        "MOVE slot".blue(),
+        "LEAVE".blue(),
+        "QUIT".blue()
+    );
+
+    thread::spawn(move || {
+        for line in BufReader::new(reader).lines().map_while(Result::ok) {
+            print_lobby_line(&line);
+        }
+        println!("Disconnected from the server");
+        process::exit(0);
+    });
+
+    for line in io::stdin().lock().lines().map_while(Result::ok) {
+        let line = match line.trim().parse::<usize>() {
+            Ok(slot) => format!("MOVE {}", slot),
+            Err(_) => line,
+        };
+        if writeln!(writer, "{}", line).is_err() {
+            break;
+        }
+    }
+    let _ = writeln!(writer, "QUIT");
+}
+
+/// Prints a line from the lobby server, drawing any board it holds.
+fn print_lobby_line(line: &str) {
+    let show_position = |position: &str| {
+        if let Ok(board) = Board::from_position_str(position) {
+            println!("{}", board.display());
+        }
+    };
+
+    match ServerMessage::parse(line) {
+        Ok(ServerMessage::Welcome { player, position }) => {
+            println!("You play {}", Player::get_player_char_from_enum(&player));
+            show_position(&position);
+        }
+        Ok(ServerMessage::Moved { player, slot }) => println!(
+            "{} played: {}",
+            Player::get_player_char_from_enum(&player),
+            slot
+        ),
+        Ok(ServerMessage::State(position)) => show_position(&position),
+        Ok(ServerMessage::Error(reason)) => print_error(&reason),
+        Ok(ServerMessage::End(Some(winner))) => println!(
+            "{} {} {}",
+            "Player".green(),
+            Player::get_player_char_from_enum(&winner)
+                .to_string()
+                .green(),
+            "won the game!!".green()
+        ),
+        Ok(ServerMessage::End(None)) => println!("Tie Game!"),
+        Err(_) => {
+            println!("{}", line);
+            if let Some((_, position)) = line
+                .strip_prefix("WATCHING ")
+                .and_then(|rest| rest.split_once(' '))
+            {
+                show_position(position);
+            }
+        }
+    }
+}
+
+/// Creates a board from `--size`, `--win-length` and `--misere`, using the classic board for
+/// anything not given.
+fn board_from_flags(player_1: Player, options: &Options) -> Board {
+    let (width, height, layers) = options.size.unwrap_or((BOARD_SIZE, BOARD_SIZE, 1));
+    let win_length = options
+        .win_length
+        .unwrap_or_else(|| default_win_length(width, height, layers));
+    Board::with_layers(player_1, width, height, layers, win_length)
+        .unwrap_or_else(|err| exit_with_error(&err.to_string()))
+        .with_misere(options.misere)
+}
+
+/// Returns the win length used when none is given: the smallest side of the board.
+fn default_win_length(width: usize, height: usize, layers: usize) -> usize {
+    if layers > 1 {
+        width.min(height).min(layers)
+    } else {
+        width.min(height)
+    }
+}
+
+/// Plays computer level `level_a` against `level_b` from `start` and prints the results.
+fn run_match(
+    level_a: usize,
+    level_b: usize,
+    games: Option<usize>,
+    start: &Board,
+    rng: &mut GameRng,
+) {
+    let games = games.unwrap_or(100);
+    let brain = |level, rng: &mut GameRng| {
+        brain_for_level(level, rng.gen())
+            .unwrap_or_else(|| exit_with_error(&format!("Invalid level {}", level)))
+    };
+    let (brain_a, brain_b) = (brain(level_a, rng), brain(level_b, rng));
+
+    println!(
+        "A: Level {}, B: Level {}, {} games on a {}x{} board with {} in a row",
+        level_a,
+        level_b,
+        games,
+        start.width(),
+        start.height(),
+        start.win_length()
+    );
+
+    match arena::play_match(brain_a.as_ref(), brain_b.as_ref(), start, games) {
+        Ok(report) => println!("{}", report),
+        Err(err) => exit_with_error(&format!("The match could not be played: {}", err)),
+    }
+}
+
+/// Plays a tournament between computer `levels` from `start`, prints the standings and
+/// writes the games to the `--records` file.
+fn run_tournament(levels: &[usize], options: &Options, start: Board, rng: &mut GameRng) {
+    let pairing = match options.swiss_rounds {
+        Some(rounds) => Pairing::Swiss { rounds },
+        None => Pairing::RoundRobin,
+    };
+    let mut tournament = Tournament::new(start, pairing, options.games.unwrap_or(10));
+
+    for (index, &level) in levels.iter().enumerate() {
+        let brain = brain_for_level(level, rng.gen())
+            .unwrap_or_else(|| exit_with_error(&format!("Invalid level {}", level)));
+        // Number the entrants so the same level can take part more than once.
+        tournament.add(&format!("{}. Level {}", index + 1, level), brain);
+    }
+
+    let results = tournament.run().unwrap_or_else(|err| {
+        exit_with_error(&format!("The tournament could not be played: {}", err))
+    });
+    print!("{}", results.standings_table());
+
+    if let Some(path) = &options.records {
+        match fs::write(path, results.game_records()) {
+            Ok(()) => println!("Games written to {}", path),
+            Err(err) => print_error(&format!("Could not write {}: {}", path, err)),
+        }
     }
 }
 
 fn main() {
-    println!("Tic Tac Toe game");
-    // 1. Ask for Computer vs Player
+    // 1. Ask for Starting Player
+    // 2. Ask for Board Size
+    // 3. Ask for Computer vs Player
     //      - If Computer:
-    //          1. Level 1 or Level2
+    //          1. Level 1 to Level 4
+    //
+    // A saved game can be resumed by passing its file: `tic-tac-toe game.txt`
+    // The file can also hold a single position string, e.g. `X1O/1X1/3 O`
     //
-    // 2. Ask for Starting Player
+    // Every question can be answered up front with a flag, see `cli::USAGE`.
+
+    let options = cli::parse_args(env::args().skip(1)).unwrap_or_else(|err| {
+        print_error(&err);
+        eprintln!("{}", cli::USAGE);
+        process::exit(2);
+    });
 
-    let player_1 = ask_for_starting_player();
-    let game_mode = ask_for_game_mode();
-    let mut board = Board::new(player_1);
-    let mut game_ended = false;
+    if options.help {
+        println!("{}", cli::USAGE);
+        return;
+    }
+
+    println!("Tic Tac Toe game");
+
+    if options.ultimate {
+        run_ultimate(options.first_player.unwrap_or_else(ask_for_starting_player));
+        return;
+    }
+
+    if options.wild {
+        let (width, height, _) = options.size.unwrap_or((BOARD_SIZE, BOARD_SIZE, 1));
+        let win_length = options
+            .win_length
+            .unwrap_or_else(|| default_win_length(width, height, 1));
+        let game = WildBoard::with_size(Player::X, width, height, win_length)
+            .unwrap_or_else(|err| exit_with_error(&err.to_string()));
+        run_wild(game);
+        return;
+    }
+
+    // Every random choice comes from this seed, so `--seed` replays a game exactly.
+    let seed = options.seed.unwrap_or_else(random_seed);
+    let mut rng = seeded_rng(seed);
+
+    if let Some(boards) = options.notakto {
+        let game = NotaktoBoard::with_boards(Player::X, boards)
+            .unwrap_or_else(|err| exit_with_error(&err.to_string()));
+        let computer = match options.mode.unwrap_or_else(ask_for_notakto_mode) {
+            ModeOption::Computer => {
+                println!("Random seed: {} (replay with --seed {})", seed, seed);
+                let computer = [Player::X, Player::O][rng.gen_range(0..2)];
+                println!("Computer is {}", notakto_player_name(computer, &game, None));
+                Some(computer)
+            }
+            _ => None,
+        };
+        run_notakto(game, computer);
+        return;
+    }
+
+    let saved_game = options.load.as_ref().map(|path| {
+        let text = fs::read_to_string(path)
+            .unwrap_or_else(|err| exit_with_error(&format!("Could not read {}: {}", path, err)));
+
+        // Position strings never contain the `Key: value` lines of a saved game.
+        let board = if text.contains(':') {
+            Board::from_notation(&text)
+        } else {
+            Board::from_position_str(text.trim()).map_err(GameError::from)
+        };
+
+        board.unwrap_or_else(|err| exit_with_error(&format!("Could not load {}: {}", path, err)))
+    });
+
+    if let Some(address) = &options.server {
+        run_server(address, options.level, seed);
+        return;
+    }
+
+    if let Some(address) = &options.web {
+        println!("Random seed: {} (replay with --seed {})", seed, seed);
+        run_web(address, &mut rng);
+        return;
+    }
+
+    if let Some(address) = &options.lobby {
+        run_lobby(address);
+        return;
+    }
+
+    if let Some(address) = &options.join {
+        run_join(address);
+        return;
+    }
+
+    if let Some(address) = &options.host {
+        let board = saved_game.unwrap_or_else(|| {
+            let player_1 = options.first_player.unwrap_or_else(ask_for_starting_player);
+            match (options.size, options.win_length) {
+                (None, None) => ask_for_board(player_1).with_misere(options.misere),
+                _ => board_from_flags(player_1, &options),
+            }
+        });
+        run_host(address, board);
+        return;
+    }
+
+    if let Some((level_a, level_b)) = options.bot_match {
+        let start = saved_game.unwrap_or_else(|| {
+            board_from_flags(options.first_player.unwrap_or(Player::X), &options)
+        });
+        run_match(level_a, level_b, options.games, &start, &mut rng);
+        println!("Random seed: {} (replay with --seed {})", seed, seed);
+        return;
+    }
+
+    if let Some(levels) = &options.tournament {
+        let start = saved_game.unwrap_or_else(|| {
+            board_from_flags(options.first_player.unwrap_or(Player::X), &options)
+        });
+        run_tournament(levels, &options, start, &mut rng);
+        println!("Random seed: {} (replay with --seed {})", seed, seed);
+        return;
+    }
+
+    // The board comes first, so the computer players can be checked against it.
+    let mut board = saved_game.unwrap_or_else(|| {
+        let player_1 = options.first_player.unwrap_or_else(ask_for_starting_player);
+        match (options.size, options.win_length) {
+            (None, None) => ask_for_board(player_1).with_misere(options.misere),
+            _ => board_from_flags(player_1, &options),
+        }
+    });
+    let player_1 = board.first_player();
+    let game_mode = ask_for_game_mode(&options, &board, &mut rng);
+    let mut game_ended = announce_game_end(&board, board.get_state());
+
+    if board.is_misere() {
+        println!(
+            "Playing on a {} board by the misère rule, whoever gets {} in a row loses",
+            board.size_text(),
+            board.win_length()
+        );
+    } else {
+        println!(
+            "Playing on a {} board, get {} in a row to win",
+            board.size_text(),
+            board.win_length()
+        );
+    }
 
     match &game_mode {
         GameMode::AgainstComputer(computer_player, _) => {
+            println!("Random seed: {} (replay with --seed {})", seed, seed);
             if player_1 == *computer_player {
                 println!(
                     "Computer has chosen: {}",
@@ -180,6 +1447,38 @@ fn main() {
             }
         }
         GameMode::AgainstHuman => {}
+        GameMode::ComputerVsComputer(..) => {
+            println!("Random seed: {} (replay with --seed {})", seed, seed);
+        }
+    }
+
+    if options.tui {
+        if board.layers() > 1 {
+            exit_with_error("--tui cannot be used with boards with layers");
+        }
+        if game_ended {
+            return;
+        }
+        if let Err(err) = tui::run(&mut board, &game_mode) {
+            exit_with_error(&format!("The terminal UI failed: {}", err));
+        }
+        if !announce_game_end(&board, board.get_state()) {
+            println!("{}", board.display());
+            println!(
+                "Game left unfinished. Position: {}",
+                board.to_position_str()
+            );
+        }
+        return;
+    }
+
+    if let GameMode::ComputerVsComputer(x_brain, o_brain, delay) = &game_mode {
+        if !game_ended {
+            println!("{}", board.display());
+        }
+        while !game_ended {
+            game_ended = watch_turn(&mut board, x_brain.as_ref(), o_brain.as_ref(), *delay);
+        }
     }
 
     while !game_ended {
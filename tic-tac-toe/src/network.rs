@@ -0,0 +1,556 @@
+// network.rs
+
+//! Two-player games over TCP.
+//!
+//! One player hosts the game and the other joins it. The host owns the real `Board`: every
+//! move, its own and the other player's, goes through `Board::make_move` on the host, which
+//! then sends the result to the other player. The joining player only keeps a copy of the
+//! board to show, which follows the `MOVED` messages and is checked against each `STATE`.
+//!
+//! The protocol is plain text, one message per line, so it can be tried out with `telnet` or
+//! `nc`. The host sends:
+//!
+//! - `WELCOME <player> <position>`: Sent once on connecting. `<player>` is the side the
+//!   joining player plays, and `<position>` is the starting position string (see
+//!   `Board::to_position_str`).
+//! - `MOVED <player> <slot>`: A move was made.
+//! - `STATE <position>`: The position after a move.
+//! - `ERROR <reason>`: The joining player's last message was rejected. They should try again.
+//! - `END X|O|DRAW`: The game is over. The host closes the connection after sending it.
+//!
+//! The joining player sends:
+//!
+//! - `MOVE <slot>`: Plays a slot, numbered from 1 like on the board.
+//! - `QUIT`: Leaves the game.
+//!
+//! If either side closes the connection before `END`, the other side gets
+//! `GameError::Network`.
+use crate::error::GameError;
+use crate::tic_tac_toe::{Board, BoardState, Move, Player};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+
+/// A message from the host to the joining player.
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub enum ServerMessage {
+    Welcome { player: Player, position: String },
+    Moved { player: Player, slot: usize },
+    State(String),
+    Error(String),
+    End(Option<Player>),
+}
+
+/// A message from the joining player to the host.
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub enum ClientMessage {
+    Move(usize),
+    Quit,
+}
+
+impl ServerMessage {
+    /// Returns the message as a line of the protocol, without the newline.
+    pub fn to_line(&self) -> String {
+        match self {
+            ServerMessage::Welcome { player, position } => format!(
+                "WELCOME {} {}",
+                Player::get_player_char_from_enum(player),
+                position
+            ),
+            ServerMessage::Moved { player, slot } => {
+                format!(
+                    "MOVED {} {}",
+                    Player::get_player_char_from_enum(player),
+                    slot
+                )
+            }
+            ServerMessage::State(position) => format!("STATE {}", position),
+            ServerMessage::Error(reason) => format!("ERROR {}", reason),
+            ServerMessage::End(Some(winner)) => {
+                format!("END {}", Player::get_player_char_from_enum(winner))
+            }
+            ServerMessage::End(None) => "END DRAW".to_string(),
+        }
+    }
+
+    /// Reads a line of the protocol.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(ServerMessage)` if the line is a valid message.
+    /// - `Err(GameError::Network)` if it is not.
+    pub fn parse(line: &str) -> Result<Self, GameError> {
+        let line = line.trim();
+        let (command, rest) = line.split_once(' ').unwrap_or((line, ""));
+
+        match command {
+            "WELCOME" => {
+                let (player, position) = rest.split_once(' ').ok_or_else(|| invalid(line))?;
+                Ok(ServerMessage::Welcome {
+                    player: parse_player(player).ok_or_else(|| invalid(line))?,
+                    position: position.to_string(),
+                })
+            }
+            "MOVED" => {
+                let (player, slot) = rest.split_once(' ').ok_or_else(|| invalid(line))?;
+                Ok(ServerMessage::Moved {
+                    player: parse_player(player).ok_or_else(|| invalid(line))?,
+                    slot: slot.parse().map_err(|_| invalid(line))?,
+                })
+            }
+            "STATE" if !rest.is_empty() => Ok(ServerMessage::State(rest.to_string())),
+            "ERROR" => Ok(ServerMessage::Error(rest.to_string())),
+            "END" if rest == "DRAW" => Ok(ServerMessage::End(None)),
+            "END" => Ok(ServerMessage::End(Some(
+                parse_player(rest).ok_or_else(|| invalid(line))?,
+            ))),
+            _ => Err(invalid(line)),
+        }
+    }
+}
+
+impl ClientMessage {
+    /// Returns the message as a line of the protocol, without the newline.
+    pub fn to_line(&self) -> String {
+        match self {
+            ClientMessage::Move(slot) => format!("MOVE {}", slot),
+            ClientMessage::Quit => "QUIT".to_string(),
+        }
+    }
+
+    /// Reads a line of the protocol. Commands are not case sensitive, for people typing
+    /// them by hand.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(ClientMessage)` if the line is a valid message.
+    /// - `Err(GameError::Network)` if it is not.
+    pub fn parse(line: &str) -> Result<Self, GameError> {
+        let line = line.trim();
+        let (command, rest) = line.split_once(' ').unwrap_or((line, ""));
+
+        match command.to_ascii_uppercase().as_str() {
+            "MOVE" => rest
+                .trim()
+                .parse()
+                .map(ClientMessage::Move)
+                .map_err(|_| invalid(line)),
+            "QUIT" if rest.is_empty() => Ok(ClientMessage::Quit),
+            _ => Err(invalid(line)),
+        }
+    }
+}
+
+fn invalid(line: &str) -> GameError {
+    GameError::Network(format!("unexpected message `{}`", line))
+}
+
+/// Reads a player written as `X` or `O`.
+pub(crate) fn parse_player(value: &str) -> Option<Player> {
+    let mut chars = value.chars();
+    match (
+        chars.next().and_then(Player::get_player_enum_from_char),
+        chars.next(),
+    ) {
+        (Some(player), None) => Some(player),
+        _ => None,
+    }
+}
+
+/// One end of a connection, reading and writing whole lines.
+struct Connection {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+impl Connection {
+    fn new(stream: TcpStream) -> Result<Self, GameError> {
+        let writer = stream.try_clone().map_err(network_error)?;
+        Ok(Connection {
+            reader: BufReader::new(stream),
+            writer,
+        })
+    }
+
+    fn send(&mut self, line: &str) -> Result<(), GameError> {
+        writeln!(self.writer, "{}", line).map_err(network_error)
+    }
+
+    /// Reads the next non-empty line, or fails if the connection was closed.
+    fn receive(&mut self) -> Result<String, GameError> {
+        loop {
+            let mut line = String::new();
+            let read = self.reader.read_line(&mut line).map_err(network_error)?;
+            if read == 0 {
+                return Err(GameError::Network(
+                    "the other player disconnected".to_string(),
+                ));
+            }
+            if !line.trim().is_empty() {
+                return Ok(line.trim().to_string());
+            }
+        }
+    }
+}
+
+fn network_error(err: std::io::Error) -> GameError {
+    GameError::Network(err.to_string())
+}
+
+/// The host's side of a networked game. The host plays one side of the board and the
+/// joining player plays the other.
+pub struct HostSession {
+    board: Board,
+    remote_player: Player,
+    connection: Connection,
+}
+
+impl HostSession {
+    /// Waits for a player to connect to `listener`, and sends them the starting board.
+    ///
+    /// # Parameters
+    ///
+    /// - `listener`: Where to accept the connection.
+    /// - `board`: The board to play on. The host keeps the real copy.
+    /// - `remote_player`: The side the joining player plays.
+    pub fn accept(
+        listener: &TcpListener,
+        board: Board,
+        remote_player: Player,
+    ) -> Result<Self, GameError> {
+        let (stream, _) = listener.accept().map_err(network_error)?;
+        let mut session = HostSession {
+            board,
+            remote_player,
+            connection: Connection::new(stream)?,
+        };
+
+        session.send(&ServerMessage::Welcome {
+            player: remote_player,
+            position: session.board.to_position_str(),
+        })?;
+        // A finished game loaded from a file is over as soon as it starts.
+        if let BoardState::Ended(winner) = session.board.get_state() {
+            session.send(&ServerMessage::End(winner))?;
+        }
+
+        Ok(session)
+    }
+
+    /// Returns the board of the game.
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// Returns the side the host plays.
+    pub fn local_player(&self) -> Player {
+        self.remote_player.opponent()
+    }
+
+    /// Plays the host's move and sends it to the other player.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(BoardState)` with the state after the move.
+    /// - `Err(GameError)` if the move is illegal (nothing is sent) or the other player has
+    ///   disconnected.
+    pub fn play_local(&mut self, slot: usize) -> Result<BoardState, GameError> {
+        let player_move = Move::create(slot, self.local_player())?;
+        let state = self.board.make_move(player_move)?;
+        self.announce_move(self.local_player(), slot, state)?;
+        Ok(state)
+    }
+
+    /// Waits for the other player's move and plays it.
+    ///
+    /// Illegal moves and unknown messages are answered with `ERROR`, and the host keeps
+    /// waiting for a legal move.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok((slot, BoardState))` with the slot played and the state after the move.
+    /// - `Err(GameError::Network)` if the other player quit or disconnected.
+    pub fn wait_for_remote(&mut self) -> Result<(usize, BoardState), GameError> {
+        loop {
+            let line = self.connection.receive()?;
+            let slot = match ClientMessage::parse(&line) {
+                Ok(ClientMessage::Move(slot)) => slot,
+                Ok(ClientMessage::Quit) => {
+                    return Err(GameError::Network("the other player quit".to_string()))
+                }
+                Err(err) => {
+                    self.send(&ServerMessage::Error(err.to_string()))?;
+                    continue;
+                }
+            };
+
+            let result = Move::create(slot, self.remote_player)
+                .and_then(|player_move| self.board.make_move(player_move));
+            match result {
+                Ok(state) => {
+                    self.announce_move(self.remote_player, slot, state)?;
+                    return Ok((slot, state));
+                }
+                Err(err) => self.send(&ServerMessage::Error(err.to_string()))?,
+            }
+        }
+    }
+
+    fn announce_move(
+        &mut self,
+        player: Player,
+        slot: usize,
+        state: BoardState,
+    ) -> Result<(), GameError> {
+        self.send(&ServerMessage::Moved { player, slot })?;
+        self.send(&ServerMessage::State(self.board.to_position_str()))?;
+        if let BoardState::Ended(winner) = state {
+            self.send(&ServerMessage::End(winner))?;
+        }
+        Ok(())
+    }
+
+    fn send(&mut self, message: &ServerMessage) -> Result<(), GameError> {
+        self.connection.send(&message.to_line())
+    }
+}
+
+/// The joining player's side of a networked game.
+pub struct JoinSession {
+    board: Board,
+    player: Player,
+    connection: Connection,
+}
+
+impl JoinSession {
+    /// Connects to a host and reads the starting board.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(JoinSession)` once the host has sent `WELCOME`.
+    /// - `Err(GameError::Network)` if the host cannot be reached or sends something else.
+    pub fn connect<A: ToSocketAddrs>(address: A) -> Result<Self, GameError> {
+        let stream = TcpStream::connect(address).map_err(network_error)?;
+        let mut connection = Connection::new(stream)?;
+
+        match ServerMessage::parse(&connection.receive()?)? {
+            ServerMessage::Welcome { player, position } => Ok(JoinSession {
+                board: Board::from_position_str(&position)?,
+                player,
+                connection,
+            }),
+            other => Err(invalid(&other.to_line())),
+        }
+    }
+
+    /// Returns the latest board sent by the host.
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// Returns the side this player plays.
+    pub fn player(&self) -> Player {
+        self.player
+    }
+
+    /// Returns true if it is this player's turn.
+    pub fn is_my_turn(&self) -> bool {
+        self.board.get_state() == BoardState::Ongoing && self.board.get_next_player() == self.player
+    }
+
+    /// Sends a move to the host. The host answers with `MOVED` and `STATE` if it is legal,
+    /// or `ERROR` if not; read the answer with `receive`.
+    pub fn send_move(&mut self, slot: usize) -> Result<(), GameError> {
+        self.connection.send(&ClientMessage::Move(slot).to_line())
+    }
+
+    /// Tells the host this player is leaving.
+    pub fn quit(mut self) -> Result<(), GameError> {
+        self.connection.send(&ClientMessage::Quit.to_line())
+    }
+
+    /// Waits for the next message from the host. `MOVED` and `STATE` messages also update
+    /// `board`.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(ServerMessage)` with the message.
+    /// - `Err(GameError::Network)` if the host disconnected or sent something invalid.
+    /// - `Err(GameError::InvalidPosition)` if the host sent an invalid position.
+    pub fn receive(&mut self) -> Result<ServerMessage, GameError> {
+        let message = ServerMessage::parse(&self.connection.receive()?)?;
+        match &message {
+            ServerMessage::Moved { player, slot } => {
+                // Replaying the move keeps the board's history. If it does not fit, the
+                // `STATE` that follows puts the board right.
+                let _ = Move::create(*slot, *player)
+                    .and_then(|player_move| self.board.make_move(player_move));
+            }
+            ServerMessage::State(position) if *position != self.board.to_position_str() => {
+                self.board = Board::from_position_str(position)?;
+            }
+            _ => {}
+        }
+        Ok(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    fn listen() -> (TcpListener, String) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        (listener, address)
+    }
+
+    #[test]
+    fn test_message_round_trip() {
+        let messages = [
+            ServerMessage::Welcome {
+                player: Player::O,
+                position: "X2/3/3 O 3".to_string(),
+            },
+            ServerMessage::Moved {
+                player: Player::X,
+                slot: 5,
+            },
+            ServerMessage::State("3/1X1/3 O 3".to_string()),
+            ServerMessage::Error("Invalid move: position 5 is already taken".to_string()),
+            ServerMessage::End(Some(Player::O)),
+            ServerMessage::End(None),
+        ];
+        for message in messages {
+            assert_eq!(ServerMessage::parse(&message.to_line()), Ok(message));
+        }
+
+        assert_eq!(ClientMessage::parse("move 7"), Ok(ClientMessage::Move(7)));
+        assert_eq!(ClientMessage::parse("QUIT\r\n"), Ok(ClientMessage::Quit));
+        assert!(ClientMessage::parse("MOVE seven").is_err());
+        assert!(ServerMessage::parse("HELLO").is_err());
+        assert!(ServerMessage::parse("END Y").is_err());
+    }
+
+    #[test]
+    fn test_game_over_localhost() {
+        let (listener, address) = listen();
+
+        let client = thread::spawn(move || {
+            let mut session = JoinSession::connect(address).unwrap();
+            assert_eq!(session.player(), Player::O);
+            let mut messages = Vec::new();
+
+            loop {
+                if session.is_my_turn() {
+                    // Play the lowest empty slot.
+                    let slot = (1..=9)
+                        .find(|&slot| session.board().is_slot_empty(slot))
+                        .unwrap();
+                    session.send_move(slot).unwrap();
+                }
+                let message = session.receive().unwrap();
+                messages.push(message.clone());
+                if let ServerMessage::End(_) = message {
+                    assert_eq!(session.board().history().len(), 5);
+                    return (messages, session.board().to_position_str());
+                }
+            }
+        });
+
+        let mut host = HostSession::accept(&listener, Board::new(Player::X), Player::O).unwrap();
+        assert_eq!(host.local_player(), Player::X);
+        // X takes the left column while O fills the top row from slot 2.
+        assert_eq!(host.play_local(1), Ok(BoardState::Ongoing));
+        assert_eq!(host.wait_for_remote(), Ok((2, BoardState::Ongoing)));
+        assert_eq!(host.play_local(4), Ok(BoardState::Ongoing));
+        assert_eq!(host.wait_for_remote(), Ok((3, BoardState::Ongoing)));
+        assert_eq!(host.play_local(7), Ok(BoardState::Ended(Some(Player::X))));
+
+        let (messages, final_position) = client.join().unwrap();
+        assert_eq!(messages.last(), Some(&ServerMessage::End(Some(Player::X))));
+        assert_eq!(final_position, host.board().to_position_str());
+        assert_eq!(host.board().history().len(), 5);
+        assert_eq!(
+            messages[0],
+            ServerMessage::Moved {
+                player: Player::X,
+                slot: 1
+            }
+        );
+    }
+
+    #[test]
+    fn test_illegal_moves_are_rejected() {
+        let (listener, address) = listen();
+
+        let client = thread::spawn(move || {
+            let mut session = JoinSession::connect(address).unwrap();
+            let mut errors = 0;
+            // Wait for the host's first move.
+            while !matches!(session.receive().unwrap(), ServerMessage::State(_)) {}
+            assert!(session.is_my_turn());
+
+            for line in ["MOVE 1", "MOVE 10", "JUMP", "MOVE 0", "MOVE 2"] {
+                session.connection.send(line).unwrap();
+                loop {
+                    match session.receive().unwrap() {
+                        ServerMessage::Error(_) => {
+                            errors += 1;
+                            break;
+                        }
+                        ServerMessage::State(_) => break,
+                        _ => {}
+                    }
+                }
+            }
+            (errors, session.board().to_position_str())
+        });
+
+        let mut host = HostSession::accept(&listener, Board::new(Player::O), Player::X).unwrap();
+        assert_eq!(host.play_local(1), Ok(BoardState::Ongoing));
+        assert_eq!(host.wait_for_remote(), Ok((2, BoardState::Ongoing)));
+
+        let (errors, position) = client.join().unwrap();
+        assert_eq!(errors, 4);
+        assert_eq!(position, "OX1/3/3 O 3");
+    }
+
+    #[test]
+    fn test_disconnects() {
+        let (listener, address) = listen();
+
+        // The joining player leaves on their first turn.
+        let client = thread::spawn(move || {
+            let session = JoinSession::connect(address).unwrap();
+            drop(session);
+        });
+        let mut host = HostSession::accept(&listener, Board::new(Player::O), Player::O).unwrap();
+        client.join().unwrap();
+        assert!(matches!(host.wait_for_remote(), Err(GameError::Network(_))));
+
+        // The host goes away while the joining player waits.
+        let (listener, address) = listen();
+        let host = thread::spawn(move || {
+            HostSession::accept(&listener, Board::new(Player::X), Player::O).unwrap();
+        });
+        let mut session = JoinSession::connect(address).unwrap();
+        host.join().unwrap();
+        assert!(matches!(session.receive(), Err(GameError::Network(_))));
+    }
+
+    #[test]
+    fn test_quit() {
+        let (listener, address) = listen();
+        let client = thread::spawn(move || {
+            JoinSession::connect(address).unwrap().quit().unwrap();
+        });
+
+        let mut host = HostSession::accept(&listener, Board::new(Player::O), Player::O).unwrap();
+        client.join().unwrap();
+        assert_eq!(
+            host.wait_for_remote(),
+            Err(GameError::Network("the other player quit".to_string()))
+        );
+    }
+}
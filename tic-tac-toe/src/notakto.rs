@@ -0,0 +1,732 @@
+// notakto.rs
+
+//! Notakto, X-only Tic Tac Toe played on several 3x3 `Board`s.
+//!
+//! Both players put an `X` on any live board. A board is dead as soon as it has three in a
+//! row, and no more moves can be made on it. Whoever kills the last live board loses.
+//!
+//! Boards and cells are numbered from 1, cells row by row like the positions of a `Board`.
+//!
+//! `NotaktoBrain` plays perfectly with the misère quotient found by Thane Plambeck and Greg
+//! Whitman in "The Secrets of Notakto". Every position of a single board has a value in a
+//! commutative monoid of 18 elements, and a game of several boards is worth the product of
+//! their values. The player to move loses exactly when that product is one of `a`, `b²`,
+//! `bc` and `c²`.
+//!
+//! ```
+//! use tic_tac_toe::notakto::{NotaktoBoard, NotaktoBrain, NotaktoMove};
+//! use tic_tac_toe::{BoardState, Player};
+//!
+//! let mut game = NotaktoBoard::with_boards(Player::X, 2)?;
+//! // The first player puts an X in the centre of board 1.
+//! game.make_move(NotaktoMove::create(1, 5, Player::X)?)?;
+//! assert!(!game.value().is_losing());
+//!
+//! let reply = NotaktoBrain.make_move(&game)?;
+//! assert_eq!(game.make_move(reply)?, BoardState::Ongoing);
+//! assert!(game.value().is_losing());
+//! println!("{}", game.display());
+//! # Ok::<(), tic_tac_toe::GameError>(())
+//! ```
+use crate::error::GameError;
+use crate::tic_tac_toe::{Board, BoardState, Move, Player, BOARD_SIZE};
+use crate::zobrist::Symmetry;
+use colored::Colorize;
+use std::fmt;
+
+/// The number of boards a game is played on unless another number is chosen.
+pub const NOTAKTO_BOARDS: usize = 3;
+
+/// Number of cells on each board.
+const CELLS: usize = BOARD_SIZE * BOARD_SIZE;
+
+/// The value of every live board, up to rotation and reflection, from "The Secrets of
+/// Notakto". Rows are separated by `/`, and `X` marks a filled cell.
+const BOARD_VALUES: [(&str, NotaktoValue); 46] = [
+    (".../.../...", NotaktoValue::C),
+    ("X../.../...", NotaktoValue::ONE),
+    (".X./.../...", NotaktoValue::ONE),
+    (".../.X./...", NotaktoValue::CC),
+    ("XX./.../...", NotaktoValue::D),
+    ("X.X/.../...", NotaktoValue::B),
+    (".X./X../...", NotaktoValue::A),
+    ("..X/X../...", NotaktoValue::B),
+    ("X../.X./...", NotaktoValue::B),
+    (".X./.X./...", NotaktoValue::B),
+    (".../X.X/...", NotaktoValue::A),
+    ("..X/.../X..", NotaktoValue::A),
+    ("XX./X../...", NotaktoValue::B),
+    ("X.X/X../...", NotaktoValue::A),
+    (".XX/X../...", NotaktoValue::AD),
+    ("XX./.X./...", NotaktoValue::AB),
+    ("X.X/.X./...", NotaktoValue::A),
+    (".X./XX./...", NotaktoValue::AB),
+    ("..X/XX./...", NotaktoValue::A),
+    ("X../X.X/...", NotaktoValue::AD),
+    (".X./X.X/...", NotaktoValue::B),
+    ("X.X/.../X..", NotaktoValue::AB),
+    (".XX/.../X..", NotaktoValue::AD),
+    ("X../..X/X..", NotaktoValue::A),
+    (".X./..X/X..", NotaktoValue::ONE),
+    ("XX./XX./...", NotaktoValue::A),
+    ("X.X/XX./...", NotaktoValue::B),
+    (".XX/XX./...", NotaktoValue::B),
+    ("XX./X.X/...", NotaktoValue::A),
+    ("X.X/X.X/...", NotaktoValue::B),
+    (".XX/X../X..", NotaktoValue::AB),
+    ("XX./..X/X..", NotaktoValue::B),
+    ("X.X/..X/X..", NotaktoValue::B),
+    (".XX/..X/X..", NotaktoValue::A),
+    (".X./X.X/X..", NotaktoValue::AB),
+    ("..X/X.X/X..", NotaktoValue::A),
+    ("X../.XX/X..", NotaktoValue::B),
+    (".X./.XX/X..", NotaktoValue::B),
+    (".X./X.X/.X.", NotaktoValue::A),
+    ("X.X/.../X.X", NotaktoValue::A),
+    (".XX/X.X/X..", NotaktoValue::B),
+    ("XX./.XX/X..", NotaktoValue::A),
+    ("XX./X.X/.X.", NotaktoValue::B),
+    ("X.X/X.X/.X.", NotaktoValue::A),
+    ("X.X/..X/XX.", NotaktoValue::A),
+    (".XX/X.X/XX.", NotaktoValue::A),
+];
+
+/// An element of the misère quotient of Notakto, the monoid
+///
+/// `⟨a, b, c, d | a² = 1, b³ = b, b²c = c, c³ = ac², b²d = d, cd = ad, d² = c²⟩`.
+///
+/// It is kept as the powers of `a`, `b`, `c` and `d` in its shortest form, so equal elements
+/// compare equal.
+#[derive(Eq, PartialEq, Debug, Clone, Copy, Hash)]
+pub struct NotaktoValue {
+    a: u8,
+    b: u8,
+    c: u8,
+    d: u8,
+}
+
+impl NotaktoValue {
+    /// The value of a game with no live boards.
+    pub const ONE: NotaktoValue = NotaktoValue::new(0, 0, 0, 0);
+    pub const A: NotaktoValue = NotaktoValue::new(1, 0, 0, 0);
+    pub const B: NotaktoValue = NotaktoValue::new(0, 1, 0, 0);
+    pub const C: NotaktoValue = NotaktoValue::new(0, 0, 1, 0);
+    pub const D: NotaktoValue = NotaktoValue::new(0, 0, 0, 1);
+    pub const AB: NotaktoValue = NotaktoValue::new(1, 1, 0, 0);
+    pub const AD: NotaktoValue = NotaktoValue::new(1, 0, 0, 1);
+    pub const CC: NotaktoValue = NotaktoValue::new(0, 0, 2, 0);
+
+    const fn new(a: u8, b: u8, c: u8, d: u8) -> Self {
+        NotaktoValue { a, b, c, d }
+    }
+
+    /// Returns the value of a game made of the boards worth `self` and `other`.
+    pub fn times(self, other: NotaktoValue) -> NotaktoValue {
+        let (mut a, mut b, mut c, mut d) = (
+            self.a + other.a,
+            self.b + other.b,
+            self.c + other.c,
+            self.d + other.d,
+        );
+
+        // d² = c²
+        c += d / 2 * 2;
+        d %= 2;
+        if d == 1 {
+            // cd = ad and b²d = d
+            a += c;
+            c = 0;
+            b %= 2;
+        } else if c > 0 {
+            // c³ = ac² and b²c = c
+            if c > 2 {
+                a += c - 2;
+                c = 2;
+            }
+            b %= 2;
+        } else if b > 2 {
+            // b³ = b
+            b = 2 - b % 2;
+        }
+
+        NotaktoValue::new(a % 2, b, c, d)
+    }
+
+    /// Returns true if the player to move in a game worth this value loses with best play.
+    pub fn is_losing(self) -> bool {
+        [
+            NotaktoValue::A,
+            NotaktoValue::B.times(NotaktoValue::B),
+            NotaktoValue::B.times(NotaktoValue::C),
+            NotaktoValue::CC,
+        ]
+        .contains(&self)
+    }
+}
+
+/// Returns the filled cells of a 3x3 board as bits, cell 1 in the lowest bit.
+fn board_mask(board: &Board) -> u16 {
+    (1..=CELLS)
+        .filter(|&position| board.get_player_at(position).is_some())
+        .fold(0, |mask, position| mask | 1 << (position - 1))
+}
+
+/// Returns the filled cells of a pattern in `BOARD_VALUES` as bits, like `board_mask`.
+fn pattern_mask(pattern: &str) -> u16 {
+    pattern
+        .chars()
+        .filter(|&c| c != '/')
+        .enumerate()
+        .filter(|&(_, c)| c == 'X')
+        .fold(0, |mask, (index, _)| mask | 1 << index)
+}
+
+/// Returns the smallest mask among the rotations and reflections of `mask`.
+fn canonical_mask(mask: u16) -> u16 {
+    Symmetry::all(BOARD_SIZE, BOARD_SIZE)
+        .iter()
+        .map(|symmetry| {
+            (0..CELLS)
+                .filter(|index| mask & 1 << index != 0)
+                .map(|index| {
+                    let (row, col) = symmetry.apply(
+                        index / BOARD_SIZE,
+                        index % BOARD_SIZE,
+                        BOARD_SIZE,
+                        BOARD_SIZE,
+                    );
+                    1 << (row * BOARD_SIZE + col)
+                })
+                .fold(0, |mask, bit| mask | bit)
+        })
+        .min()
+        .expect("Boards always have a symmetry")
+}
+
+/// Returns the value of a board. Dead boards can no longer be played, so they are worth one.
+fn board_value(board: &Board) -> NotaktoValue {
+    if board.get_state() != BoardState::Ongoing {
+        return NotaktoValue::ONE;
+    }
+
+    let mask = canonical_mask(board_mask(board));
+    BOARD_VALUES
+        .iter()
+        .find(|(pattern, _)| canonical_mask(pattern_mask(pattern)) == mask)
+        .map(|&(_, value)| value)
+        .expect("Every live board has a value")
+}
+
+/// A move in Notakto: an X placed on a cell of one of the boards, and the player placing it.
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub struct NotaktoMove {
+    /// The board the move is made on (1-based).
+    board: usize,
+    /// The cell on that board, always with an X.
+    cell: Move,
+    /// The player making the move.
+    player: Player,
+}
+
+impl NotaktoMove {
+    /// Creates a new move where `player` puts an X on cell `position` of board `board`.
+    /// Returns `GameError::PositionOutOfRange` if either number is 0. The upper bounds are
+    /// checked by `NotaktoBoard::make_move`.
+    pub fn create(board: usize, position: usize, player: Player) -> Result<Self, GameError> {
+        if board == 0 {
+            return Err(GameError::PositionOutOfRange(board));
+        }
+        Ok(NotaktoMove {
+            board,
+            cell: Move::create(position, Player::X)?,
+            player,
+        })
+    }
+
+    /// Returns the board the move is made on (1-based).
+    pub fn board(&self) -> usize {
+        self.board
+    }
+
+    /// Returns the cell on the board (1-based, row by row).
+    pub fn position(&self) -> usize {
+        self.cell.position()
+    }
+
+    /// Returns the player making the move.
+    pub fn player(&self) -> Player {
+        self.player
+    }
+}
+
+/// A Notakto game: the boards and the players taking turns on them.
+#[derive(Debug, Clone)]
+pub struct NotaktoBoard {
+    /// The boards, in order. A board whose state has ended is dead.
+    boards: Vec<Board>,
+    player_1: Player,
+    /// Every move made so far, in the order it was played.
+    history: Vec<NotaktoMove>,
+    /// Moves taken back with `undo`, most recently undone last.
+    undone: Vec<NotaktoMove>,
+}
+
+impl NotaktoBoard {
+    /// Creates a new game on `NOTAKTO_BOARDS` empty boards, where `player_1` moves first.
+    pub fn new(player_1: Player) -> Self {
+        NotaktoBoard::with_boards(player_1, NOTAKTO_BOARDS)
+            .expect("The default number of boards is valid")
+    }
+
+    /// Creates a new game on `boards` empty boards, where `player_1` moves first.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(NotaktoBoard)` if there is at least one board.
+    /// - `Err(GameError::InvalidBoardCount)` if `boards` is 0.
+    pub fn with_boards(player_1: Player, boards: usize) -> Result<Self, GameError> {
+        if boards == 0 {
+            return Err(GameError::InvalidBoardCount(boards));
+        }
+        Ok(NotaktoBoard {
+            boards: vec![Board::new(Player::X); boards],
+            player_1,
+            history: Vec::new(),
+            undone: Vec::new(),
+        })
+    }
+
+    /// Returns the number of boards, dead or alive.
+    pub fn board_count(&self) -> usize {
+        self.boards.len()
+    }
+
+    /// Returns board `board` (1-based), or `None` if there is no such board.
+    pub fn board(&self, board: usize) -> Option<&Board> {
+        board
+            .checked_sub(1)
+            .and_then(|index| self.boards.get(index))
+    }
+
+    /// Returns the boards that can still be played on, in order.
+    pub fn live_boards(&self) -> Vec<usize> {
+        (1..=self.boards.len())
+            .filter(|&board| self.boards[board - 1].get_state() == BoardState::Ongoing)
+            .collect()
+    }
+
+    /// Returns the player who moved first.
+    pub fn first_player(&self) -> Player {
+        self.player_1
+    }
+
+    /// Returns the player whose turn it is.
+    pub fn get_next_player(&self) -> Player {
+        if self.history.len().is_multiple_of(2) {
+            self.player_1
+        } else {
+            self.player_1.opponent()
+        }
+    }
+
+    /// Returns the value of the game in the misère quotient, the product of the values of
+    /// its live boards. The player to move loses with best play if `is_losing` is true.
+    pub fn value(&self) -> NotaktoValue {
+        self.boards
+            .iter()
+            .map(board_value)
+            .fold(NotaktoValue::ONE, NotaktoValue::times)
+    }
+
+    /// Makes a move.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(BoardState::Ended(Some(player)))` if the move killed the last live board, so
+    ///   `player`, the opponent of the player who made it, has won.
+    /// - `Ok(BoardState::Ongoing)` if the game continues after the move.
+    /// - `Err(GameError::GameOver)` if the game has already ended.
+    /// - `Err(GameError::WrongPlayer)` if it is the other player's turn.
+    /// - `Err(GameError::PositionOutOfRange)` if the board or the cell does not exist.
+    /// - `Err(GameError::BoardFinished)` if the board is already dead.
+    /// - `Err(GameError::SlotOccupied)` if the cell already holds an X.
+    pub fn make_move(&mut self, player_move: NotaktoMove) -> Result<BoardState, GameError> {
+        if self.get_state() != BoardState::Ongoing {
+            return Err(GameError::GameOver);
+        }
+        if player_move.player != self.get_next_player() {
+            return Err(GameError::WrongPlayer {
+                expected: self.get_next_player(),
+                found: player_move.player,
+            });
+        }
+
+        let board = self
+            .boards
+            .get_mut(player_move.board - 1)
+            .ok_or(GameError::PositionOutOfRange(player_move.board))?;
+        if board.get_state() != BoardState::Ongoing {
+            return Err(GameError::BoardFinished(player_move.board));
+        }
+        board.make_move_out_of_turn(player_move.cell)?;
+
+        self.undone.clear();
+        self.history.push(player_move);
+        Ok(self.get_state())
+    }
+
+    /// Returns the current state of the game.
+    ///
+    /// # Returns
+    ///
+    /// - `BoardState::Ended(Some(player))` if every board is dead, and `player` did not kill
+    ///   the last one. Notakto never ends in a tie.
+    /// - `BoardState::Ongoing` if the game is still being played.
+    pub fn get_state(&self) -> BoardState {
+        match self.history.last() {
+            Some(last) if self.live_boards().is_empty() => {
+                BoardState::Ended(Some(last.player.opponent()))
+            }
+            _ => BoardState::Ongoing,
+        }
+    }
+
+    /// Returns every move made so far, in the order it was played.
+    pub fn history(&self) -> &[NotaktoMove] {
+        &self.history
+    }
+
+    /// Takes back the last move.
+    ///
+    /// # Returns
+    ///
+    /// - `Some(NotaktoMove)` with the move that was taken back.
+    /// - `None` if no moves have been made.
+    pub fn undo(&mut self) -> Option<NotaktoMove> {
+        let player_move = self.history.pop()?;
+        self.boards[player_move.board - 1].undo();
+        self.undone.push(player_move);
+        Some(player_move)
+    }
+
+    /// Plays again the last move taken back with `undo`.
+    ///
+    /// # Returns
+    ///
+    /// - `Some(BoardState)` with the state of the game after the move is replayed.
+    /// - `None` if there is no move to redo.
+    pub fn redo(&mut self) -> Option<BoardState> {
+        let player_move = self.undone.pop()?;
+        let undone = std::mem::take(&mut self.undone);
+        let state = self
+            .make_move(player_move)
+            .expect("Undone moves can always be replayed");
+        self.undone = undone;
+        Some(state)
+    }
+
+    /// Returns a value that draws the boards side by side in the terminal with `{}`.
+    ///
+    /// Empty cells of live boards show their number, and the line that killed a dead board
+    /// is highlighted.
+    pub fn display(&self) -> NotaktoBoardDisplay<'_> {
+        NotaktoBoardDisplay { game: self }
+    }
+}
+
+/// A brain that plays Notakto perfectly with the misère quotient.
+///
+/// It moves to a position whose value is losing for the opponent whenever there is one.
+/// Otherwise it cannot win against best play, and it avoids killing the last board for as
+/// long as it can.
+pub struct NotaktoBrain;
+
+impl NotaktoBrain {
+    /// Chooses a move for the next player of `game`.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(NotaktoMove)` with the chosen move.
+    /// - `Err(GameError::GameOver)` if the game has already ended.
+    pub fn make_move(&self, game: &NotaktoBoard) -> Result<NotaktoMove, GameError> {
+        if game.get_state() != BoardState::Ongoing {
+            return Err(GameError::GameOver);
+        }
+
+        let player = game.get_next_player();
+        let mut fallback: Option<(NotaktoMove, bool)> = None;
+        for number in game.live_boards() {
+            let board = &game.boards[number - 1];
+            let others = game
+                .boards
+                .iter()
+                .enumerate()
+                .filter(|&(index, _)| index != number - 1)
+                .map(|(_, other)| board_value(other))
+                .fold(NotaktoValue::ONE, NotaktoValue::times);
+
+            for position in (1..=CELLS).filter(|&position| board.is_slot_empty(position)) {
+                let player_move = NotaktoMove::create(number, position, player)?;
+                let mut after = board.clone();
+                after.make_move_out_of_turn(player_move.cell)?;
+
+                let value = others.times(board_value(&after));
+                if value.is_losing() {
+                    return Ok(player_move);
+                }
+                // Only the move that kills the last board leaves a game worth one.
+                let loses_now = value == NotaktoValue::ONE && others == NotaktoValue::ONE;
+                if fallback.is_none_or(|(_, fallback_loses)| fallback_loses && !loses_now) {
+                    fallback = Some((player_move, loses_now));
+                }
+            }
+        }
+
+        fallback
+            .map(|(player_move, _)| player_move)
+            .ok_or_else(|| GameError::BrainFailure("No available positions".to_string()))
+    }
+}
+
+/// Draws a Notakto game in the terminal. Created by `NotaktoBoard::display`.
+pub struct NotaktoBoardDisplay<'a> {
+    game: &'a NotaktoBoard,
+}
+
+impl fmt::Display for NotaktoBoardDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let game = self.game;
+        let width = BOARD_SIZE * 2 + 1;
+
+        let numbers: Vec<String> = (1..=game.boards.len())
+            .map(|number| format!("{:^width$}", number, width = width))
+            .collect();
+        writeln!(f, "{}", numbers.join(" "))?;
+
+        for row in 0..BOARD_SIZE {
+            let mut boards = Vec::new();
+            for board in &game.boards {
+                let dead_line: Vec<usize> = board
+                    .winning_lines()
+                    .iter()
+                    .flat_map(|line| line.positions().to_vec())
+                    .collect();
+                let mut cells = String::from(" ");
+                for col in 0..BOARD_SIZE {
+                    let position = row * BOARD_SIZE + col + 1;
+                    let text = match board.get_player_at(position) {
+                        Some(_) if dead_line.contains(&position) => "X".red().reversed(),
+                        Some(_) => "X".red(),
+                        None if board.get_state() == BoardState::Ongoing => {
+                            position.to_string().white()
+                        }
+                        None => ".".white(),
+                    };
+                    cells.push_str(&format!("{} ", text));
+                }
+                boards.push(cells);
+            }
+            writeln!(f, "{}", boards.join("|"))?;
+        }
+
+        let live: Vec<String> = game
+            .live_boards()
+            .iter()
+            .map(|board| board.to_string())
+            .collect();
+        if live.is_empty() {
+            write!(f, "Every board is dead")
+        } else {
+            write!(f, "Live boards: {}", live.join(", "))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    /// Plays `(board, position)` moves in order, alternating players from X.
+    fn play(game: &mut NotaktoBoard, moves: &[(usize, usize)]) -> BoardState {
+        let mut state = BoardState::Ongoing;
+        for &(board, position) in moves {
+            let player_move = NotaktoMove::create(board, position, game.get_next_player()).unwrap();
+            state = game.make_move(player_move).unwrap();
+        }
+        state
+    }
+
+    /// Returns true if the player to move loses the game of `boards`, found by searching
+    /// every continuation. Each board is given by its canonical mask, and dead boards are
+    /// left out. A board dies when it fills one of `lines`.
+    fn search_is_losing(
+        boards: Vec<u16>,
+        lines: &[Vec<usize>],
+        seen: &mut HashMap<Vec<u16>, bool>,
+    ) -> bool {
+        if let Some(&losing) = seen.get(&boards) {
+            return losing;
+        }
+
+        // With no live boards left, the previous player killed the last one and lost.
+        let mut losing = !boards.is_empty();
+        'search: for index in 0..boards.len() {
+            for cell in (0..CELLS).filter(|cell| boards[index] & 1 << cell == 0) {
+                let mut next = boards.clone();
+                let mask = next.remove(index) | 1 << cell;
+                let dead = lines
+                    .iter()
+                    .any(|line| line.iter().all(|position| mask & 1 << (position - 1) != 0));
+                if !dead {
+                    next.push(canonical_mask(mask));
+                    next.sort_unstable();
+                }
+                if search_is_losing(next, lines, seen) {
+                    losing = false;
+                    break 'search;
+                }
+            }
+        }
+
+        seen.insert(boards, losing);
+        losing
+    }
+
+    #[test]
+    fn test_quotient_relations() {
+        let (a, b, c, d) = (
+            NotaktoValue::A,
+            NotaktoValue::B,
+            NotaktoValue::C,
+            NotaktoValue::D,
+        );
+        assert_eq!(a.times(a), NotaktoValue::ONE);
+        assert_eq!(b.times(b).times(b), b);
+        assert_eq!(b.times(b).times(c), c);
+        assert_eq!(c.times(c).times(c), a.times(c).times(c));
+        assert_eq!(b.times(b).times(d), d);
+        assert_eq!(c.times(d), NotaktoValue::AD);
+        assert_eq!(d.times(d), NotaktoValue::CC);
+        assert_eq!(c.times(NotaktoValue::ONE), c);
+
+        // The quotient has 18 elements.
+        let mut elements = vec![NotaktoValue::ONE];
+        let mut next = 0;
+        while next < elements.len() {
+            for generator in [a, b, c, d] {
+                let element = elements[next].times(generator);
+                if !elements.contains(&element) {
+                    elements.push(element);
+                }
+            }
+            next += 1;
+        }
+        assert_eq!(elements.len(), 18);
+    }
+
+    #[test]
+    fn test_values_match_search() {
+        let live: Vec<u16> = BOARD_VALUES
+            .iter()
+            .map(|(pattern, _)| canonical_mask(pattern_mask(pattern)))
+            .collect();
+
+        // Every position of up to three boards is lost exactly when its value says so.
+        let lines = Board::new(Player::X).lines();
+        let mut seen = HashMap::new();
+        for (i, &first) in live.iter().enumerate() {
+            for (j, &second) in live.iter().enumerate().skip(i) {
+                for (k, &third) in live.iter().enumerate().skip(j) {
+                    let mut boards = vec![first, second, third];
+                    boards.sort_unstable();
+                    let value = [i, j, k]
+                        .iter()
+                        .map(|&index| BOARD_VALUES[index].1)
+                        .fold(NotaktoValue::ONE, NotaktoValue::times);
+                    assert_eq!(
+                        value.is_losing(),
+                        search_is_losing(boards, &lines, &mut seen),
+                        "{:?}",
+                        [first, second, third]
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_killing_the_last_board_loses() {
+        let mut game = NotaktoBoard::with_boards(Player::X, 2).unwrap();
+        assert_eq!(play(&mut game, &[(1, 1), (1, 2)]), BoardState::Ongoing);
+        assert_eq!(play(&mut game, &[(1, 3)]), BoardState::Ongoing);
+        assert_eq!(game.live_boards(), vec![2]);
+        assert_eq!(
+            game.make_move(NotaktoMove::create(1, 5, Player::O).unwrap()),
+            Err(GameError::BoardFinished(1))
+        );
+        assert_eq!(
+            game.make_move(NotaktoMove::create(3, 5, Player::O).unwrap()),
+            Err(GameError::PositionOutOfRange(3))
+        );
+        assert_eq!(
+            game.make_move(NotaktoMove::create(2, 5, Player::X).unwrap()),
+            Err(GameError::WrongPlayer {
+                expected: Player::O,
+                found: Player::X
+            })
+        );
+
+        // O kills the last board, so X wins.
+        let state = play(&mut game, &[(2, 1), (2, 5), (2, 9)]);
+        assert_eq!(state, BoardState::Ended(Some(Player::X)));
+        assert_eq!(
+            game.make_move(NotaktoMove::create(2, 2, Player::X).unwrap()),
+            Err(GameError::GameOver)
+        );
+
+        game.undo();
+        assert_eq!(game.get_state(), BoardState::Ongoing);
+        assert_eq!(game.live_boards(), vec![2]);
+        assert_eq!(game.redo(), Some(BoardState::Ended(Some(Player::X))));
+        assert_eq!(game.history().len(), 6);
+        assert!(NotaktoBoard::with_boards(Player::X, 0).is_err());
+    }
+
+    #[test]
+    fn test_brain_wins_when_it_can() {
+        // The first player wins on one board, and the second player wins on two.
+        for (boards, winner) in [(1, Player::X), (2, Player::O), (3, Player::X)] {
+            let mut game = NotaktoBoard::with_boards(Player::X, boards).unwrap();
+            while game.get_state() == BoardState::Ongoing {
+                let player_move = NotaktoBrain.make_move(&game).unwrap();
+                game.make_move(player_move).unwrap();
+            }
+            assert_eq!(game.get_state(), BoardState::Ended(Some(winner)));
+        }
+
+        // On a single board, the centre is the only winning move.
+        let game = NotaktoBoard::with_boards(Player::X, 1).unwrap();
+        assert_eq!(
+            NotaktoBrain.make_move(&game),
+            Ok(NotaktoMove::create(1, 5, Player::X).unwrap())
+        );
+        assert_eq!(NotaktoBoard::new(Player::X).board_count(), NOTAKTO_BOARDS);
+    }
+
+    #[test]
+    fn test_display() {
+        colored::control::set_override(false);
+        let mut game = NotaktoBoard::with_boards(Player::X, 2).unwrap();
+        play(&mut game, &[(1, 1), (1, 5), (1, 9), (2, 2)]);
+
+        assert_eq!(
+            game.display().to_string(),
+            "   1       2   \n \
+             X . . | 1 X 3 \n \
+             . X . | 4 5 6 \n \
+             . . X | 7 8 9 \n\
+             Live boards: 2"
+        );
+    }
+}
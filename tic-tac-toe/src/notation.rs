@@ -0,0 +1,402 @@
+// notation.rs
+
+//! Saving and loading games as text.
+//!
+//! A saved game is a list of `Key: value` lines. Blank lines and lines starting with `#`
+//! are ignored, and keys are not case sensitive.
+//!
+//! ```text
+//! # Tic Tac Toe game
+//! Board: 3x3
+//! WinLength: 3
+//! First: X
+//! Moves: 5 1 9 3
+//! Result: *
+//! ```
+//!
+//! - `Board`: The board size as `WIDTHxHEIGHT`, or `WIDTHxHEIGHTxLAYERS` for a board with
+//!   layers like `4x4x4`. Optional, defaults to 3x3.
+//! - `WinLength`: How many marks in a row are needed to win. Optional, defaults to the
+//!   smallest side of the board.
+//! - `Rules`: `Misere` if completing a line loses the game, or `Standard`. Optional,
+//!   defaults to `Standard`, and only written for misère games.
+//! - `First`: The player who moved first, `X` or `O`.
+//! - `Position`: The position string the game started from, see `Board::from_position_str`.
+//!   Optional, and only written when the game did not start from an empty board. `Board`,
+//!   `WinLength`, `Rules` and `First` must agree with it when they are given.
+//! - `Moves`: The positions played, in order, separated by spaces. Players alternate,
+//!   starting with `First`, or with the player to move in `Position`. May be empty.
+//! - `Result`: `X` or `O` if that player won, `Draw` for a tie, or `*` if the game is still
+//!   being played. Optional when reading; if present it must match the moves.
+use crate::error::GameError;
+use crate::tic_tac_toe::{Board, BoardState, Move, Player, BOARD_SIZE};
+
+impl Board {
+    /// Writes the game in the text notation described in the `notation` module.
+    pub fn to_notation(&self) -> String {
+        let moves: Vec<String> = self
+            .history()
+            .iter()
+            .map(|player_move| player_move.position().to_string())
+            .collect();
+
+        let result = match self.get_state() {
+            BoardState::Ended(Some(winner)) => {
+                Player::get_player_char_from_enum(&winner).to_string()
+            }
+            BoardState::Ended(None) => "Draw".to_string(),
+            BoardState::Ongoing => "*".to_string(),
+        };
+
+        let rules = if self.is_misere() {
+            "Rules: Misere\n"
+        } else {
+            ""
+        };
+
+        let position = match self.start_position() {
+            Some(position) => format!("Position: {}\n", position),
+            None => String::new(),
+        };
+
+        format!(
+            "# Tic Tac Toe game\nBoard: {}\nWinLength: {}\n{}First: {}\n{}Moves: {}\nResult: {}\n",
+            self.size_text(),
+            self.win_length(),
+            rules,
+            Player::get_player_char_from_enum(&self.first_player()),
+            position,
+            moves.join(" "),
+            result
+        )
+    }
+
+    /// Returns the position string the game started from, before any move in its history
+    /// was played, or `None` if it started from an empty board.
+    ///
+    /// Boards loaded with `from_position_str` start with marks that are not in the history,
+    /// and their moves can only be replayed from that position.
+    pub fn start_position(&self) -> Option<String> {
+        let start = self.start_board();
+        (start.get_number_of_open_slots() < start.get_number_of_slots())
+            .then(|| start.to_position_str())
+    }
+
+    /// Returns the board as it was before any move in its history was played.
+    pub fn start_board(&self) -> Board {
+        let mut start = self.clone();
+        while start.undo().is_some() {}
+        start
+    }
+
+    /// Reads a game written in the text notation described in the `notation` module.
+    ///
+    /// Every move is replayed with `make_move`, from the `Position` if there is one, so the
+    /// board's history can be undone like a game that was played move by move.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(Board)` with all the moves played.
+    /// - `Err(GameError::InvalidNotation)` if the text is malformed, the position is invalid or
+    ///   does not agree with the other keys, a move is illegal, or the result does not match
+    ///   the moves.
+    /// - `Err(GameError::InvalidBoardSize)` if the board size or win length is not valid.
+    pub fn from_notation(text: &str) -> Result<Board, GameError> {
+        let mut size: Option<(usize, usize, usize)> = None;
+        let mut win_length: Option<usize> = None;
+        let mut misere: Option<bool> = None;
+        let mut first_player: Option<Player> = None;
+        let mut position: Option<Board> = None;
+        let mut moves: Option<Vec<usize>> = None;
+        let mut result: Option<BoardState> = None;
+
+        for (line_number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (key, value) = match line.split_once(':') {
+                Some((key, value)) => (key.trim().to_ascii_lowercase(), value.trim()),
+                None => {
+                    return Err(invalid(format!(
+                        "line {}: expected `Key: value`",
+                        line_number + 1
+                    )))
+                }
+            };
+
+            match key.as_str() {
+                "board" => size = Some(parse_size(value)?),
+                "winlength" => {
+                    win_length = Some(
+                        value
+                            .parse()
+                            .map_err(|_| invalid(format!("invalid win length `{}`", value)))?,
+                    )
+                }
+                "rules" => misere = Some(parse_rules(value)?),
+                "position" => {
+                    position = Some(
+                        Board::from_position_str(value)
+                            .map_err(|err| invalid(format!("invalid position: {}", err)))?,
+                    )
+                }
+                "first" => first_player = Some(parse_player(value)?),
+                "moves" => {
+                    moves = Some(
+                        value
+                            .split_whitespace()
+                            .map(|position| {
+                                position
+                                    .parse()
+                                    .map_err(|_| invalid(format!("invalid move `{}`", position)))
+                            })
+                            .collect::<Result<Vec<usize>, GameError>>()?,
+                    )
+                }
+                "result" => result = Some(parse_result(value)?),
+                _ => {
+                    return Err(invalid(format!(
+                        "line {}: unknown key `{}`",
+                        line_number + 1,
+                        key
+                    )))
+                }
+            }
+        }
+
+        let first_player =
+            first_player.ok_or_else(|| invalid("missing `First` player".to_string()))?;
+        let moves = moves.ok_or_else(|| invalid("missing `Moves`".to_string()))?;
+        let mut board = match position {
+            Some(board) => {
+                let board_size = (board.width(), board.height(), board.layers());
+                if size.is_some_and(|size| size != board_size)
+                    || win_length.is_some_and(|win_length| win_length != board.win_length())
+                    || misere.is_some_and(|misere| misere != board.is_misere())
+                    || first_player != board.first_player()
+                {
+                    return Err(invalid(
+                        "`Position` does not match the other keys".to_string(),
+                    ));
+                }
+                board
+            }
+            None => {
+                let (width, height, layers) = size.unwrap_or((BOARD_SIZE, BOARD_SIZE, 1));
+                let win_length = match win_length {
+                    Some(win_length) => win_length,
+                    None if layers > 1 => width.min(height).min(layers),
+                    None => width.min(height),
+                };
+                Board::with_layers(first_player, width, height, layers, win_length)?
+                    .with_misere(misere.unwrap_or(false))
+            }
+        };
+        for (index, position) in moves.into_iter().enumerate() {
+            Move::create(position, board.get_next_player())
+                .and_then(|player_move| board.make_move(player_move))
+                .map_err(|err| {
+                    invalid(format!(
+                        "illegal move {} (move {}): {}",
+                        position,
+                        index + 1,
+                        err
+                    ))
+                })?;
+        }
+
+        if let Some(result) = result {
+            if result != board.get_state() {
+                return Err(invalid(
+                    "`Result` does not match the moves played".to_string(),
+                ));
+            }
+        }
+
+        Ok(board)
+    }
+}
+
+/// Creates the error returned for text that is not a valid saved game.
+fn invalid(reason: String) -> GameError {
+    GameError::InvalidNotation(reason)
+}
+
+/// Parses a board size written as `WIDTHxHEIGHT` or `WIDTHxHEIGHTxLAYERS`.
+fn parse_size(value: &str) -> Result<(usize, usize, usize), GameError> {
+    let error = || invalid(format!("invalid board size `{}`", value));
+    let sides: Vec<usize> = value
+        .to_ascii_lowercase()
+        .split('x')
+        .map(|side| side.trim().parse())
+        .collect::<Result<_, _>>()
+        .map_err(|_| error())?;
+
+    match sides[..] {
+        [width, height] => Ok((width, height, 1)),
+        [width, height, layers] => Ok((width, height, layers)),
+        _ => Err(error()),
+    }
+}
+
+/// Parses a player written as `X` or `O`.
+fn parse_player(value: &str) -> Result<Player, GameError> {
+    let mut chars = value.chars();
+    match (
+        chars.next().and_then(Player::get_player_enum_from_char),
+        chars.next(),
+    ) {
+        (Some(player), None) => Ok(player),
+        _ => Err(invalid(format!("invalid player `{}`", value))),
+    }
+}
+
+/// Parses the rules written as `Standard` or `Misere`, returning true for misère.
+fn parse_rules(value: &str) -> Result<bool, GameError> {
+    match value.to_ascii_lowercase().as_str() {
+        "standard" => Ok(false),
+        "misere" => Ok(true),
+        _ => Err(invalid(format!("invalid rules `{}`", value))),
+    }
+}
+
+/// Parses a result written as `X`, `O`, `Draw` or `*`.
+fn parse_result(value: &str) -> Result<BoardState, GameError> {
+    match value.to_ascii_lowercase().as_str() {
+        "*" => Ok(BoardState::Ongoing),
+        "draw" => Ok(BoardState::Ended(None)),
+        _ => parse_player(value)
+            .map(|winner| BoardState::Ended(Some(winner)))
+            .map_err(|_| invalid(format!("invalid result `{}`", value))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(board: &mut Board, positions: &[usize]) {
+        for &position in positions {
+            let player_move = Move::create(position, board.get_next_player()).unwrap();
+            board.make_move(player_move).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut board = Board::with_size(Player::O, 4, 5, 3).unwrap();
+        play(&mut board, &[6, 1, 11, 20]);
+
+        let text = board.to_notation();
+        let loaded = Board::from_notation(&text).unwrap();
+
+        assert_eq!(loaded.history(), board.history());
+        assert_eq!(loaded.width(), 4);
+        assert_eq!(loaded.height(), 5);
+        assert_eq!(loaded.win_length(), 3);
+        assert_eq!(loaded.first_player(), Player::O);
+        assert_eq!(loaded.to_notation(), text);
+    }
+
+    #[test]
+    fn test_round_trip_with_layers() {
+        let mut board = Board::qubic(Player::X);
+        play(&mut board, &[1, 2, 22, 3, 43, 4, 64]);
+
+        let text = board.to_notation();
+        assert!(text.contains("Board: 4x4x4\n"));
+        assert!(text.contains("Result: X\n"));
+
+        let loaded = Board::from_notation("Board: 4x4x4\nFirst: X\nMoves: 1 2 22").unwrap();
+        assert_eq!(loaded.layers(), 4);
+        assert_eq!(loaded.win_length(), 4);
+        assert_eq!(Board::from_notation(&text).unwrap().to_notation(), text);
+    }
+
+    #[test]
+    fn test_round_trip_misere() {
+        let mut board = Board::new(Player::X).with_misere(true);
+        play(&mut board, &[1, 4, 2, 5, 3]);
+
+        let text = board.to_notation();
+        assert!(text.contains("Rules: Misere\n"));
+        assert!(text.contains("Result: O\n"));
+        let loaded = Board::from_notation(&text).unwrap();
+        assert!(loaded.is_misere());
+        assert_eq!(loaded.to_notation(), text);
+
+        assert!(!Board::new(Player::X).to_notation().contains("Rules"));
+        assert!(Board::from_notation("Rules: suicide\nFirst: X\nMoves:\n").is_err());
+    }
+
+    #[test]
+    fn test_round_trip_from_position() {
+        let mut board = Board::from_position_str("X1O/1X1/3 O").unwrap();
+        assert!(board.to_notation().contains("Position: X1O/1X1/3 O 3\n"));
+        play(&mut board, &[9, 2]);
+
+        let text = board.to_notation();
+        assert!(text.contains("Position: X1O/1X1/3 O 3\n"));
+        assert!(text.contains("Moves: 9 2\n"));
+        let loaded = Board::from_notation(&text).unwrap();
+        assert_eq!(loaded.to_position_str(), board.to_position_str());
+        assert_eq!(loaded.history(), board.history());
+        assert_eq!(loaded.to_notation(), text);
+
+        assert!(!Board::new(Player::X).to_notation().contains("Position"));
+        assert!(Board::from_notation("First: X\nPosition: X1O/1X1/3 O\nMoves:\n").is_ok());
+        assert!(Board::from_notation("First: O\nPosition: X1O/1X1/3 O\nMoves:\n").is_err());
+        assert!(
+            Board::from_notation("Board: 4x4\nFirst: X\nPosition: X1O/1X1/3 O\nMoves:\n").is_err()
+        );
+        assert!(Board::from_notation("First: X\nPosition: XXX/3/3 O\nMoves:\n").is_err());
+    }
+
+    #[test]
+    fn test_writes_result() {
+        let mut board = Board::new(Player::X);
+        play(&mut board, &[1, 4, 2, 5, 3]);
+
+        assert!(board.to_notation().contains("Result: X\n"));
+        assert!(board.to_notation().contains("Moves: 1 4 2 5 3\n"));
+    }
+
+    #[test]
+    fn test_reads_minimal_game() {
+        let board = Board::from_notation("# comment\n\nfirst: o\nmoves: 5 1\n").unwrap();
+
+        assert_eq!(board.width(), BOARD_SIZE);
+        assert_eq!(board.first_player(), Player::O);
+        assert_eq!(board.history().len(), 2);
+        assert_eq!(board.get_next_player(), Player::O);
+    }
+
+    #[test]
+    fn test_rejects_illegal_moves() {
+        assert!(Board::from_notation("First: X\nMoves: 5 5\n").is_err());
+        assert!(Board::from_notation("First: X\nMoves: 10\n").is_err());
+        assert!(Board::from_notation("First: X\nMoves: 0\n").is_err());
+        // The game is over after X completes the top row.
+        assert!(Board::from_notation("First: X\nMoves: 1 4 2 5 3 6\n").is_err());
+    }
+
+    #[test]
+    fn test_rejects_wrong_result() {
+        assert!(Board::from_notation("First: X\nMoves: 1 4 2 5 3\nResult: O\n").is_err());
+        assert!(Board::from_notation("First: X\nMoves: 1 4 2 5 3\nResult: *\n").is_err());
+        assert!(Board::from_notation("First: X\nMoves: 1 4 2 5 3\nResult: X\n").is_ok());
+    }
+
+    #[test]
+    fn test_rejects_malformed_text() {
+        assert!(Board::from_notation("Moves: 1\n").is_err());
+        assert!(Board::from_notation("First: X\n").is_err());
+        assert!(Board::from_notation("First: Y\nMoves:\n").is_err());
+        assert!(Board::from_notation("First: X\nMoves: a\n").is_err());
+        assert!(Board::from_notation("First: X\nMoves:\nBoard: 3by3\n").is_err());
+        assert!(Board::from_notation("First: X\nMoves:\nColour: red\n").is_err());
+        assert!(Board::from_notation("First X\nMoves:\n").is_err());
+    }
+}
@@ -0,0 +1,56 @@
+// random.rs
+
+//! Seedable randomness.
+//!
+//! Every random decision in the crate, from `BrainLevelOne`'s moves to Monte Carlo playouts,
+//! is made with a `GameRng`. Creating them all from a known seed makes a game replay exactly,
+//! which is how a surprising game or a failing test can be looked at again.
+//!
+//! ```
+//! use tic_tac_toe::random::seeded_rng;
+//! use tic_tac_toe::{Board, BrainLevelOne, Player, TicTacToeBrain};
+//!
+//! let board = Board::new(Player::X);
+//! let brain_1 = BrainLevelOne::from_rng(seeded_rng(42));
+//! let brain_2 = BrainLevelOne::new(42);
+//! assert_eq!(brain_1.make_move(&board)?, brain_2.make_move(&board)?);
+//! # Ok::<(), tic_tac_toe::GameError>(())
+//! ```
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+/// The random number generator used for every random decision.
+///
+/// The same seed gives the same numbers every time, as long as the `rand` version does
+/// not change.
+pub type GameRng = StdRng;
+
+/// Creates a generator that always produces the same numbers for the same `seed`.
+pub fn seeded_rng(seed: u64) -> GameRng {
+    GameRng::seed_from_u64(seed)
+}
+
+/// Returns a new seed from the operating system.
+///
+/// This is the only place randomness enters the crate without a seed. Keep the seed it
+/// returns, e.g. by printing it, to be able to replay what it was used for.
+pub fn random_seed() -> u64 {
+    rand::random()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn test_same_seed_same_numbers() {
+        let numbers = |seed| {
+            let mut rng = seeded_rng(seed);
+            (0..10).map(|_| rng.gen_range(0..100)).collect::<Vec<u32>>()
+        };
+
+        assert_eq!(numbers(1), numbers(1));
+        assert_ne!(numbers(1), numbers(2));
+    }
+}
@@ -0,0 +1,1196 @@
+// server.rs
+
+//! A server hosting many games at once, with a lobby.
+//!
+//! Every client connects to the lobby first. From there it can list the games, create a game
+//! with its own board size and rules, join a game as a player, or watch one as a spectator.
+//! `PLAY` finds an opponent automatically: it joins a game another client is waiting in, or
+//! waits for one itself, and if nobody turns up the server's own computer player takes the
+//! other seat.
+//!
+//! Each client is served by its own thread. The games live in one shared lobby, and every
+//! move goes through `Board::make_move` on the server, like in a hosted game (see
+//! `network`). A client that stops reading its messages is disconnected, so it cannot hold
+//! up the other games.
+//!
+//! The protocol is one line per message. Clients send:
+//!
+//! - `LIST`: Lists the games, one `GAME` line each, followed by `OK`.
+//! - `CREATE [OPTIONS]`: Creates a game and takes a seat in it.
+//! - `PLAY [OPTIONS]`: Finds an opponent, as described above.
+//! - `JOIN <id>`: Takes the open seat in a game.
+//! - `WATCH <id>`: Watches a game.
+//! - `MOVE <slot>`: Plays a slot in the game the client is seated in.
+//! - `LEAVE`: Goes back to the lobby. Leaving a game in progress forfeits it.
+//! - `QUIT`: Disconnects, leaving any game first.
+//!
+//! `OPTIONS` are `key=value` words, all optional: `size=N` or `size=WxH` (default 3x3),
+//! `win=K` (the shorter side), `first=X|O` (X), `side=X|O` for the side to play (X) and
+//! `level=N` to play the server's computer player at level N. With `PLAY`, `level` is the
+//! level of the computer player used if no one else wants to play (level 3 unless the
+//! server is set up with another), and `side` only counts if the client ends up waiting for
+//! an opponent. Boards larger than `MAX_BOARD_SIDE` on a side are refused with an `ERROR`.
+//! A level whose computer player would take too long to move on the board, like the full
+//! search of level 2 on a 4x4 board, is refused with an `ERROR`.
+//!
+//! Besides the in-game messages of `network::ServerMessage` (`WELCOME`, `MOVED`, `STATE`,
+//! `ERROR` and `END`), the server sends:
+//!
+//! - `GAME <id> <W>x<H> <win> <first> <X seat> <O seat> <spectators> <status>`: One game in
+//!   a `LIST`. Seats are `open`, `human` or `brain`, and the status is `waiting`, `playing`,
+//!   `over` or `abandoned`.
+//! - `OK`: The end of a `LIST`, or a `LEAVE` done.
+//! - `CREATED <id>`: A game was created. `WELCOME` follows.
+//! - `JOINED <player> human|brain`: Someone took the seat of `<player>`.
+//! - `LEFT <player>`: The player of `<player>` left.
+//! - `WATCHING <id> <position>`: Sent to a spectator, with the current position.
+use crate::brains::TicTacToeBrain;
+use crate::error::GameError;
+use crate::network::{parse_player, ClientMessage, ServerMessage};
+use crate::random::{seeded_rng, GameRng};
+use crate::tic_tac_toe::{Board, BoardState, Move, Player, BOARD_SIZE};
+use rand::Rng;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::Duration;
+
+/// Creates the server's computer player for a level, with a seed for its random choices.
+/// Returns `None` if there is no such level.
+pub type BrainFactory = fn(usize, u64) -> Option<Box<dyn TicTacToeBrain + Send + Sync>>;
+
+/// The board a game is played on.
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub struct GameRules {
+    pub width: usize,
+    pub height: usize,
+    pub win_length: usize,
+    pub first_player: Player,
+}
+
+impl Default for GameRules {
+    /// The classic 3x3 board with X moving first.
+    fn default() -> Self {
+        GameRules {
+            width: BOARD_SIZE,
+            height: BOARD_SIZE,
+            win_length: BOARD_SIZE,
+            first_player: Player::X,
+        }
+    }
+}
+
+impl GameRules {
+    /// Returns an empty board with these rules, or an error if the size is not valid.
+    pub fn new_board(&self) -> Result<Board, GameError> {
+        Board::with_size(self.first_player, self.width, self.height, self.win_length)
+    }
+}
+
+/// What a client asks for when it creates a game or looks for one.
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub struct GameRequest {
+    pub rules: GameRules,
+    /// The side the client plays.
+    pub side: Player,
+    /// The level of the computer opponent, if the client wants one.
+    pub level: Option<usize>,
+}
+
+impl GameRequest {
+    /// Reads the `key=value` options of `CREATE` and `PLAY`.
+    fn parse(options: &str) -> Result<Self, GameError> {
+        let mut request = GameRequest {
+            rules: GameRules::default(),
+            side: Player::X,
+            level: None,
+        };
+        let mut win_length = None;
+
+        for option in options.split_whitespace() {
+            let invalid = || GameError::Network(format!("invalid option `{}`", option));
+            let (key, value) = option.split_once('=').ok_or_else(invalid)?;
+
+            match key.to_ascii_lowercase().as_str() {
+                "size" => {
+                    let value = value.to_ascii_lowercase();
+                    let (width, height) = value.split_once('x').unwrap_or((&value, &value));
+                    request.rules.width = width.parse().map_err(|_| invalid())?;
+                    request.rules.height = height.parse().map_err(|_| invalid())?;
+                }
+                "win" => win_length = Some(value.parse().map_err(|_| invalid())?),
+                "first" => request.rules.first_player = parse_player(value).ok_or_else(invalid)?,
+                "side" => request.side = parse_player(value).ok_or_else(invalid)?,
+                "level" => request.level = Some(value.parse().map_err(|_| invalid())?),
+                _ => return Err(invalid()),
+            }
+        }
+
+        request.rules.win_length =
+            win_length.unwrap_or(request.rules.width.min(request.rules.height));
+        Ok(request)
+    }
+}
+
+/// A line sent by a client.
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub enum Command {
+    List,
+    Create(GameRequest),
+    Play(GameRequest),
+    Join(u32),
+    Watch(u32),
+    Leave,
+    /// `MOVE` and `QUIT`, which mean the same as in a hosted game.
+    Game(ClientMessage),
+}
+
+impl Command {
+    /// Reads a line sent by a client. Commands are not case sensitive.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(Command)` if the line is a valid command.
+    /// - `Err(GameError::Network)` if it is not.
+    pub fn parse(line: &str) -> Result<Self, GameError> {
+        let line = line.trim();
+        let (command, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let game_id = || {
+            rest.trim()
+                .parse()
+                .map_err(|_| GameError::Network(format!("invalid game id `{}`", rest.trim())))
+        };
+
+        match command.to_ascii_uppercase().as_str() {
+            "LIST" => Ok(Command::List),
+            "CREATE" => Ok(Command::Create(GameRequest::parse(rest)?)),
+            "PLAY" => Ok(Command::Play(GameRequest::parse(rest)?)),
+            "JOIN" => Ok(Command::Join(game_id()?)),
+            "WATCH" => Ok(Command::Watch(game_id()?)),
+            "LEAVE" => Ok(Command::Leave),
+            _ => ClientMessage::parse(line).map(Command::Game),
+        }
+    }
+}
+
+/// Who sits in a seat, as shown in the lobby.
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub enum SeatKind {
+    Open,
+    Human,
+    Brain,
+}
+
+/// How far along a game is.
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub enum GameStatus {
+    /// A seat is still open.
+    Waiting,
+    Playing,
+    Over,
+    /// A player left before the end.
+    Abandoned,
+}
+
+/// One game in the lobby.
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub struct GameSummary {
+    pub id: u32,
+    pub rules: GameRules,
+    pub x_seat: SeatKind,
+    pub o_seat: SeatKind,
+    pub spectators: usize,
+    pub status: GameStatus,
+}
+
+impl fmt::Display for GameSummary {
+    /// Formats the summary as a `GAME` line.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let seat = |seat| match seat {
+            SeatKind::Open => "open",
+            SeatKind::Human => "human",
+            SeatKind::Brain => "brain",
+        };
+        let status = match self.status {
+            GameStatus::Waiting => "waiting",
+            GameStatus::Playing => "playing",
+            GameStatus::Over => "over",
+            GameStatus::Abandoned => "abandoned",
+        };
+
+        write!(
+            f,
+            "GAME {} {}x{} {} {} {} {} {} {}",
+            self.id,
+            self.rules.width,
+            self.rules.height,
+            self.rules.win_length,
+            Player::get_player_char_from_enum(&self.rules.first_player),
+            seat(self.x_seat),
+            seat(self.o_seat),
+            self.spectators,
+            status
+        )
+    }
+}
+
+type ClientId = u32;
+type SharedBrain = Arc<dyn TicTacToeBrain + Send + Sync>;
+
+enum Seat {
+    Open,
+    Human(ClientId),
+    Brain(SharedBrain),
+}
+
+/// Returns the index of a player's seat.
+fn seat_index(player: Player) -> usize {
+    match player {
+        Player::X => 0,
+        Player::O => 1,
+    }
+}
+
+struct Room {
+    board: Board,
+    rules: GameRules,
+    /// The seats of X and O, in that order.
+    seats: [Seat; 2],
+    spectators: Vec<ClientId>,
+    /// True if the game was created by `PLAY` and is waiting for anyone to join.
+    matchmaking: bool,
+    abandoned: bool,
+}
+
+impl Room {
+    fn status(&self) -> GameStatus {
+        if self.abandoned {
+            GameStatus::Abandoned
+        } else if self.board.get_state() != BoardState::Ongoing {
+            GameStatus::Over
+        } else if self.seats.iter().any(|seat| matches!(seat, Seat::Open)) {
+            GameStatus::Waiting
+        } else {
+            GameStatus::Playing
+        }
+    }
+
+    /// Returns the first open seat, if there is one.
+    fn open_seat(&self) -> Option<Player> {
+        [Player::X, Player::O]
+            .into_iter()
+            .find(|&player| matches!(self.seats[seat_index(player)], Seat::Open))
+    }
+
+    /// Returns every client in the room, players and spectators.
+    fn members(&self) -> Vec<ClientId> {
+        let players = self.seats.iter().filter_map(|seat| match seat {
+            Seat::Human(client) => Some(*client),
+            _ => None,
+        });
+        players.chain(self.spectators.iter().copied()).collect()
+    }
+
+    fn summary(&self, id: u32) -> GameSummary {
+        let kind = |seat: &Seat| match seat {
+            Seat::Open => SeatKind::Open,
+            Seat::Human(_) => SeatKind::Human,
+            Seat::Brain(_) => SeatKind::Brain,
+        };
+
+        GameSummary {
+            id,
+            rules: self.rules,
+            x_seat: kind(&self.seats[0]),
+            o_seat: kind(&self.seats[1]),
+            spectators: self.spectators.len(),
+            status: self.status(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Lobby {
+    games: BTreeMap<u32, Room>,
+    clients: HashMap<ClientId, TcpStream>,
+    next_game: u32,
+    next_client: ClientId,
+}
+
+impl Lobby {
+    /// Sends a line to a client. A client that cannot be reached, or that stops reading for
+    /// longer than `Server::WRITE_TIMEOUT`, is disconnected, since it would hold up everyone
+    /// else waiting for the lobby. Its own thread then finds out and leaves the lobby.
+    fn send(&mut self, client: ClientId, line: &str) {
+        let Some(stream) = self.clients.get_mut(&client) else {
+            return;
+        };
+        if writeln!(stream, "{}", line).is_err() {
+            let _ = stream.shutdown(Shutdown::Both);
+            self.clients.remove(&client);
+        }
+    }
+
+    /// Sends a line to everyone in a game except `skip`.
+    fn broadcast(&mut self, game: u32, line: &str, skip: Option<ClientId>) {
+        let members = self.games.get(&game).map(Room::members).unwrap_or_default();
+        for client in members.into_iter().filter(|&client| Some(client) != skip) {
+            self.send(client, line);
+        }
+    }
+
+    fn new_game_id(&mut self) -> u32 {
+        self.next_game += 1;
+        self.next_game
+    }
+
+    fn new_client_id(&mut self) -> ClientId {
+        self.next_client += 1;
+        self.next_client
+    }
+
+    /// Sends everyone in a game the move just made.
+    fn announce_move(&mut self, game: u32, player_move: &Move, player: Player) {
+        let Some(room) = self.games.get(&game) else {
+            return;
+        };
+        let state = room.board.get_state();
+        let position = room.board.to_position_str();
+
+        let moved = ServerMessage::Moved {
+            player,
+            slot: player_move.position(),
+        };
+        self.broadcast(game, &moved.to_line(), None);
+        self.broadcast(game, &ServerMessage::State(position).to_line(), None);
+        if let BoardState::Ended(winner) = state {
+            self.broadcast(game, &ServerMessage::End(winner).to_line(), None);
+        }
+    }
+}
+
+/// Where a client is.
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+struct Place {
+    game: u32,
+    /// The player the client plays, or `None` for a spectator.
+    player: Option<Player>,
+}
+
+/// Everything a client's thread needs. Cloning it shares the same lobby.
+#[derive(Clone)]
+struct Context {
+    lobby: Arc<Mutex<Lobby>>,
+    rng: Arc<Mutex<GameRng>>,
+    brains: BrainFactory,
+    brain_level: usize,
+    match_wait: Duration,
+}
+
+/// A server hosting games for many clients.
+///
+/// ```no_run
+/// use std::net::TcpListener;
+/// use tic_tac_toe::server::Server;
+/// use tic_tac_toe::{BrainLevelTwo, TicTacToeBrain};
+///
+/// fn brains(_level: usize, _seed: u64) -> Option<Box<dyn TicTacToeBrain + Send + Sync>> {
+///     Some(Box::new(BrainLevelTwo))
+/// }
+///
+/// let server = Server::new(brains, 42);
+/// server.serve(TcpListener::bind("0.0.0.0:7878")?)?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub struct Server {
+    context: Context,
+}
+
+impl Server {
+    /// How long `PLAY` waits for another client before the computer player takes the seat.
+    pub const DEFAULT_MATCH_WAIT: Duration = Duration::from_secs(10);
+
+    /// The level of the computer player `PLAY` falls back on. Level 3 keeps to a time budget,
+    /// so it can take a seat on any board a client asks for, where the full search of level 2
+    /// is refused on boards larger than 3x3.
+    pub const DEFAULT_BRAIN_LEVEL: usize = 3;
+
+    /// How long a message to a client can take before the client is disconnected.
+    pub const WRITE_TIMEOUT: Duration = Duration::from_secs(2);
+
+    /// Creates a server with no games.
+    ///
+    /// # Parameters
+    ///
+    /// - `brains`: Creates the server's computer players.
+    /// - `seed`: Seeds the random choices of the computer players.
+    pub fn new(brains: BrainFactory, seed: u64) -> Self {
+        Server {
+            context: Context {
+                lobby: Arc::new(Mutex::new(Lobby::default())),
+                rng: Arc::new(Mutex::new(seeded_rng(seed))),
+                brains,
+                brain_level: Server::DEFAULT_BRAIN_LEVEL,
+                match_wait: Server::DEFAULT_MATCH_WAIT,
+            },
+        }
+    }
+
+    /// Sets the level of the computer player `PLAY` falls back on when a client does not
+    /// choose one. The default is `DEFAULT_BRAIN_LEVEL`.
+    pub fn with_brain_level(mut self, level: usize) -> Self {
+        self.context.brain_level = level;
+        self
+    }
+
+    /// Sets how long `PLAY` waits for another client before the computer player takes the
+    /// seat.
+    pub fn with_match_wait(mut self, match_wait: Duration) -> Self {
+        self.context.match_wait = match_wait;
+        self
+    }
+
+    /// Returns the games in the lobby, in the order they were created.
+    pub fn games(&self) -> Vec<GameSummary> {
+        let lobby = self.context.lock();
+        lobby
+            .games
+            .iter()
+            .map(|(&id, room)| room.summary(id))
+            .collect()
+    }
+
+    /// Serves clients connecting to `listener`, each on its own thread. Only returns if the
+    /// listener fails.
+    pub fn serve(&self, listener: TcpListener) -> Result<(), GameError> {
+        for stream in listener.incoming() {
+            let stream = stream.map_err(|err| GameError::Network(err.to_string()))?;
+            let context = self.context.clone();
+            thread::spawn(move || context.handle_client(stream));
+        }
+        Ok(())
+    }
+}
+
+impl Context {
+    fn lock(&self) -> MutexGuard<'_, Lobby> {
+        self.lobby.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    fn send(&self, client: ClientId, line: &str) {
+        self.lock().send(client, line);
+    }
+
+    fn error(&self, client: ClientId, reason: &str) {
+        self.send(client, &ServerMessage::Error(reason.to_string()).to_line());
+    }
+
+    /// Reads a client's commands until it quits or disconnects.
+    fn handle_client(&self, stream: TcpStream) {
+        let Ok(writer) = stream.try_clone() else {
+            return;
+        };
+        if writer
+            .set_write_timeout(Some(Server::WRITE_TIMEOUT))
+            .is_err()
+        {
+            return;
+        }
+        let client = {
+            let mut lobby = self.lock();
+            let client = lobby.new_client_id();
+            lobby.clients.insert(client, writer);
+            client
+        };
+        let mut place = None;
+
+        for line in BufReader::new(stream).lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            match Command::parse(&line) {
+                Ok(Command::Game(ClientMessage::Quit)) => break,
+                Ok(command) => self.run(client, &mut place, command),
+                Err(err) => self.error(client, &err.to_string()),
+            }
+        }
+
+        self.leave(client, &mut place);
+        self.lock().clients.remove(&client);
+    }
+
+    fn run(&self, client: ClientId, place: &mut Option<Place>, command: Command) {
+        match command {
+            Command::List => {
+                let mut lobby = self.lock();
+                let lines: Vec<String> = lobby
+                    .games
+                    .iter()
+                    .map(|(&id, room)| room.summary(id).to_string())
+                    .collect();
+                for line in lines {
+                    lobby.send(client, &line);
+                }
+                lobby.send(client, "OK");
+            }
+            Command::Create(request) => {
+                self.leave(client, place);
+                if let Some(game) = self.create(client, place, request, false) {
+                    self.play_brains(game);
+                }
+            }
+            Command::Play(request) => {
+                self.leave(client, place);
+                self.play(client, place, request);
+            }
+            Command::Join(game) => {
+                self.leave(client, place);
+                self.join(client, place, game);
+            }
+            Command::Watch(game) => {
+                self.leave(client, place);
+                let mut lobby = self.lock();
+                match lobby.games.get_mut(&game) {
+                    Some(room) => {
+                        room.spectators.push(client);
+                        let position = room.board.to_position_str();
+                        *place = Some(Place { game, player: None });
+                        lobby.send(client, &format!("WATCHING {} {}", game, position));
+                    }
+                    None => lobby.send(
+                        client,
+                        &ServerMessage::Error(format!("There is no game {}", game)).to_line(),
+                    ),
+                }
+            }
+            Command::Leave => {
+                self.leave(client, place);
+                self.send(client, "OK");
+            }
+            Command::Game(ClientMessage::Move(slot)) => self.make_move(client, place, slot),
+            Command::Game(ClientMessage::Quit) => {}
+        }
+    }
+
+    /// Creates a game and seats the client in it, with a computer opponent if the request
+    /// asks for one.
+    ///
+    /// Returns the id of the game, or `None` if it could not be created.
+    fn create(
+        &self,
+        client: ClientId,
+        place: &mut Option<Place>,
+        request: GameRequest,
+        matchmaking: bool,
+    ) -> Option<u32> {
+        let board = match request.rules.new_board() {
+            Ok(board) => board,
+            Err(err) => {
+                self.error(client, &err.to_string());
+                return None;
+            }
+        };
+
+        let opponent = match request.level {
+            Some(level) => match self.brain_for(level, &board) {
+                Ok(brain) => Seat::Brain(brain),
+                Err(err) => {
+                    self.error(client, &err);
+                    return None;
+                }
+            },
+            None => Seat::Open,
+        };
+        let has_brain = matches!(opponent, Seat::Brain(_));
+
+        let mut seats = [Seat::Open, Seat::Open];
+        seats[seat_index(request.side)] = Seat::Human(client);
+        seats[seat_index(request.side.opponent())] = opponent;
+
+        let mut lobby = self.lock();
+        let game = lobby.new_game_id();
+        let welcome = ServerMessage::Welcome {
+            player: request.side,
+            position: board.to_position_str(),
+        };
+        lobby.games.insert(
+            game,
+            Room {
+                board,
+                rules: request.rules,
+                seats,
+                spectators: Vec::new(),
+                matchmaking,
+                abandoned: false,
+            },
+        );
+        *place = Some(Place {
+            game,
+            player: Some(request.side),
+        });
+
+        lobby.send(client, &format!("CREATED {}", game));
+        lobby.send(client, &welcome.to_line());
+        if has_brain {
+            let joined = format!(
+                "JOINED {} brain",
+                Player::get_player_char_from_enum(&request.side.opponent())
+            );
+            lobby.send(client, &joined);
+        }
+        Some(game)
+    }
+
+    /// Joins a game waiting for an opponent with the same rules, or creates one. If no one
+    /// joins it in time, a computer player takes the open seat.
+    fn play(&self, client: ClientId, place: &mut Option<Place>, request: GameRequest) {
+        let waiting = self.lock().games.iter().find_map(|(&id, room)| {
+            let fits = room.matchmaking
+                && room.status() == GameStatus::Waiting
+                && room.rules == request.rules
+                && room.open_seat().is_some();
+            fits.then_some(id)
+        });
+        if let Some(game) = waiting {
+            self.join(client, place, game);
+            return;
+        }
+
+        // Check the computer player now, so the client does not wait for one that cannot
+        // play.
+        let level = request.level.unwrap_or(self.brain_level);
+        if let Ok(board) = request.rules.new_board() {
+            if let Err(err) = self.brain_for(level, &board) {
+                self.error(client, &err);
+                return;
+            }
+        }
+        let request = GameRequest {
+            level: None,
+            ..request
+        };
+        if let Some(game) = self.create(client, place, request, true) {
+            let context = self.clone();
+            thread::spawn(move || {
+                thread::sleep(context.match_wait);
+                context.seat_brain(game, level);
+            });
+        }
+    }
+
+    /// Seats a computer player in a matchmaking game if no one has joined it.
+    fn seat_brain(&self, game: u32, level: usize) {
+        {
+            let mut lobby = self.lock();
+            let Some(room) = lobby.games.get(&game) else {
+                return;
+            };
+            if !room.matchmaking || room.status() != GameStatus::Waiting {
+                return;
+            }
+            let brain = match self.brain_for(level, &room.board) {
+                Ok(brain) => brain,
+                Err(err) => {
+                    lobby.broadcast(game, &ServerMessage::Error(err).to_line(), None);
+                    return;
+                }
+            };
+            let Some(room) = lobby.games.get_mut(&game) else {
+                return;
+            };
+            let Some(player) = room.open_seat() else {
+                return;
+            };
+
+            room.seats[seat_index(player)] = Seat::Brain(brain);
+            room.matchmaking = false;
+            let joined = format!(
+                "JOINED {} brain",
+                Player::get_player_char_from_enum(&player)
+            );
+            lobby.broadcast(game, &joined, None);
+        }
+        self.play_brains(game);
+    }
+
+    /// Seats the client in the open seat of a game.
+    fn join(&self, client: ClientId, place: &mut Option<Place>, game: u32) {
+        let mut lobby = self.lock();
+        let Some(room) = lobby.games.get_mut(&game) else {
+            lobby.send(
+                client,
+                &ServerMessage::Error(format!("There is no game {}", game)).to_line(),
+            );
+            return;
+        };
+
+        let player = match room.open_seat() {
+            Some(player) if room.status() == GameStatus::Waiting => player,
+            _ => {
+                let line = ServerMessage::Error(format!("Game {} has no open seat", game));
+                lobby.send(client, &line.to_line());
+                return;
+            }
+        };
+
+        room.seats[seat_index(player)] = Seat::Human(client);
+        room.matchmaking = false;
+        let welcome = ServerMessage::Welcome {
+            player,
+            position: room.board.to_position_str(),
+        };
+        *place = Some(Place {
+            game,
+            player: Some(player),
+        });
+
+        lobby.send(client, &welcome.to_line());
+        let joined = format!(
+            "JOINED {} human",
+            Player::get_player_char_from_enum(&player)
+        );
+        lobby.broadcast(game, &joined, Some(client));
+    }
+
+    /// Takes the client out of its game. A player leaving a game in progress abandons it, and
+    /// a game with no human players left is closed.
+    fn leave(&self, client: ClientId, place: &mut Option<Place>) {
+        let Some(Place { game, player }) = place.take() else {
+            return;
+        };
+        let mut lobby = self.lock();
+        let Some(room) = lobby.games.get_mut(&game) else {
+            return;
+        };
+
+        let Some(player) = player else {
+            room.spectators.retain(|&spectator| spectator != client);
+            return;
+        };
+
+        if room.status() == GameStatus::Playing {
+            room.abandoned = true;
+        }
+        room.seats[seat_index(player)] = Seat::Open;
+        let humans_left = room.seats.iter().any(|seat| matches!(seat, Seat::Human(_)));
+
+        let left = format!("LEFT {}", Player::get_player_char_from_enum(&player));
+        lobby.broadcast(game, &left, None);
+        if !humans_left {
+            lobby.games.remove(&game);
+        }
+    }
+
+    fn make_move(&self, client: ClientId, place: &Option<Place>, slot: usize) {
+        let Some(Place {
+            game,
+            player: Some(player),
+        }) = *place
+        else {
+            self.error(client, "You are not playing a game");
+            return;
+        };
+
+        {
+            let mut lobby = self.lock();
+            let Some(room) = lobby.games.get_mut(&game) else {
+                lobby.send(
+                    client,
+                    &ServerMessage::Error("The game has closed".to_string()).to_line(),
+                );
+                return;
+            };
+
+            let result = match room.status() {
+                GameStatus::Waiting => Err("Waiting for an opponent".to_string()),
+                GameStatus::Abandoned => Err("The game was abandoned".to_string()),
+                _ => Move::create(slot, player)
+                    .and_then(|player_move| room.board.make_move(player_move))
+                    .map_err(|err| err.to_string()),
+            };
+
+            match result {
+                Ok(_) => {
+                    let player_move = *room.board.history().last().expect("A move was just made");
+                    lobby.announce_move(game, &player_move, player);
+                }
+                Err(reason) => {
+                    lobby.send(client, &ServerMessage::Error(reason).to_line());
+                    return;
+                }
+            }
+        }
+        self.play_brains(game);
+    }
+
+    /// Lets the computer players of a game move until it is a human's turn.
+    ///
+    /// The lobby is not locked while a computer player thinks, so other games carry on.
+    fn play_brains(&self, game: u32) {
+        loop {
+            let (brain, board) = {
+                let lobby = self.lock();
+                let Some(room) = lobby.games.get(&game) else {
+                    return;
+                };
+                if room.status() != GameStatus::Playing {
+                    return;
+                }
+                match &room.seats[seat_index(room.board.get_next_player())] {
+                    Seat::Brain(brain) => (brain.clone(), room.board.clone()),
+                    _ => return,
+                }
+            };
+
+            let result = brain.make_move(&board);
+
+            let mut lobby = self.lock();
+            let Some(room) = lobby.games.get_mut(&game) else {
+                return;
+            };
+            // Someone left while the brain was thinking.
+            if room.status() != GameStatus::Playing
+                || room.board.history().len() != board.history().len()
+            {
+                return;
+            }
+
+            let player = board.get_next_player();
+            match result.and_then(|player_move| room.board.make_move(player_move)) {
+                Ok(_) => {
+                    let player_move = *room.board.history().last().expect("A move was just made");
+                    lobby.announce_move(game, &player_move, player);
+                }
+                Err(err) => {
+                    room.abandoned = true;
+                    let line =
+                        ServerMessage::Error(format!("The computer could not move: {}", err));
+                    lobby.broadcast(game, &line.to_line(), None);
+                    return;
+                }
+            }
+        }
+    }
+
+    fn new_brain(&self, level: usize) -> Option<SharedBrain> {
+        let seed = self.rng.lock().unwrap_or_else(|err| err.into_inner()).gen();
+        (self.brains)(level, seed).map(SharedBrain::from)
+    }
+
+    /// Creates the computer player for a level, if there is one that can play on `board`.
+    ///
+    /// Returns the message to send the client otherwise.
+    fn brain_for(&self, level: usize, board: &Board) -> Result<SharedBrain, String> {
+        let brain = self
+            .new_brain(level)
+            .ok_or_else(|| format!("Invalid level {}", level))?;
+        if !brain.can_play(board) {
+            return Err(format!(
+                "Level {} is too slow to play on a {} board",
+                level,
+                board.size_text()
+            ));
+        }
+        Ok(brain)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::brains::{BrainLevelOne, BrainLevelThree, BrainLevelTwo};
+
+    fn brains(level: usize, seed: u64) -> Option<Box<dyn TicTacToeBrain + Send + Sync>> {
+        match level {
+            1 => Some(Box::new(BrainLevelOne::new(seed))),
+            2 => Some(Box::new(BrainLevelTwo)),
+            3 => Some(Box::new(BrainLevelThree::default())),
+            _ => None,
+        }
+    }
+
+    /// Starts a server on a free local port and returns it with its address.
+    fn start(match_wait: Duration) -> (Arc<Server>, String) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let server = Arc::new(Server::new(brains, 7).with_match_wait(match_wait));
+        let serving = server.clone();
+        thread::spawn(move || serving.serve(listener));
+        (server, address)
+    }
+
+    struct TestClient {
+        reader: BufReader<TcpStream>,
+        writer: TcpStream,
+    }
+
+    impl TestClient {
+        fn connect(address: &str) -> Self {
+            let stream = TcpStream::connect(address).unwrap();
+            stream
+                .set_read_timeout(Some(Duration::from_secs(10)))
+                .unwrap();
+            TestClient {
+                writer: stream.try_clone().unwrap(),
+                reader: BufReader::new(stream),
+            }
+        }
+
+        fn send(&mut self, line: &str) {
+            writeln!(self.writer, "{}", line).unwrap();
+        }
+
+        fn line(&mut self) -> String {
+            let mut line = String::new();
+            self.reader.read_line(&mut line).unwrap();
+            line.trim().to_string()
+        }
+
+        /// Reads lines until one starts with `prefix`, and returns it.
+        fn expect(&mut self, prefix: &str) -> String {
+            loop {
+                let line = self.line();
+                assert!(!line.is_empty(), "connection closed waiting for {}", prefix);
+                if line.starts_with(prefix) {
+                    return line;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_commands() {
+        let request = GameRequest {
+            rules: GameRules {
+                width: 5,
+                height: 4,
+                win_length: 4,
+                first_player: Player::O,
+            },
+            side: Player::O,
+            level: Some(2),
+        };
+        assert_eq!(
+            Command::parse("create size=5x4 first=O side=o level=2"),
+            Ok(Command::Create(request))
+        );
+        assert_eq!(
+            Command::parse("PLAY size=4 win=3"),
+            Ok(Command::Play(GameRequest {
+                rules: GameRules {
+                    width: 4,
+                    height: 4,
+                    win_length: 3,
+                    first_player: Player::X,
+                },
+                side: Player::X,
+                level: None,
+            }))
+        );
+        assert_eq!(Command::parse("JOIN 3"), Ok(Command::Join(3)));
+        assert_eq!(
+            Command::parse("MOVE 5"),
+            Ok(Command::Game(ClientMessage::Move(5)))
+        );
+        assert!(Command::parse("CREATE size=big").is_err());
+        assert!(Command::parse("WATCH").is_err());
+    }
+
+    #[test]
+    fn test_two_players_and_a_spectator() {
+        let (server, address) = start(Server::DEFAULT_MATCH_WAIT);
+        let mut x = TestClient::connect(&address);
+        let mut o = TestClient::connect(&address);
+        let mut spectator = TestClient::connect(&address);
+
+        x.send("CREATE size=3");
+        let game: u32 = x.expect("CREATED")[8..].parse().unwrap();
+        assert_eq!(x.expect("WELCOME"), "WELCOME X 3/3/3 X 3");
+
+        o.send("LIST");
+        assert_eq!(
+            o.expect("GAME"),
+            format!("GAME {} 3x3 3 X human open 0 waiting", game)
+        );
+        o.send("MOVE 1");
+        o.expect("ERROR");
+        o.send(&format!("JOIN {}", game));
+        assert_eq!(o.expect("WELCOME"), "WELCOME O 3/3/3 X 3");
+        assert_eq!(x.expect("JOINED"), "JOINED O human");
+
+        spectator.send(&format!("WATCH {}", game));
+        spectator.expect("WATCHING");
+
+        for (player, slot) in [("X", 1), ("O", 4), ("X", 2), ("O", 5)] {
+            let mover = if player == "X" { &mut x } else { &mut o };
+            mover.send(&format!("MOVE {}", slot));
+            let moved = format!("MOVED {} {}", player, slot);
+            for client in [&mut x, &mut o, &mut spectator] {
+                assert_eq!(client.expect("MOVED"), moved);
+            }
+        }
+
+        // It is X's turn, so O cannot move.
+        o.send("MOVE 6");
+        assert!(o.expect("ERROR").contains("X's turn"));
+
+        x.send("MOVE 3");
+        for client in [&mut x, &mut o, &mut spectator] {
+            assert_eq!(client.expect("END"), "END X");
+        }
+        assert_eq!(server.games()[0].status, GameStatus::Over);
+        assert_eq!(server.games()[0].spectators, 1);
+    }
+
+    #[test]
+    fn test_against_the_server_brain() {
+        let (_server, address) = start(Server::DEFAULT_MATCH_WAIT);
+        let mut client = TestClient::connect(&address);
+
+        // The brain plays X and moves first.
+        client.send("CREATE side=O level=2");
+        client.expect("CREATED");
+        assert_eq!(client.expect("JOINED"), "JOINED X brain");
+        client.expect("MOVED X");
+
+        // A perfect brain never loses, whatever O plays.
+        let mut moves = 0;
+        loop {
+            let state = client.expect("STATE");
+            let board = Board::from_position_str(&state[6..]).unwrap();
+            if board.get_state() != BoardState::Ongoing {
+                break;
+            }
+            if board.get_next_player() == Player::O {
+                let slot = (1..=9).find(|&slot| board.is_slot_empty(slot)).unwrap();
+                client.send(&format!("MOVE {}", slot));
+                moves += 1;
+            }
+        }
+        assert!(moves > 0);
+        assert_ne!(client.expect("END"), "END O");
+    }
+
+    #[test]
+    fn test_matchmaking() {
+        let (server, address) = start(Server::DEFAULT_MATCH_WAIT);
+        let mut first = TestClient::connect(&address);
+        let mut second = TestClient::connect(&address);
+
+        first.send("PLAY");
+        first.expect("WELCOME X");
+        // Different rules do not match.
+        second.send("PLAY size=4");
+        second.expect("WELCOME X");
+        second.send("PLAY");
+        second.expect("WELCOME O");
+        assert_eq!(first.expect("JOINED"), "JOINED O human");
+        assert_eq!(server.games().len(), 1);
+
+        // With no one else waiting, the server's brain takes the seat.
+        let (_server, address) = start(Duration::from_millis(50));
+        let mut alone = TestClient::connect(&address);
+        alone.send("PLAY side=O level=1");
+        alone.expect("WELCOME O");
+        assert_eq!(alone.expect("JOINED"), "JOINED X brain");
+        alone.expect("MOVED X");
+    }
+
+    #[test]
+    fn test_refuses_slow_brains_on_large_boards() {
+        let (server, address) = start(Duration::from_millis(50));
+        let mut client = TestClient::connect(&address);
+
+        client.send("CREATE size=4 level=2");
+        assert!(client.expect("ERROR").contains("too slow"));
+        client.send("PLAY size=5 level=2");
+        assert!(client.expect("ERROR").contains("too slow"));
+        assert!(server.games().is_empty());
+
+        // The default computer player keeps to a time budget, so it can take the seat.
+        client.send("PLAY size=4 side=O");
+        client.expect("WELCOME O");
+        assert_eq!(client.expect("JOINED"), "JOINED X brain");
+        client.expect("MOVED X");
+        client.send("LEAVE");
+        client.expect("OK");
+
+        // The full search is fine on the classic board.
+        client.send("CREATE side=O level=2");
+        client.expect("CREATED");
+        assert_eq!(client.expect("JOINED"), "JOINED X brain");
+    }
+
+    #[test]
+    fn test_refuses_oversized_boards() {
+        let (server, address) = start(Duration::from_millis(50));
+        let mut client = TestClient::connect(&address);
+
+        client.send("CREATE size=100000");
+        assert!(client
+            .expect("ERROR")
+            .contains("Invalid board size 100000x100000"));
+        client.send("PLAY size=2000x2");
+        assert!(client.expect("ERROR").contains("Invalid board size"));
+        assert!(server.games().is_empty());
+    }
+
+    #[test]
+    fn test_stalled_client_is_disconnected() {
+        let (_server, address) = start(Server::DEFAULT_MATCH_WAIT);
+        let mut player = TestClient::connect(&address);
+        player.send("CREATE size=1x1000");
+        player.expect("WELCOME");
+
+        // Every `WATCH` is answered with the long position of the tall board, and this
+        // client never reads them, so its socket fills up.
+        let stalled = TestClient::connect(&address);
+        let mut writer = stalled.writer.try_clone().unwrap();
+        let flood = "WATCH 1\n".repeat(50_000);
+        thread::spawn(move || writer.write_all(flood.as_bytes()));
+        thread::sleep(Duration::from_secs(2));
+
+        player.send("LIST");
+        assert!(player.expect("GAME 1 ").contains("1x1000"));
+        player.expect("OK");
+    }
+
+    #[test]
+    fn test_leaving_abandons_the_game() {
+        let (server, address) = start(Server::DEFAULT_MATCH_WAIT);
+        let mut x = TestClient::connect(&address);
+        let mut o = TestClient::connect(&address);
+
+        x.send("CREATE");
+        x.expect("WELCOME");
+        o.send("JOIN 99");
+        assert!(o.expect("ERROR").contains("no game 99"));
+        o.send("JOIN 1");
+        o.expect("WELCOME");
+        x.expect("JOINED");
+
+        o.send("LEAVE");
+        o.expect("OK");
+        assert_eq!(x.expect("LEFT"), "LEFT O");
+        assert_eq!(server.games()[0].status, GameStatus::Abandoned);
+        x.send("MOVE 1");
+        assert!(x.expect("ERROR").contains("abandoned"));
+
+        // The game closes once its last player disconnects.
+        drop(x);
+        for _ in 0..100 {
+            if server.games().is_empty() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        panic!("the game was not closed");
+    }
+}
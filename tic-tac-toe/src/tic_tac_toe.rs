@@ -1,25 +1,112 @@
 // tic_tac_toe.rs
 
 /// Represents the Tic Tac Toe game board.
-/// The board consists of a 3x3 grid of cells, each of which can hold an `Option<char>`
+/// The board consists of a grid of cells, each of which can hold an `Option<char>`
 /// representing either an 'X' or an 'O' player's move.
+use crate::error::GameError;
+use crate::zobrist::{self, Symmetry};
 use colored::Colorize;
+use std::fmt;
 
-/// Constant size of the tic tac toe board.
-/// Tic Tac Toe boards are 3x3, and I'm unsure if this module would work for a 4x4 board.
+/// Size of the classic tic tac toe board.
+/// Classic Tic Tac Toe boards are 3x3 and need 3 in a row to win. Other sizes can be created
+/// with `Board::with_size`.
 pub const BOARD_SIZE: usize = 3;
 
+/// Size of the Qubic cube.
+/// Qubic is played on a 4x4x4 cube and needs 4 in a row to win. Lines can run along a layer or
+/// through all four layers. See `Board::qubic`.
+pub const QUBIC_SIZE: usize = 4;
+
+/// The longest a side of a board can be. Boards can be asked for by network clients, so
+/// their size is bounded. Runs of empty cells in a position string are checked against it as
+/// they are read, so a huge number cannot allocate a huge board.
+pub const MAX_BOARD_SIDE: usize = 1024;
+
+/// The most cells a board can have, as many as the largest flat board.
+pub const MAX_BOARD_CELLS: usize = MAX_BOARD_SIDE * MAX_BOARD_SIDE;
+
+/// The directions a line can run in, as `(layer, row, column)` steps. Each direction is only
+/// listed one way round. The first four stay within a layer.
+const DIRECTIONS: [(isize, isize, isize); 13] = [
+    (0, 0, 1),
+    (0, 1, 0),
+    (0, 1, 1),
+    (0, 1, -1),
+    (1, -1, -1),
+    (1, -1, 0),
+    (1, -1, 1),
+    (1, 0, -1),
+    (1, 0, 0),
+    (1, 0, 1),
+    (1, 1, -1),
+    (1, 1, 0),
+    (1, 1, 1),
+];
+
 /// Represents the Tic Tac Toe game board.
-/// The board consists of a 3x3 grid of cells, each of which can hold an `Option<char>`
-/// representing either an 'X' or an 'O' player's move.
-#[derive(Debug)]
+/// The board is an m,n,k-game: a `width` x `height` grid of cells, each of which can hold an
+/// `Option<char>` representing either an 'X' or an 'O' player's move. The first player to get
+/// `win_length` of their marks in a row, column or diagonal wins.
+///
+/// A board can also have several layers stacked on top of each other, like the 4x4x4 cube of
+/// Qubic. Lines then run through the layers as well as along them.
+///
+/// Under the misère rule, completing a line loses the game instead of winning it.
+#[derive(Debug, Clone)]
 pub struct Board {
-    cells: [[Option<char>; BOARD_SIZE]; BOARD_SIZE],
+    /// The rows of every layer, the rows of the top layer first.
+    cells: Vec<Vec<Option<char>>>,
+    width: usize,
+    height: usize,
+    /// Number of layers. Flat boards have one.
+    layers: usize,
+    win_length: usize,
+    player_1: Player,
+    /// Every move made so far, in the order it was played.
+    history: Vec<Move>,
+    /// Moves taken back with `undo`, most recently undone last.
+    undone: Vec<Move>,
+    /// Number of empty cells, kept up to date as moves are made and undone.
+    open_slots: usize,
+    /// The winner of the game, kept up to date as moves are made and undone.
+    winner: Option<Player>,
+    /// Whether completing a line loses the game instead of winning it.
+    misere: bool,
+    /// The Zobrist hash of the board seen through each of its symmetries, in the order of
+    /// `Symmetry::all`. Kept up to date as moves are made and undone.
+    hashes: Vec<u64>,
+}
+
+/// Writes a board size as `WIDTHxHEIGHT`, followed by `xLAYERS` if it has more than one layer.
+pub(crate) fn format_size(width: usize, height: usize, layers: usize) -> String {
+    if layers == 1 {
+        format!("{}x{}", width, height)
+    } else {
+        format!("{}x{}x{}", width, height, layers)
+    }
+}
+
+/// Returns the `(layer, row, column)` steps of `direction` going the other way.
+fn reversed((layer_step, row_step, col_step): (isize, isize, isize)) -> (isize, isize, isize) {
+    (-layer_step, -row_step, -col_step)
+}
+
+/// Returns the hashes of an empty board, one for each of its symmetries.
+fn empty_hashes(
+    width: usize,
+    height: usize,
+    layers: usize,
+    win_length: usize,
     player_1: Player,
+) -> Vec<u64> {
+    let hash = zobrist::board_key(width, height, layers, win_length, player_1);
+    vec![hash; Symmetry::all(width, height).len()]
 }
 
 /// Represents the possible states of the Tic Tac Toe game.
 /// The game can be in an ongoing state or can have ended with a winner or a tie.
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
 pub enum BoardState {
     /// The game has ended. If `Some(Player)`, the indicated player has won.
     /// If `None`, the game ended in a tie.
@@ -27,6 +114,121 @@ pub enum BoardState {
     /// The game is still ongoing.
     Ongoing,
 }
+
+/// Reasons a position string can be rejected by `Board::from_position_str`.
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub enum PositionError {
+    /// The string does not say which player is to move.
+    MissingSideToMove,
+    /// The string has more fields than rows, side to move, win length and rule.
+    TooManyFields,
+    /// A cell is not `X`, `O`, `.` or a number of empty cells.
+    InvalidCell(char),
+    /// A number of empty cells is too large to be stored.
+    NumberTooLarge(String),
+    /// A row has more cells than a position string can describe.
+    RowTooLong { row: usize, limit: usize },
+    /// A row has a different number of cells than the first row.
+    RowLengthMismatch {
+        row: usize,
+        expected: usize,
+        found: usize,
+    },
+    /// A layer has a different number of rows than the first layer.
+    LayerHeightMismatch {
+        layer: usize,
+        expected: usize,
+        found: usize,
+    },
+    /// The player to move is not `X` or `O`.
+    InvalidSideToMove(String),
+    /// The win length is not a number.
+    InvalidWinLength(String),
+    /// The rule after the win length is not `misere`.
+    UnknownRule(String),
+    /// The board size or win length is not valid.
+    InvalidSize {
+        width: usize,
+        height: usize,
+        layers: usize,
+        win_length: usize,
+    },
+    /// The players' mark counts differ by more than one, so they cannot have alternated.
+    PieceCountMismatch { x_count: usize, o_count: usize },
+    /// The mark counts show it is the other player's turn.
+    WrongSideToMove { expected: Player },
+    /// Both players have a winning line, but the game stops at the first one.
+    BothPlayersWon,
+    /// A player completed a line, but the other player moved after it.
+    MoveAfterWin { line_player: Player },
+}
+
+impl fmt::Display for PositionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PositionError::MissingSideToMove => write!(f, "Missing the player to move"),
+            PositionError::TooManyFields => write!(f, "Too many fields in position"),
+            PositionError::InvalidCell(c) => write!(f, "Invalid cell `{}`", c),
+            PositionError::NumberTooLarge(number) => {
+                write!(f, "Number of empty cells `{}` is too large", number)
+            }
+            PositionError::RowTooLong { row, limit } => {
+                write!(f, "Row {} has more than {} cells", row, limit)
+            }
+            PositionError::RowLengthMismatch {
+                row,
+                expected,
+                found,
+            } => write!(f, "Row {} has {} cells, expected {}", row, found, expected),
+            PositionError::LayerHeightMismatch {
+                layer,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Layer {} has {} rows, expected {}",
+                layer, found, expected
+            ),
+            PositionError::InvalidSideToMove(side) => {
+                write!(f, "Invalid player to move `{}`", side)
+            }
+            PositionError::InvalidWinLength(win_length) => {
+                write!(f, "Invalid win length `{}`", win_length)
+            }
+            PositionError::UnknownRule(rule) => write!(f, "Unknown rule `{}`", rule),
+            PositionError::InvalidSize {
+                width,
+                height,
+                layers,
+                win_length,
+            } => write!(
+                f,
+                "Invalid board size {} with {} in a row",
+                format_size(*width, *height, *layers),
+                win_length
+            ),
+            PositionError::PieceCountMismatch { x_count, o_count } => write!(
+                f,
+                "Impossible position: X has {} marks and O has {}",
+                x_count, o_count
+            ),
+            PositionError::WrongSideToMove { expected } => write!(
+                f,
+                "Impossible position: it should be {}'s turn",
+                Player::get_player_char_from_enum(expected)
+            ),
+            PositionError::BothPlayersWon => write!(f, "Impossible position: both players won"),
+            PositionError::MoveAfterWin { line_player } => write!(
+                f,
+                "Impossible position: {} moved after {} completed a line",
+                Player::get_player_char_from_enum(&line_player.opponent()),
+                Player::get_player_char_from_enum(line_player)
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PositionError {}
 /// Represents a player in the Tic Tac Toe game.
 /// Players can be 'X' or 'O'.
 #[derive(Eq, PartialEq, Debug, Clone, Copy)]
@@ -52,10 +254,19 @@ impl Player {
             _ => None,
         }
     }
+
+    /// Returns the other player.
+    pub fn opponent(&self) -> Player {
+        match self {
+            Player::O => Player::X,
+            Player::X => Player::O,
+        }
+    }
 }
 /// Represents a move made by a player.
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
 pub struct Move {
-    /// The position on the board where the move is made (1 to 9).
+    /// The position on the board where the move is made (1-based, row by row).
     position: usize,
     /// The player making the move.
     player: Player,
@@ -63,69 +274,338 @@ pub struct Move {
 
 impl Move {
     /// Creates a new move instance.
-    /// Returns an error if the position is outside the valid range, i.e 1-9.
-    pub fn create(position: usize, player: Player) -> Result<Self, String> {
-        if position > 0 && position <= 9 {
+    /// Returns `GameError::PositionOutOfRange` if the position is 0. Positions start at 1;
+    /// the upper bound depends on the board and is checked by `Board::make_move`.
+    pub fn create(position: usize, player: Player) -> Result<Self, GameError> {
+        if position > 0 {
             Ok(Move { position, player })
         } else {
-            let error_message = format!(
-                "Invalid position {}. Position should be in the range 0 < position <= 9",
-                position
-            );
-            Err(error_message)
+            Err(GameError::PositionOutOfRange(position))
         }
     }
+
+    /// Returns the position on the board where the move is made (1-based, row by row).
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Returns the player making the move.
+    pub fn player(&self) -> Player {
+        self.player
+    }
+}
+
+/// A run of cells that won the game. Returned by `Board::winning_lines`.
+///
+/// Under the misère rule, the line lost the game for the player who made it.
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct WinningLine {
+    /// The player whose marks fill the line.
+    player: Player,
+    /// The positions of the cells in the line (1-based, row by row), in order along the line.
+    positions: Vec<usize>,
+}
+
+impl WinningLine {
+    /// Returns the player whose marks fill the line.
+    pub fn player(&self) -> Player {
+        self.player
+    }
+
+    /// Returns the positions of the cells in the line (1-based, row by row), in order along
+    /// the line.
+    pub fn positions(&self) -> &[usize] {
+        &self.positions
+    }
 }
 
 impl Board {
-    /// Creates a new instance of the Tic Tac Toe game board.
+    /// Creates a new instance of the classic 3x3 Tic Tac Toe game board.
     pub fn new(player_1: Player) -> Self {
         Board {
-            cells: [[None; BOARD_SIZE]; BOARD_SIZE],
+            cells: vec![vec![None; BOARD_SIZE]; BOARD_SIZE],
+            width: BOARD_SIZE,
+            height: BOARD_SIZE,
+            layers: 1,
+            win_length: BOARD_SIZE,
             player_1,
+            history: Vec::new(),
+            undone: Vec::new(),
+            open_slots: BOARD_SIZE * BOARD_SIZE,
+            winner: None,
+            misere: false,
+            hashes: empty_hashes(BOARD_SIZE, BOARD_SIZE, 1, BOARD_SIZE, player_1),
         }
     }
 
-    /// Displays the current state of the board.
-    pub fn display(&self) {
-        let horizontal_borders = "-".repeat(19);
+    /// Creates a new game board of any size.
+    ///
+    /// # Parameters
+    ///
+    /// - `player_1`: The player who moves first.
+    /// - `width`: The number of columns.
+    /// - `height`: The number of rows.
+    /// - `win_length`: How many marks in a row are needed to win.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(Board)` if the size is valid.
+    /// - `Err(GameError::InvalidBoardSize)` if the board has no cells or is larger than
+    ///   `MAX_BOARD_SIDE` or `MAX_BOARD_CELLS` allow, or if `win_length` is 0 or does not fit
+    ///   on the board.
+    pub fn with_size(
+        player_1: Player,
+        width: usize,
+        height: usize,
+        win_length: usize,
+    ) -> Result<Self, GameError> {
+        Board::with_layers(player_1, width, height, 1, win_length)
+    }
+
+    /// Creates a new game board made of `layers` grids stacked on top of each other.
+    ///
+    /// Lines can run along a layer, straight down through the layers, or slant across them.
+    /// Positions count along the rows of the top layer first, then the layer below it.
+    ///
+    /// # Parameters
+    ///
+    /// - `player_1`: The player who moves first.
+    /// - `width`: The number of columns.
+    /// - `height`: The number of rows.
+    /// - `layers`: The number of layers. A board with one layer is a flat board.
+    /// - `win_length`: How many marks in a row are needed to win.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(Board)` if the size is valid.
+    /// - `Err(GameError::InvalidBoardSize)` if the board has no cells or is larger than
+    ///   `MAX_BOARD_SIDE` or `MAX_BOARD_CELLS` allow, or if `win_length` is 0 or does not fit
+    ///   on the board.
+    pub fn with_layers(
+        player_1: Player,
+        width: usize,
+        height: usize,
+        layers: usize,
+        win_length: usize,
+    ) -> Result<Self, GameError> {
+        let cells = width
+            .checked_mul(height)
+            .and_then(|area| area.checked_mul(layers));
+        if width == 0
+            || height == 0
+            || layers == 0
+            || width.max(height).max(layers) > MAX_BOARD_SIDE
+            || cells.is_none_or(|cells| cells > MAX_BOARD_CELLS)
+            || win_length == 0
+            || win_length > width.max(height).max(layers)
+        {
+            return Err(GameError::InvalidBoardSize {
+                width,
+                height,
+                layers,
+                win_length,
+            });
+        }
+
+        Ok(Board {
+            cells: vec![vec![None; width]; height * layers],
+            width,
+            height,
+            layers,
+            win_length,
+            player_1,
+            history: Vec::new(),
+            undone: Vec::new(),
+            open_slots: width * height * layers,
+            winner: None,
+            misere: false,
+            hashes: empty_hashes(width, height, layers, win_length, player_1),
+        })
+    }
 
-        println!("{}", horizontal_borders);
+    /// Creates the 4x4x4 cube of Qubic, where 4 in a row wins.
+    ///
+    /// The cube has 76 lines: the rows, columns and diagonals of each layer, the columns
+    /// running down through the layers, and the diagonals slanting across them.
+    pub fn qubic(player_1: Player) -> Self {
+        Board::with_layers(player_1, QUBIC_SIZE, QUBIC_SIZE, QUBIC_SIZE, QUBIC_SIZE)
+            .expect("The Qubic cube is a valid board")
+    }
 
-        for i in 0..self.cells.len() {
-            for j in 0..self.cells[0].len() {
-                // println!("Row: {:?}, Col: {:?}", row, col);
-                let value = match self.cells[i][j] {
-                    Some(val) => match val.to_string().to_ascii_lowercase().as_str() {
-                        "x" => "X".red(),
-                        "o" => "O".blue(),
-                        others => others.white(),
-                    },
-                    None => {
-                        let val = ((i * BOARD_SIZE) + j + 1).to_string(); // TODO: MOVE TO ANOTHER FUNCTION
-                        val.white()
-                    }
-                };
-                print!("|  {}  ", value);
+    /// Sets whether the board plays by the misère rule, where completing a line loses the
+    /// game instead of winning it.
+    ///
+    /// # Parameters
+    ///
+    /// - `misere`: `true` for the misère rule, `false` for the standard one.
+    ///
+    /// # Returns
+    ///
+    /// The same board under the chosen rule.
+    pub fn with_misere(mut self, misere: bool) -> Self {
+        if self.misere != misere {
+            self.misere = misere;
+            for hash in &mut self.hashes {
+                *hash ^= zobrist::misere_key();
             }
-            println!("|");
-            println!("{}", horizontal_borders);
+            self.winner = self.game_winner();
         }
-        println!("Total open slots: {}", self.get_number_of_open_slots());
+        self
     }
 
-    /// Returns the total number of open slots on the board.
-    fn get_number_of_open_slots(&self) -> usize {
-        let mut total = 0;
+    /// Returns true if completing a line loses the game instead of winning it.
+    pub fn is_misere(&self) -> bool {
+        self.misere
+    }
+
+    /// Returns the number of columns on the board.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the number of rows on the board.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns the size of the board written as `WIDTHxHEIGHT`, or `WIDTHxHEIGHTxLAYERS` for a
+    /// board with layers, e.g. `4x4x4`.
+    pub fn size_text(&self) -> String {
+        format_size(self.width, self.height, self.layers)
+    }
+
+    /// Returns the number of layers on the board. Flat boards have one.
+    pub fn layers(&self) -> usize {
+        self.layers
+    }
+
+    /// Returns how many marks in a row are needed to win.
+    pub fn win_length(&self) -> usize {
+        self.win_length
+    }
+
+    /// Returns the player who moved first.
+    pub fn first_player(&self) -> Player {
+        self.player_1
+    }
+
+    /// Returns the total number of slots on the board, i.e. the highest valid position.
+    pub fn get_number_of_slots(&self) -> usize {
+        self.width * self.height * self.layers
+    }
+
+    /// Converts a 1-based position to a `(row, column)` pair in `cells`, where the rows of
+    /// every layer follow each other.
+    /// Returns `None` if the position is not on the board.
+    fn position_to_cell(&self, position: usize) -> Option<(usize, usize)> {
+        if position == 0 || position > self.get_number_of_slots() {
+            return None;
+        }
+
+        Some(((position - 1) / self.width, (position - 1) % self.width))
+    }
+
+    /// Returns the 1-based `(layer, row, column)` of a position.
+    /// Returns `None` if the position is not on the board.
+    pub fn coordinates(&self, position: usize) -> Option<(usize, usize, usize)> {
+        let (row, col) = self.position_to_cell(position)?;
+        Some((row / self.height + 1, row % self.height + 1, col + 1))
+    }
+
+    /// Returns the position of the cell at the 1-based `layer`, `row` and `column`.
+    /// Returns `None` if there is no such cell.
+    pub fn position_at(&self, layer: usize, row: usize, col: usize) -> Option<usize> {
+        let on_board = (1..=self.layers).contains(&layer)
+            && (1..=self.height).contains(&row)
+            && (1..=self.width).contains(&col);
+        on_board.then(|| ((layer - 1) * self.height + row - 1) * self.width + col)
+    }
+
+    /// Reads a position typed by a player.
+    ///
+    /// A position can be typed as its number, or as its 1-based coordinates separated by
+    /// spaces or commas: `row column` on a flat board, or `layer row column` on a board with
+    /// layers. For example `2 1 4` is the top right cell of the second layer of a 4x4x4 cube.
+    ///
+    /// # Returns
+    ///
+    /// - `Some(position)` if the text names a cell on the board.
+    /// - `None` otherwise.
+    pub fn parse_position(&self, text: &str) -> Option<usize> {
+        let numbers: Vec<usize> = text
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|part| !part.is_empty())
+            .map(str::parse)
+            .collect::<Result<_, _>>()
+            .ok()?;
+
+        match numbers[..] {
+            [position] => self.position_to_cell(position).map(|_| position),
+            [row, col] if self.layers == 1 => self.position_at(1, row, col),
+            [layer, row, col] => self.position_at(layer, row, col),
+            _ => None,
+        }
+    }
+
+    /// Moves `steps` times in `direction` from the cell at `(layer, row, column)`, counted
+    /// from 0. Returns `None` if that leaves the board.
+    fn step_from(
+        &self,
+        (layer, row, col): (usize, usize, usize),
+        (layer_step, row_step, col_step): (isize, isize, isize),
+        steps: isize,
+    ) -> Option<(usize, usize, usize)> {
+        let layer = layer.checked_add_signed(layer_step * steps)?;
+        let row = row.checked_add_signed(row_step * steps)?;
+        let col = col.checked_add_signed(col_step * steps)?;
+        (layer < self.layers && row < self.height && col < self.width).then_some((layer, row, col))
+    }
 
-        for row in self.cells {
-            for col in row {
-                if col.is_none() {
-                    total += 1;
+    /// Returns every group of `win_length` cells in a straight line, the groups a player can
+    /// win by filling. A 3x3 board has 8 and the 4x4x4 Qubic cube has 76.
+    ///
+    /// # Returns
+    ///
+    /// The positions of each group's cells, in order along the line.
+    pub fn lines(&self) -> Vec<Vec<usize>> {
+        let mut lines = Vec::new();
+        for layer in 0..self.layers {
+            for row in 0..self.height {
+                for col in 0..self.width {
+                    for direction in DIRECTIONS {
+                        let line: Option<Vec<usize>> = (0..self.win_length as isize)
+                            .map(|steps| {
+                                let (layer, row, col) =
+                                    self.step_from((layer, row, col), direction, steps)?;
+                                Some((layer * self.height + row) * self.width + col + 1)
+                            })
+                            .collect();
+                        lines.extend(line);
+                    }
                 }
             }
         }
-        total
+        lines
+    }
+
+    /// Returns an object that draws the board when formatted with `{}`.
+    ///
+    /// The board is drawn as a grid with coloured marks, and empty cells show their position.
+    /// Nothing is printed until the caller does so, e.g. `println!("{}", board.display())`.
+    pub fn display(&self) -> BoardDisplay<'_> {
+        BoardDisplay { board: self }
+    }
+
+    /// Returns the total number of open slots on the board.
+    pub fn get_number_of_open_slots(&self) -> usize {
+        self.open_slots
+    }
+
+    /// Returns the player whose mark is at `position`.
+    /// Returns `None` if the slot is empty or the position is not on the board.
+    pub fn get_player_at(&self, position: usize) -> Option<Player> {
+        let (row, col) = self.position_to_cell(position)?;
+        self.cells[row][col].and_then(Player::get_player_enum_from_char)
     }
 
     // Checks if a particular slot is empty or occupied.
@@ -139,24 +619,18 @@ impl Board {
     /// # Returns
     ///
     /// - `true` if the specified slot is empty.
-    /// - `false` if the specified slot is occupied.
+    /// - `false` if the specified slot is occupied or is not on the board.
     pub fn is_slot_empty(&self, position: usize) -> bool {
-        let mut empty = true;
-        for (i, row) in self.cells.iter().enumerate() {
-            for (j, num) in row.iter().enumerate() {
-                if (i * BOARD_SIZE) + j + 1 == position && num.is_some() {
-                        empty = false;
-                    
-                }
-            }
+        match self.position_to_cell(position) {
+            Some((row, col)) => self.cells[row][col].is_none(),
+            None => false,
         }
-        // slot = self.cells[]
-        empty
     }
 
     /// Checks if a move is valid.
     ///
     /// This function verifies whether a player's move is valid based on the following criteria:
+    /// - The selected slot is on the board.
     /// - The player's move is in sequence (e.g., if it's 'X's turn, the next move should be 'O's).
     /// - The selected slot is not already occupied.
     ///
@@ -166,162 +640,242 @@ impl Board {
     ///
     /// # Returns
     ///
-    /// - `true` if the move is valid.
-    /// - `false` if the move is invalid.
-    fn check_valid_move(&self, player_move: &Move) -> bool {
-        let mut valid_move = true;
+    /// - `Ok(())` if the move is valid.
+    /// - `Err(GameError)` describing the first rule the move breaks.
+    fn check_valid_move(&self, player_move: &Move) -> Result<(), GameError> {
+        if self.position_to_cell(player_move.position).is_none() {
+            return Err(GameError::PositionOutOfRange(player_move.position));
+        }
+
         if player_move.player != self.get_next_player() {
-            valid_move = false;
+            return Err(GameError::WrongPlayer {
+                expected: self.get_next_player(),
+                found: player_move.player,
+            });
         }
 
         if !self.is_slot_empty(player_move.position) {
-            valid_move = false;
+            return Err(GameError::SlotOccupied(player_move.position));
         }
 
-        valid_move
+        Ok(())
     }
 
     /// Returns the next player whose turn it is.
     ///
-    /// The next player is determined based on the number of open slots on the board.
-    /// If the number of open slots is even, it's Player 'X'; otherwise, it's Player 'O'.
+    /// The next player is determined based on the number of moves already made.
+    /// If an even number of moves has been made, it's the first player's turn;
+    /// otherwise, it's the second player's turn.
     ///
     /// # Returns
     ///
-    /// - `Player::X` if the number of open slots is even.
-    /// - `Player::O` if the number of open slots is odd.
+    /// - `player_1` if the number of filled slots is even.
+    /// - The opponent of `player_1` if the number of filled slots is odd.
     pub fn get_next_player(&self) -> Player {
-        let player_1: Player;
-        let player_2: Player;
-        match self.player_1 {
-            Player::O => {
-                player_1 = Player::O;
-                player_2 = Player::X;
-            }
-            Player::X => {
-                player_1 = Player::X;
-                player_2 = Player::O;
-            }
-        }
-        if (self.get_number_of_open_slots() % 2) == 0 {
-            player_2
-        } else {
+        let player_1 = self.player_1;
+        let player_2 = self.player_1.opponent();
+        let filled_slots = self.get_number_of_slots() - self.get_number_of_open_slots();
+        if filled_slots.is_multiple_of(2) {
             player_1
+        } else {
+            player_2
         }
     }
 
-    /// Checks rows of the game board for a winner.
+    /// Checks rows of the game board for winning lines.
     ///
-    /// This function iterates through each row of the board and calls `check_equal_cells`
-    /// to determine if all cells in a row have the same non-empty value (either 'X' or 'O').
+    /// This function iterates through each row of every layer and calls `check_equal_cells`
+    /// to find runs of `win_length` or more consecutive cells with the same non-empty value.
     ///
     /// # Returns
     ///
-    /// - `Some(Player)` if a winning player is found.
-    /// - `None` if no winner is found.
-    fn check_rows(&self) -> Option<Player> {
-        for row in &self.cells {
-            if let Some(player) = self.check_equal_cells(row.iter()) {
-                return Some(player);
-            }
+    /// Every winning line found in a row. The list is empty if no row has one.
+    fn check_rows(&self) -> Vec<WinningLine> {
+        let mut lines = Vec::new();
+        for row in 0..self.cells.len() {
+            lines.extend(self.check_equal_cells((0..self.width).map(|col| (row, col))));
         }
-
-        None
+        lines
     }
 
-    /// Checks columns of the game board for a winner.
+    /// Checks columns of the game board for winning lines.
     ///
-    /// This function iterates through each column of the board and calls `check_equal_cells`
-    /// to determine if all cells in a column have the same non-empty value (either 'X' or 'O').
+    /// This function iterates through each column of every layer and calls `check_equal_cells`
+    /// to find runs of `win_length` or more consecutive cells with the same non-empty value.
     ///
     /// # Returns
     ///
-    /// - `Some(Player)` if a winning player is found.
-    /// - `None` if no winner is found.
-    fn check_columns(&self) -> Option<Player> {
-        for col in 0..BOARD_SIZE {
-            let column = self.cells.iter().map(|row| &row[col]);
-            if let Some(player) = self.check_equal_cells(column) {
-                return Some(player);
+    /// Every winning line found in a column. The list is empty if no column has one.
+    fn check_columns(&self) -> Vec<WinningLine> {
+        let mut lines = Vec::new();
+        for top in (0..self.cells.len()).step_by(self.height) {
+            for col in 0..self.width {
+                lines.extend(self.check_equal_cells((0..self.height).map(|row| (top + row, col))));
             }
         }
-        None
+        lines
     }
 
-    /// Checks diagonals of the game board for a winner.
+    /// Checks diagonals of the game board for winning lines.
     ///
-    /// This function checks both the primary and secondary diagonals of the board
-    /// by calling `check_equal_cells` with the appropriate iterators.
+    /// This function checks every diagonal of every layer running down-right (primary) and
+    /// down-left (secondary) by calling `check_equal_cells` with the appropriate iterators.
     ///
     /// # Returns
     ///
-    /// - `Some(Player)` if a winning player is found.
-    /// - `None` if no winner is found.    
-    fn check_diagonals(&self) -> Option<Player> {
-        let primary_diagonal = (0..BOARD_SIZE).map(|i| &self.cells[i][i]);
-        let secondary_diagonal = (0..BOARD_SIZE).map(|i| &self.cells[i][BOARD_SIZE - 1 - i]);
+    /// Every winning line found on a diagonal. The list is empty if no diagonal has one.
+    fn check_diagonals(&self) -> Vec<WinningLine> {
+        // Every diagonal starts on the top row or on the left/right edge.
+        let mut starts: Vec<(usize, usize)> = (0..self.width).map(|col| (0, col)).collect();
+        starts.extend((1..self.height).map(|row| (row, 0)));
+        starts.extend((1..self.height).map(|row| (row, self.width - 1)));
+
+        let mut lines = Vec::new();
+        for top in (0..self.cells.len()).step_by(self.height) {
+            for &(row, col) in &starts {
+                if col == 0 || row == 0 {
+                    let primary_diagonal = (0..)
+                        .map(|i| (row + i, col + i))
+                        .take_while(|&(row, col)| row < self.height && col < self.width)
+                        .map(|(row, col)| (top + row, col));
+                    lines.extend(self.check_equal_cells(primary_diagonal));
+                }
+
+                if col == self.width - 1 || row == 0 {
+                    let secondary_diagonal = (0..=col)
+                        .map(|i| (row + i, col - i))
+                        .take_while(|&(row, _)| row < self.height)
+                        .map(|(row, col)| (top + row, col));
+                    lines.extend(self.check_equal_cells(secondary_diagonal));
+                }
+            }
+        }
+
+        lines
+    }
 
-        if let Some(player) = self.check_equal_cells(primary_diagonal) {
-            return Some(player);
+    /// Checks the lines running through the layers of the game board for winning lines.
+    ///
+    /// Lines can run straight down through the layers, or slant across them along a row,
+    /// a column or a diagonal. Each line is checked from the cell where it enters the board,
+    /// by calling `check_equal_cells`. Flat boards have none of these lines.
+    ///
+    /// # Returns
+    ///
+    /// Every winning line found through the layers. The list is empty if there is none.
+    fn check_layers(&self) -> Vec<WinningLine> {
+        let mut lines = Vec::new();
+        if self.layers == 1 {
+            return lines;
         }
 
-        if let Some(player) = self.check_equal_cells(secondary_diagonal) {
-            return Some(player);
+        for direction in DIRECTIONS
+            .into_iter()
+            .filter(|(layer_step, _, _)| *layer_step == 1)
+        {
+            for layer in 0..self.layers {
+                for row in 0..self.height {
+                    for col in 0..self.width {
+                        let start = (layer, row, col);
+                        if self.step_from(start, direction, -1).is_some() {
+                            continue;
+                        }
+                        let line = (0..)
+                            .map_while(|steps| self.step_from(start, direction, steps))
+                            .map(|(layer, row, col)| (layer * self.height + row, col));
+                        lines.extend(self.check_equal_cells(line));
+                    }
+                }
+            }
         }
 
-        None
+        lines
     }
 
-    /// Checks if a sequence of cells contains equal non-empty values.
+    /// Finds the runs of enough equal non-empty values in a row to win in a sequence of cells.
     ///
-    /// This function takes an iterator of cell references and checks if all cells
-    /// have the same non-empty value (either 'X' or 'O').
+    /// This function takes an iterator of `(row, col)` cells along a line and looks for
+    /// `win_length` or more consecutive cells with the same non-empty value (either 'X' or
+    /// 'O'). A run longer than `win_length` is reported once, with all of its cells.
     ///
     /// # Parameters
     ///
-    /// - `cells`: An iterator over cell references.
+    /// - `cells`: An iterator over the cells of a line, in order.
     ///
     /// # Returns
     ///
-    /// - `Some(Player)` if all cells have the same non-empty value.
-    /// - `None` if not all cells have the same value or if any cell is empty.
-    fn check_equal_cells<'a, I>(&self, cells: I) -> Option<Player>
+    /// Every winning run in the line. The list is empty if there is no such run.
+    fn check_equal_cells<I>(&self, cells: I) -> Vec<WinningLine>
     where
-        I: Iterator<Item = &'a Option<char>>,
+        I: Iterator<Item = (usize, usize)>,
     {
-        let values: Vec<&Option<char>> = cells.collect();
-        let first_value = values[0];
+        let mut lines = Vec::new();
+        let mut run_value: Option<char> = None;
+        let mut run: Vec<usize> = Vec::new();
+        let mut finish_run = |value: Option<char>, positions: Vec<usize>| {
+            if positions.len() >= self.win_length {
+                if let Some(player) = value.and_then(Player::get_player_enum_from_char) {
+                    lines.push(WinningLine { player, positions });
+                }
+            }
+        };
 
-        if values.iter().all(|&v| v == first_value && v.is_some()) {
-            return Player::get_player_enum_from_char(first_value.unwrap());
+        for (row, col) in cells {
+            let value = self.cells[row][col];
+            if value.is_none() || value != run_value {
+                finish_run(run_value, std::mem::take(&mut run));
+                run_value = value;
+            }
+            if value.is_some() {
+                run.push(row * self.width + col + 1);
+            }
         }
+        finish_run(run_value, run);
+
+        lines
+    }
 
-        None
+    /// Returns every line of `win_length` or more marks in a row on the board.
+    ///
+    /// A single move can complete several lines at once, for example a row and a diagonal
+    /// through the same cell, so all of them are returned. Rows come first, then columns,
+    /// then diagonals, then the lines through the layers of a board with layers.
+    ///
+    /// # Returns
+    ///
+    /// The winning lines, each with the player who made it and the positions of its cells.
+    /// The list is empty if nobody has won. Under the misère rule, these are the lines that
+    /// lost the game.
+    pub fn winning_lines(&self) -> Vec<WinningLine> {
+        let mut lines = self.check_rows();
+        lines.extend(self.check_columns());
+        lines.extend(self.check_diagonals());
+        lines.extend(self.check_layers());
+        lines
     }
 
     /// Determines the winner of the game.
     ///
-    /// This function checks for a winner by calling `check_rows`.
+    /// This function checks for a completed line by calling `winning_lines`. Under the
+    /// misère rule, the player who completed it loses.
     ///
     /// # Returns
     ///
     /// - `Some(Player)` if a winning player is found.
     /// - `None` if no winner is found.
     fn game_winner(&self) -> Option<Player> {
-        // Check rows
-        if let Some(player) = self.check_rows() {
-            return Some(player);
-        }
-
-        if let Some(player) = self.check_columns() {
-            return Some(player);
-        }
+        self.winning_lines()
+            .first()
+            .map(|line| self.winner_for_line(line.player()))
+    }
 
-        if let Some(player) = self.check_diagonals() {
-            return Some(player);
+    /// Returns the player who wins when `player` completes a line.
+    fn winner_for_line(&self, player: Player) -> Player {
+        if self.misere {
+            player.opponent()
+        } else {
+            player
         }
-
-        None
     }
 
     /// Makes a move on the game board.
@@ -338,42 +892,525 @@ impl Board {
     /// - `Ok(BoardState::Ended(Some(player)))` if the game is won by a player.
     /// - `Ok(BoardState::Ended(None))` if the game ends in a tie.
     /// - `Ok(BoardState::Ongoing)` if the game continues after the move.
-    /// - `Err("Game has already ended")` if the game has already been won or tied.
-    /// - `Err("Invalid move.")` if the move is not valid.
-    pub fn make_move(&mut self, player_move: Move) -> Result<BoardState, String> {
+    /// - `Err(GameError::GameOver)` if the game has already been won or tied.
+    /// - `Err(GameError::PositionOutOfRange)` if the position is not on the board.
+    /// - `Err(GameError::WrongPlayer)` if it is the other player's turn.
+    /// - `Err(GameError::SlotOccupied)` if the slot already holds a mark.
+    pub fn make_move(&mut self, player_move: Move) -> Result<BoardState, GameError> {
         // Check that game has not ended.
-        if self.game_winner().is_some() {
-            return Err("Game has already ended".to_string());
+        if self.get_state() != BoardState::Ongoing {
+            return Err(GameError::GameOver);
         }
         // Check valid move
-        if self.check_valid_move(&player_move) {
-            // Make the move
-
-            for (i, row) in self.cells.iter_mut().enumerate() {
-                for (j, num) in row.iter_mut().enumerate() {
-                    if (i * BOARD_SIZE) + j + 1 == player_move.position {
-                        if num.is_none() {
-                            *num = Some(Player::get_player_char_from_enum(&player_move.player));
-                        } else {
-                            let error_message = format!("Invalid Move: {:}", player_move.position);
-                            return Err(error_message);
+        self.check_valid_move(&player_move)?;
+
+        let (row, col) = self
+            .position_to_cell(player_move.position)
+            .expect("Valid moves are always on the board");
+        // A new move replaces anything that was undone
+        self.undone.clear();
+        self.place_mark(row, col, player_move.player);
+        self.history.push(player_move);
+
+        Ok(self.get_state())
+    }
+
+    /// Makes a move without checking whose turn it is on this board.
+    ///
+    /// Variants built from several boards, like Ultimate Tic Tac Toe, take turns across all of
+    /// the boards, so one of them can see the same player move twice in a row.
+    ///
+    /// # Returns
+    ///
+    /// The same as `make_move`, except that it never returns `GameError::WrongPlayer`.
+    pub(crate) fn make_move_out_of_turn(
+        &mut self,
+        player_move: Move,
+    ) -> Result<BoardState, GameError> {
+        if self.get_state() != BoardState::Ongoing {
+            return Err(GameError::GameOver);
+        }
+        let (row, col) = self
+            .position_to_cell(player_move.position)
+            .ok_or(GameError::PositionOutOfRange(player_move.position))?;
+        if !self.is_slot_empty(player_move.position) {
+            return Err(GameError::SlotOccupied(player_move.position));
+        }
+
+        self.undone.clear();
+        self.place_mark(row, col, player_move.player);
+        self.history.push(player_move);
+        Ok(self.get_state())
+    }
+
+    /// Puts `player`'s mark in an empty cell and updates the open slots and winner.
+    ///
+    /// Only lines through the new mark can have been completed, so only those are checked.
+    fn place_mark(&mut self, row: usize, col: usize, player: Player) {
+        self.cells[row][col] = Some(Player::get_player_char_from_enum(&player));
+        self.open_slots -= 1;
+        self.toggle_hashes(row, col, player);
+
+        let cell = (row / self.height, row % self.height, col);
+        for direction in DIRECTIONS {
+            let run_length = 1
+                + self.count_marks_from(cell, direction)
+                + self.count_marks_from(cell, reversed(direction));
+
+            if run_length >= self.win_length {
+                self.winner = Some(self.winner_for_line(player));
+            }
+        }
+    }
+
+    /// Adds `player`'s mark at `(row, col)` to the hashes, or removes it if it is already there.
+    ///
+    /// On a board with layers, each layer is turned or flipped the same way.
+    fn toggle_hashes(&mut self, row: usize, col: usize, player: Player) {
+        let (top, row) = (row - row % self.height, row % self.height);
+        for (hash, symmetry) in self
+            .hashes
+            .iter_mut()
+            .zip(Symmetry::all(self.width, self.height))
+        {
+            let (row, col) = symmetry.apply(row, col, self.width, self.height);
+            *hash ^= zobrist::mark_key((top + row) * self.width + col, player);
+        }
+    }
+
+    /// Returns the Zobrist hash of the board.
+    ///
+    /// Boards with the same marks, size, win length and first player have the same hash, no
+    /// matter which order the moves were played in. The hash is updated as moves are made
+    /// and undone, so reading it is cheap.
+    pub fn zobrist_hash(&self) -> u64 {
+        self.hashes[0]
+    }
+
+    /// Returns a hash that is the same for every rotation and reflection of the board.
+    ///
+    /// A square board has eight symmetries and other boards have four. Positions that are
+    /// turned or flipped copies of each other are equally good, so searches can use this
+    /// hash to evaluate each of them only once. The layers of a board with layers are turned
+    /// and flipped together, but never swapped.
+    pub fn canonical_hash(&self) -> u64 {
+        *self.hashes.iter().min().expect("Boards always have a hash")
+    }
+
+    /// Returns the rotation or reflection of the board whose `zobrist_hash` is its
+    /// `canonical_hash`.
+    ///
+    /// Boards that are turned or flipped copies of each other have the same canonical form.
+    /// The returned board has no move history.
+    pub fn canonical_form(&self) -> Board {
+        let (index, _) = self
+            .hashes
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, hash)| **hash)
+            .expect("Boards always have a hash");
+        let symmetry = Symmetry::all(self.width, self.height)[index];
+
+        let mut board = Board::with_layers(
+            self.player_1,
+            self.width,
+            self.height,
+            self.layers,
+            self.win_length,
+        )
+        .expect("The board size was already checked")
+        .with_misere(self.misere);
+        for row in 0..self.cells.len() {
+            for col in 0..self.width {
+                if let Some(player) =
+                    self.cells[row][col].and_then(Player::get_player_enum_from_char)
+                {
+                    let top = row - row % self.height;
+                    let (new_row, new_col) =
+                        symmetry.apply(row % self.height, col, self.width, self.height);
+                    board.cells[top + new_row][new_col] = self.cells[row][col];
+                    board.toggle_hashes(top + new_row, new_col, player);
+                }
+            }
+        }
+        board.open_slots = self.open_slots;
+        board.winner = self.winner;
+        board
+    }
+
+    /// Counts the marks matching the one at `(layer, row, column)` in a straight line from
+    /// it, not including the cell itself.
+    fn count_marks_from(
+        &self,
+        cell: (usize, usize, usize),
+        direction: (isize, isize, isize),
+    ) -> usize {
+        let mark =
+            |(layer, row, col): (usize, usize, usize)| self.cells[layer * self.height + row][col];
+
+        (1..)
+            .map_while(|steps| self.step_from(cell, direction, steps))
+            .take_while(|&next| mark(next) == mark(cell))
+            .count()
+    }
+
+    /// Returns the current state of the game.
+    ///
+    /// # Returns
+    ///
+    /// - `BoardState::Ended(Some(player))` if the game has been won by a player.
+    /// - `BoardState::Ended(None)` if the game ended in a tie.
+    /// - `BoardState::Ongoing` if the game is still being played.
+    pub fn get_state(&self) -> BoardState {
+        if let Some(winner) = self.winner {
+            BoardState::Ended(Some(winner))
+        } else if self.get_number_of_open_slots() == 0 {
+            BoardState::Ended(None)
+        } else {
+            BoardState::Ongoing
+        }
+    }
+
+    /// Returns every move made so far, in the order it was played.
+    pub fn history(&self) -> &[Move] {
+        &self.history
+    }
+
+    /// Takes back the last move made.
+    ///
+    /// The move can be played again with `redo`, until a new move is made.
+    ///
+    /// # Returns
+    ///
+    /// - `Some(Move)` with the move that was taken back.
+    /// - `None` if no moves have been made.
+    pub fn undo(&mut self) -> Option<Move> {
+        let player_move = self.history.pop()?;
+        let (row, col) = self
+            .position_to_cell(player_move.position)
+            .expect("Moves in the history are always on the board");
+
+        self.cells[row][col] = None;
+        self.open_slots += 1;
+        self.toggle_hashes(row, col, player_move.player);
+        // The game stops at the first completed line, so there was no winner before this move.
+        self.winner = None;
+        self.undone.push(player_move);
+        Some(player_move)
+    }
+
+    /// Plays again the last move taken back with `undo`.
+    ///
+    /// # Returns
+    ///
+    /// - `Some(BoardState)` with the state of the game after the move is replayed.
+    /// - `None` if there is no move to redo.
+    pub fn redo(&mut self) -> Option<BoardState> {
+        let player_move = self.undone.pop()?;
+        let (row, col) = self
+            .position_to_cell(player_move.position)
+            .expect("Undone moves are always on the board");
+
+        self.place_mark(row, col, player_move.player);
+        self.history.push(player_move);
+        Some(self.get_state())
+    }
+
+    /// Creates a board from a position string.
+    ///
+    /// A position string describes the cells and the player to move on one line, e.g.
+    /// `X1O/1X1/3 O`. Rows are listed from top to bottom and separated by `/`. In each row,
+    /// `X` and `O` are marks, and a number is that many empty cells in a row (`.` can also
+    /// be used for a single empty cell). The rows are followed by the player to move and,
+    /// optionally, the win length, which defaults to the smaller of the width and height.
+    /// A game played by the misère rule ends with `misere` after the win length, e.g.
+    /// `X1O/1X1/3 O 3 misere`.
+    ///
+    /// The layers of a board with layers are listed from top to bottom and separated by `|`,
+    /// e.g. `4/4/4/4|4/1X2/4/4|4/4/4/4|4/4/4/4 O` for a 4x4x4 cube. Their win length defaults
+    /// to the smallest of the width, height and number of layers.
+    ///
+    /// The loaded board has no move history, so there is nothing to `undo`. Rows can have at
+    /// most 1024 cells.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(Board)` if the position could have been reached in a game.
+    /// - `Err(PositionError)` if the string is malformed or describes an impossible position.
+    pub fn from_position_str(position: &str) -> Result<Board, PositionError> {
+        let mut fields = position.split_whitespace();
+        let rows = fields.next().ok_or(PositionError::MissingSideToMove)?;
+        let side_to_move = fields.next().ok_or(PositionError::MissingSideToMove)?;
+        let win_length = fields.next();
+        let misere = match fields.next() {
+            None => false,
+            Some("misere") => true,
+            Some(rule) => return Err(PositionError::UnknownRule(rule.to_string())),
+        };
+        if fields.next().is_some() {
+            return Err(PositionError::TooManyFields);
+        }
+
+        let height = rows
+            .split('|')
+            .next()
+            .unwrap_or_default()
+            .split('/')
+            .count();
+        for (layer, layer_rows) in rows.split('|').enumerate() {
+            let found = layer_rows.split('/').count();
+            if found != height {
+                return Err(PositionError::LayerHeightMismatch {
+                    layer: layer + 1,
+                    expected: height,
+                    found,
+                });
+            }
+        }
+
+        let mut cells: Vec<Vec<Option<char>>> = Vec::new();
+        for row in rows.split(['/', '|']) {
+            let mut cells_in_row = Vec::new();
+            let mut empty_run = String::new();
+
+            for c in row.chars().chain(std::iter::once('/')) {
+                if c.is_ascii_digit() {
+                    empty_run.push(c);
+                    continue;
+                }
+
+                if !empty_run.is_empty() {
+                    let count: usize = empty_run
+                        .parse()
+                        .map_err(|_| PositionError::NumberTooLarge(empty_run.clone()))?;
+                    if count > MAX_BOARD_SIDE - cells_in_row.len() {
+                        return Err(PositionError::RowTooLong {
+                            row: cells.len() + 1,
+                            limit: MAX_BOARD_SIDE,
+                        });
+                    }
+                    cells_in_row.extend(std::iter::repeat_n(None, count));
+                    empty_run.clear();
+                }
+
+                if c != '/' && cells_in_row.len() == MAX_BOARD_SIDE {
+                    return Err(PositionError::RowTooLong {
+                        row: cells.len() + 1,
+                        limit: MAX_BOARD_SIDE,
+                    });
+                }
+                match c {
+                    '/' => {}
+                    '.' => cells_in_row.push(None),
+                    _ => match Player::get_player_enum_from_char(c) {
+                        Some(player) => {
+                            cells_in_row.push(Some(Player::get_player_char_from_enum(&player)))
+                        }
+                        None => return Err(PositionError::InvalidCell(c)),
+                    },
+                }
+            }
+
+            if let Some(first_row) = cells.first() {
+                if first_row.len() != cells_in_row.len() {
+                    return Err(PositionError::RowLengthMismatch {
+                        row: cells.len() + 1,
+                        expected: first_row.len(),
+                        found: cells_in_row.len(),
+                    });
+                }
+            }
+            cells.push(cells_in_row);
+        }
+
+        let mut chars = side_to_move.chars();
+        let side_to_move = match (
+            chars.next().and_then(Player::get_player_enum_from_char),
+            chars.next(),
+        ) {
+            (Some(player), None) => player,
+            _ => return Err(PositionError::InvalidSideToMove(side_to_move.to_string())),
+        };
+
+        let layers = cells.len() / height;
+        let width = cells[0].len();
+        let win_length = match win_length {
+            Some(win_length) => win_length
+                .parse()
+                .map_err(|_| PositionError::InvalidWinLength(win_length.to_string()))?,
+            None if layers > 1 => width.min(height).min(layers),
+            None => width.min(height),
+        };
+
+        // Work out who moved first from the number of marks each player has.
+        let count = |player: Player| {
+            let c = Player::get_player_char_from_enum(&player);
+            cells
+                .iter()
+                .flatten()
+                .filter(|cell| **cell == Some(c))
+                .count()
+        };
+        let (x_count, o_count) = (count(Player::X), count(Player::O));
+        let player_1 = if x_count == o_count {
+            side_to_move
+        } else if x_count == o_count + 1 {
+            Player::X
+        } else if o_count == x_count + 1 {
+            Player::O
+        } else {
+            return Err(PositionError::PieceCountMismatch { x_count, o_count });
+        };
+
+        let mut board = Board::with_layers(player_1, width, height, layers, win_length)
+            .map_err(|_| PositionError::InvalidSize {
+                width,
+                height,
+                layers,
+                win_length,
+            })?
+            .with_misere(misere);
+        board.open_slots = cells.iter().flatten().filter(|cell| cell.is_none()).count();
+        board.cells = cells;
+        board.winner = board.game_winner();
+        for row in 0..board.cells.len() {
+            for col in 0..width {
+                if let Some(player) =
+                    board.cells[row][col].and_then(Player::get_player_enum_from_char)
+                {
+                    board.toggle_hashes(row, col, player);
+                }
+            }
+        }
+
+        if board.get_next_player() != side_to_move {
+            return Err(PositionError::WrongSideToMove {
+                expected: board.get_next_player(),
+            });
+        }
+
+        let wins = |player: Player| {
+            let mut board = board.clone();
+            board.cells.iter_mut().flatten().for_each(|cell| {
+                if *cell != Some(Player::get_player_char_from_enum(&player)) {
+                    *cell = None;
+                }
+            });
+            board.game_winner().is_some()
+        };
+        if wins(Player::X) && wins(Player::O) {
+            return Err(PositionError::BothPlayersWon);
+        }
+
+        // The game stops as soon as a line is completed, so its owner made the last move.
+        if let Some(line) = board.winning_lines().first() {
+            if line.player() != side_to_move.opponent() {
+                return Err(PositionError::MoveAfterWin {
+                    line_player: line.player(),
+                });
+            }
+        }
+
+        Ok(board)
+    }
+
+    /// Returns the position string for the board.
+    ///
+    /// See `from_position_str` for the format. The win length is always included, and the
+    /// rule only for misère games.
+    pub fn to_position_str(&self) -> String {
+        let rows: Vec<String> = self
+            .cells
+            .iter()
+            .map(|row| {
+                let mut text = String::new();
+                let mut empty_run = 0;
+
+                for cell in row {
+                    match cell {
+                        Some(c) => {
+                            if empty_run > 0 {
+                                text.push_str(&empty_run.to_string());
+                                empty_run = 0;
+                            }
+                            text.push(*c);
                         }
+                        None => empty_run += 1,
                     }
                 }
-            }
-            // Check game ended
-            if let Some(winner) = self.game_winner() {
-                Ok(BoardState::Ended(Some(winner)))
-            } else {
-                if self.get_number_of_open_slots() == 0 {
-                    println!("Open slots: {}", self.get_number_of_open_slots());
-                    return Ok(BoardState::Ended(None));
+
+                if empty_run > 0 {
+                    text.push_str(&empty_run.to_string());
+                }
+                text
+            })
+            .collect();
+
+        let layers: Vec<String> = rows
+            .chunks(self.height)
+            .map(|rows| rows.join("/"))
+            .collect();
+        let rule = if self.misere { " misere" } else { "" };
+        format!(
+            "{} {} {}{}",
+            layers.join("|"),
+            Player::get_player_char_from_enum(&self.get_next_player()),
+            self.win_length,
+            rule
+        )
+    }
+}
+
+/// Draws a board in the terminal. Created by `Board::display`.
+pub struct BoardDisplay<'a> {
+    board: &'a Board,
+}
+
+impl fmt::Display for BoardDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let board = self.board;
+        let cell_width = board.get_number_of_slots().to_string().len();
+        let horizontal_borders = "-".repeat(board.width * (cell_width + 5) + 1);
+
+        let winning: Vec<usize> = board
+            .winning_lines()
+            .iter()
+            .flat_map(|line| line.positions().to_vec())
+            .collect();
+
+        for i in 0..board.cells.len() {
+            if i % board.height == 0 {
+                if board.layers > 1 {
+                    writeln!(f, "Layer {}", i / board.height + 1)?;
                 }
-                Ok(BoardState::Ongoing)
+                writeln!(f, "{}", horizontal_borders)?;
             }
-        } else {
-            Err("Invalid move.".to_string())
+            for j in 0..board.width {
+                let text = match board.cells[i][j] {
+                    Some(val) => val.to_ascii_uppercase().to_string(),
+                    None => ((i * board.width) + j + 1).to_string(),
+                };
+                let text = format!("{:^width$}", text, width = cell_width);
+                let value = match board.cells[i][j] {
+                    Some(val) => match val.to_ascii_lowercase() {
+                        'x' => text.red(),
+                        'o' => text.blue(),
+                        _ => text.white(),
+                    },
+                    None => text.white(),
+                };
+                // The cells of a winning line are drawn in reverse video so they stand out.
+                let value = if winning.contains(&(i * board.width + j + 1)) {
+                    value.bold().reversed()
+                } else {
+                    value
+                };
+                write!(f, "|  {}  ", value)?;
+            }
+            writeln!(f, "|")?;
+            writeln!(f, "{}", horizontal_borders)?;
         }
+        write!(f, "Total open slots: {}", board.get_number_of_open_slots())
     }
 }
 
@@ -446,7 +1483,7 @@ mod tests {
             player: player_2,
         };
 
-        assert!(!board.check_valid_move(&player_move));
+        assert!(board.check_valid_move(&player_move).is_err());
     }
 
     #[test]
@@ -476,18 +1513,18 @@ mod tests {
 
         for i in 1..=BOARD_SIZE {
             let player_o_move = Move {
-                position: i as usize,
+                position: i,
                 player: Player::O,
             };
             board.make_move(player_o_move);
             let player_x_move = Move {
-                position: (i + BOARD_SIZE) as usize,
+                position: i + BOARD_SIZE,
                 player: Player::X,
             };
             board.make_move(player_x_move);
         }
-        board.display();
-        assert!(board.check_rows().is_some())
+        println!("{}", board.display());
+        assert!(!board.check_rows().is_empty())
     }
 
     #[test]
@@ -498,18 +1535,18 @@ mod tests {
 
         for i in 1..=BOARD_SIZE {
             let player_o_move = Move {
-                position: (BOARD_SIZE * i - BOARD_SIZE + 1) as usize,
+                position: BOARD_SIZE * i - BOARD_SIZE + 1,
                 player: Player::O,
             };
             board.make_move(player_o_move);
             let player_x_move = Move {
-                position: (BOARD_SIZE * i - BOARD_SIZE + 2) as usize,
+                position: BOARD_SIZE * i - BOARD_SIZE + 2,
                 player: Player::X,
             };
             board.make_move(player_x_move);
         }
-        board.display();
-        assert!(board.check_columns().is_some())
+        println!("{}", board.display());
+        assert!(!board.check_columns().is_empty())
     }
 
     #[test]
@@ -521,18 +1558,18 @@ mod tests {
 
         for i in 1..=BOARD_SIZE {
             let player_o_move = Move {
-                position: (BOARD_SIZE * i - (BOARD_SIZE - i)) as usize,
+                position: BOARD_SIZE * i - (BOARD_SIZE - i),
                 player: Player::O,
             };
             board.make_move(player_o_move);
             let player_x_move = Move {
-                position: (i + 1) as usize,
+                position: i + 1,
                 player: Player::X,
             };
             board.make_move(player_x_move);
         }
-        board.display();
-        assert!(board.check_diagonals().is_some())
+        println!("{}", board.display());
+        assert!(!board.check_diagonals().is_empty())
     }
 
     #[test]
@@ -544,18 +1581,18 @@ mod tests {
 
         for i in 1..=BOARD_SIZE {
             let player_o_move = Move {
-                position: (BOARD_SIZE * i - (i - 1)) as usize,
+                position: BOARD_SIZE * i - (i - 1),
                 player: Player::O,
             };
             board.make_move(player_o_move);
             let player_x_move = Move {
-                position: (i.pow(2)) as usize,
+                position: i.pow(2),
                 player: Player::X,
             };
             board.make_move(player_x_move);
         }
-        board.display();
-        assert!(board.check_diagonals().is_some())
+        println!("{}", board.display());
+        assert!(!board.check_diagonals().is_empty())
     }
 
     #[test]
@@ -567,17 +1604,17 @@ mod tests {
 
         for i in 1..=BOARD_SIZE {
             let player_o_move = Move {
-                position: (BOARD_SIZE * i - (i - 1)) as usize,
+                position: BOARD_SIZE * i - (i - 1),
                 player: Player::O,
             };
             board.make_move(player_o_move);
             let player_x_move = Move {
-                position: (i.pow(2)) as usize,
+                position: i.pow(2),
                 player: Player::X,
             };
             board.make_move(player_x_move);
         }
-        board.display();
+        println!("{}", board.display());
         assert_eq!(board.game_winner(), Some(Player::O));
     }
 
@@ -604,8 +1641,693 @@ mod tests {
             board.make_move(player_move).unwrap();
         }
 
-        board.display();
+        println!("{}", board.display());
         // Check that the game is recognized as a tie
         assert_eq!(board.game_winner(), None);
     }
+
+    #[test]
+    #[allow(unused_must_use)]
+    fn test_larger_board_needs_full_win_length() {
+        let mut board = Board::with_size(Player::X, 4, 4, 4).unwrap();
+
+        // X fills 1, 2, 3 on the top row while O plays on the second row.
+        for (x_position, o_position) in [(1, 5), (2, 6), (3, 7)] {
+            board.make_move(Move::create(x_position, Player::X).unwrap());
+            board.make_move(Move::create(o_position, Player::O).unwrap());
+        }
+        assert_eq!(board.game_winner(), None);
+
+        let state = board.make_move(Move::create(4, Player::X).unwrap());
+        assert_eq!(state, Ok(BoardState::Ended(Some(Player::X))));
+    }
+
+    #[test]
+    #[allow(unused_must_use)]
+    fn test_k_in_a_row_diagonal_off_the_main_diagonal() {
+        // 5x5 board with 4 in a row. O wins on the diagonal 2, 8, 14, 20.
+        let mut board = Board::with_size(Player::O, 5, 5, 4).unwrap();
+
+        for (o_position, x_position) in [(2, 1), (8, 3), (14, 5)] {
+            board.make_move(Move::create(o_position, Player::O).unwrap());
+            board.make_move(Move::create(x_position, Player::X).unwrap());
+        }
+        assert!(board.check_diagonals().is_empty());
+
+        board.make_move(Move::create(20, Player::O).unwrap());
+        assert_eq!(board.check_diagonals()[0].player(), Player::O);
+    }
+
+    #[test]
+    #[allow(unused_must_use)]
+    fn test_k_in_a_row_secondary_diagonal() {
+        // 5x5 board with 3 in a row. X wins on the diagonal 10, 14, 18.
+        let mut board = Board::with_size(Player::X, 5, 5, 3).unwrap();
+
+        for (x_position, o_position) in [(10, 1), (14, 2)] {
+            board.make_move(Move::create(x_position, Player::X).unwrap());
+            board.make_move(Move::create(o_position, Player::O).unwrap());
+        }
+        board.make_move(Move::create(18, Player::X).unwrap());
+
+        assert_eq!(board.check_diagonals()[0].player(), Player::X);
+    }
+
+    #[test]
+    fn test_rectangular_board() {
+        let mut board = Board::with_size(Player::X, 5, 2, 3).unwrap();
+
+        assert_eq!(board.get_number_of_slots(), 10);
+        assert!(board.is_slot_empty(10));
+        assert!(!board.is_slot_empty(11));
+        assert!(board
+            .make_move(Move::create(11, Player::X).unwrap())
+            .is_err());
+    }
+
+    #[test]
+    fn test_invalid_board_size() {
+        assert!(Board::with_size(Player::X, 0, 3, 3).is_err());
+        assert!(Board::with_size(Player::X, 3, 3, 0).is_err());
+        assert!(Board::with_size(Player::X, 3, 3, 4).is_err());
+        assert!(Board::with_size(Player::X, 15, 15, 5).is_ok());
+
+        // The win length is checked against the longest dimension, layers included.
+        assert!(Board::with_layers(Player::X, 3, 3, 5, 5).is_ok());
+        let err = Board::with_layers(Player::X, 3, 3, 4, 5).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("between 1 and the longest of them"));
+
+        assert!(Board::with_size(Player::X, MAX_BOARD_SIDE, MAX_BOARD_SIDE, 5).is_ok());
+        assert!(Board::with_size(Player::X, MAX_BOARD_SIDE + 1, 1, 1).is_err());
+        assert!(Board::with_layers(Player::X, MAX_BOARD_SIDE, MAX_BOARD_SIDE, 2, 3).is_err());
+        assert!(Board::with_layers(Player::X, usize::MAX, usize::MAX, 2, 3).is_err());
+    }
+
+    #[test]
+    #[allow(unused_must_use)]
+    fn test_get_next_player_on_even_sized_board() {
+        let mut board = Board::with_size(Player::O, 4, 4, 3).unwrap();
+        assert_eq!(board.get_next_player(), Player::O);
+
+        board.make_move(Move::create(1, Player::O).unwrap());
+        assert_eq!(board.get_next_player(), Player::X);
+    }
+
+    #[test]
+    fn test_undo_and_redo() {
+        let mut board = Board::new(Player::X);
+        assert_eq!(board.undo(), None);
+        assert_eq!(board.redo(), None);
+
+        let first_move = Move::create(5, Player::X).unwrap();
+        let second_move = Move::create(1, Player::O).unwrap();
+        board.make_move(first_move).unwrap();
+        board.make_move(second_move).unwrap();
+        assert_eq!(board.history(), &[first_move, second_move]);
+
+        assert_eq!(board.undo(), Some(second_move));
+        assert!(board.is_slot_empty(1));
+        assert_eq!(board.get_next_player(), Player::O);
+        assert_eq!(board.history(), &[first_move]);
+
+        assert_eq!(board.redo(), Some(BoardState::Ongoing));
+        assert!(!board.is_slot_empty(1));
+        assert_eq!(board.history(), &[first_move, second_move]);
+        assert_eq!(board.redo(), None);
+    }
+
+    #[test]
+    fn test_new_move_clears_redo() {
+        let mut board = Board::new(Player::X);
+
+        board
+            .make_move(Move::create(5, Player::X).unwrap())
+            .unwrap();
+        board.undo();
+        board
+            .make_move(Move::create(1, Player::X).unwrap())
+            .unwrap();
+
+        assert_eq!(board.redo(), None);
+        assert!(board.is_slot_empty(5));
+    }
+
+    #[test]
+    fn test_undo_winning_move() {
+        let mut board = Board::new(Player::X);
+        for position in [1, 4, 2, 5] {
+            let player_move = Move::create(position, board.get_next_player()).unwrap();
+            board.make_move(player_move).unwrap();
+        }
+        let winning_move = Move::create(3, Player::X).unwrap();
+        assert_eq!(
+            board.make_move(winning_move),
+            Ok(BoardState::Ended(Some(Player::X)))
+        );
+
+        board.undo();
+        assert_eq!(board.get_state(), BoardState::Ongoing);
+        assert_eq!(
+            board.make_move(Move::create(7, Player::X).unwrap()),
+            Ok(BoardState::Ongoing)
+        );
+    }
+
+    #[test]
+    fn test_position_str_round_trip() {
+        let board = Board::from_position_str("X1O/1X1/3 O").unwrap();
+
+        assert!(!board.is_slot_empty(1));
+        assert!(board.is_slot_empty(2));
+        assert!(!board.is_slot_empty(5));
+        assert_eq!(board.get_next_player(), Player::O);
+        assert_eq!(board.first_player(), Player::X);
+        assert_eq!(board.to_position_str(), "X1O/1X1/3 O 3");
+    }
+
+    #[test]
+    fn test_position_str_from_board() {
+        let mut board = Board::with_size(Player::O, 5, 4, 4).unwrap();
+        board
+            .make_move(Move::create(3, Player::O).unwrap())
+            .unwrap();
+        board
+            .make_move(Move::create(20, Player::X).unwrap())
+            .unwrap();
+
+        assert_eq!(board.to_position_str(), "2O2/5/5/4X O 4");
+        let loaded = Board::from_position_str(&board.to_position_str()).unwrap();
+        assert_eq!(loaded.width(), 5);
+        assert_eq!(loaded.height(), 4);
+        assert_eq!(loaded.win_length(), 4);
+        assert_eq!(loaded.get_next_player(), Player::O);
+    }
+
+    #[test]
+    fn test_position_str_accepts_dots() {
+        let board = Board::from_position_str("xo./.../... x").unwrap();
+        assert_eq!(board.to_position_str(), "XO1/3/3 X 3");
+        assert_eq!(board.get_state(), BoardState::Ongoing);
+    }
+
+    #[test]
+    fn test_position_str_ended_game() {
+        let board = Board::from_position_str("XXX/OO1/3 O").unwrap();
+        assert_eq!(board.get_state(), BoardState::Ended(Some(Player::X)));
+    }
+
+    #[test]
+    fn test_position_str_rejects_impossible_positions() {
+        assert_eq!(
+            Board::from_position_str("XX1/3/3 O").unwrap_err(),
+            PositionError::PieceCountMismatch {
+                x_count: 2,
+                o_count: 0
+            }
+        );
+        assert_eq!(
+            Board::from_position_str("X2/3/3 X").unwrap_err(),
+            PositionError::WrongSideToMove {
+                expected: Player::O
+            }
+        );
+        assert_eq!(
+            Board::from_position_str("XXX/OOO/3 X").unwrap_err(),
+            PositionError::BothPlayersWon
+        );
+        assert_eq!(
+            Board::from_position_str("XXX/OO1/O2 X").unwrap_err(),
+            PositionError::MoveAfterWin {
+                line_player: Player::X
+            }
+        );
+        assert_eq!(
+            Board::from_position_str("XXX/OO1/O2 X 3 misere").unwrap_err(),
+            PositionError::MoveAfterWin {
+                line_player: Player::X
+            }
+        );
+        assert!(Board::from_position_str("XXX/OO1/3 O 3 misere").is_ok());
+    }
+
+    #[test]
+    fn test_position_str_rejects_malformed_strings() {
+        assert_eq!(
+            Board::from_position_str("3/3/3").unwrap_err(),
+            PositionError::MissingSideToMove
+        );
+        assert_eq!(
+            Board::from_position_str("3/3/3 X 3 extra").unwrap_err(),
+            PositionError::UnknownRule("extra".to_string())
+        );
+        assert_eq!(
+            Board::from_position_str("3/3/3 X 3 misere extra").unwrap_err(),
+            PositionError::TooManyFields
+        );
+        assert_eq!(
+            Board::from_position_str("3/3/2Z X").unwrap_err(),
+            PositionError::InvalidCell('Z')
+        );
+        assert_eq!(
+            Board::from_position_str("99999999999999999999999/3/3 X").unwrap_err(),
+            PositionError::NumberTooLarge("99999999999999999999999".to_string())
+        );
+        assert_eq!(
+            Board::from_position_str("3/99999999999/3 X").unwrap_err(),
+            PositionError::RowTooLong {
+                row: 2,
+                limit: MAX_BOARD_SIDE
+            }
+        );
+        assert_eq!(
+            Board::from_position_str(&format!("X{} O", MAX_BOARD_SIDE)).unwrap_err(),
+            PositionError::RowTooLong {
+                row: 1,
+                limit: MAX_BOARD_SIDE
+            }
+        );
+        assert_eq!(
+            Board::from_position_str("3/4/3 X").unwrap_err(),
+            PositionError::RowLengthMismatch {
+                row: 2,
+                expected: 3,
+                found: 4
+            }
+        );
+        assert_eq!(
+            Board::from_position_str("3/3/3 Q").unwrap_err(),
+            PositionError::InvalidSideToMove("Q".to_string())
+        );
+        assert_eq!(
+            Board::from_position_str("3/3/3 X k").unwrap_err(),
+            PositionError::InvalidWinLength("k".to_string())
+        );
+        assert!(matches!(
+            Board::from_position_str("3/3/3 X 4"),
+            Err(PositionError::InvalidSize { .. })
+        ));
+    }
+
+    #[test]
+    fn test_misere_completing_line_loses() {
+        let mut board = Board::new(Player::X).with_misere(true);
+        for (position, player) in [
+            (1, Player::X),
+            (4, Player::O),
+            (2, Player::X),
+            (5, Player::O),
+        ] {
+            board
+                .make_move(Move::create(position, player).unwrap())
+                .unwrap();
+        }
+
+        let state = board
+            .make_move(Move::create(3, Player::X).unwrap())
+            .unwrap();
+        assert_eq!(state, BoardState::Ended(Some(Player::O)));
+        assert_eq!(board.game_winner(), Some(Player::O));
+        assert_eq!(board.winning_lines()[0].player(), Player::X);
+
+        board.undo();
+        assert_eq!(board.get_state(), BoardState::Ongoing);
+        assert_eq!(board.redo(), Some(BoardState::Ended(Some(Player::O))));
+    }
+
+    #[test]
+    fn test_position_str_with_misere() {
+        let board = Board::from_position_str("XXX/OO1/3 O 3 misere").unwrap();
+        assert!(board.is_misere());
+        assert_eq!(board.get_state(), BoardState::Ended(Some(Player::O)));
+        assert_eq!(board.to_position_str(), "XXX/OO1/3 O 3 misere");
+
+        // Switching the rule on a finished game changes who won it.
+        let board = board.with_misere(false);
+        assert_eq!(board.get_state(), BoardState::Ended(Some(Player::X)));
+        assert_eq!(board.to_position_str(), "XXX/OO1/3 O 3");
+    }
+
+    #[test]
+    fn test_make_move_errors() {
+        let mut board = Board::from_position_str("X2/1O1/3 X").unwrap();
+
+        assert_eq!(
+            board.make_move(Move::create(10, Player::X).unwrap()),
+            Err(GameError::PositionOutOfRange(10))
+        );
+        assert_eq!(
+            board.make_move(Move::create(2, Player::O).unwrap()),
+            Err(GameError::WrongPlayer {
+                expected: Player::X,
+                found: Player::O
+            })
+        );
+        assert_eq!(
+            board.make_move(Move::create(5, Player::X).unwrap()),
+            Err(GameError::SlotOccupied(5))
+        );
+        assert_eq!(
+            Move::create(0, Player::X),
+            Err(GameError::PositionOutOfRange(0))
+        );
+
+        let mut board = Board::from_position_str("XXX/OO1/3 O").unwrap();
+        assert_eq!(
+            board.make_move(Move::create(6, Player::O).unwrap()),
+            Err(GameError::GameOver)
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        colored::control::set_override(false);
+        let board = Board::from_position_str("X1O/1X1/3 O").unwrap();
+
+        assert_eq!(
+            board.display().to_string(),
+            "-------------------\n\
+             |  X  |  2  |  O  |\n\
+             -------------------\n\
+             |  4  |  X  |  6  |\n\
+             -------------------\n\
+             |  7  |  8  |  9  |\n\
+             -------------------\n\
+             Total open slots: 6"
+        );
+    }
+
+    #[test]
+    fn test_winning_lines() {
+        let board = Board::from_position_str("X1O/OX1/2O X").unwrap();
+        assert!(board.winning_lines().is_empty());
+
+        let board = Board::from_position_str("X1O/OX1/2X O").unwrap();
+        let lines = board.winning_lines();
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].player(), Player::X);
+        assert_eq!(lines[0].positions(), [1, 5, 9]);
+
+        let board = Board::from_position_str("2X/1X1/XOO O").unwrap();
+        assert_eq!(board.winning_lines()[0].positions(), [3, 5, 7]);
+
+        // A move in the middle of a line of five on a larger board.
+        let mut board = Board::from_position_str("XX1XX/5/OOOO1/5/5 X 5").unwrap();
+        board
+            .make_move(Move::create(3, Player::X).unwrap())
+            .unwrap();
+        let lines = board.winning_lines();
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].positions(), [1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn test_winning_lines_reports_every_line() {
+        // X at 1 completes the top row and the left column at once.
+        let mut board = Board::from_position_str("1XX/XOO/XOO X").unwrap();
+        board
+            .make_move(Move::create(1, Player::X).unwrap())
+            .unwrap();
+
+        let lines = board.winning_lines();
+        assert_eq!(lines.len(), 2);
+        assert!(lines.iter().all(|line| line.player() == Player::X));
+        assert_eq!(lines[0].positions(), [1, 2, 3]);
+        assert_eq!(lines[1].positions(), [1, 4, 7]);
+    }
+
+    #[test]
+    fn test_lines() {
+        assert_eq!(Board::new(Player::X).lines().len(), 8);
+
+        // 5 rows and 5 columns with 2 lines each, and 4 diagonals each way.
+        let board = Board::with_size(Player::X, 5, 5, 4).unwrap();
+        assert_eq!(board.lines().len(), 28);
+
+        let cube = Board::qubic(Player::X);
+        assert_eq!(cube.lines().len(), 76);
+        assert!(cube.lines().contains(&vec![1, 22, 43, 64]));
+        assert!(cube.lines().contains(&vec![6, 22, 38, 54]));
+    }
+
+    #[test]
+    fn test_qubic_wins_through_layers() {
+        // X fills the diagonal from the top left corner of the top layer to the bottom right
+        // corner of the bottom layer.
+        let mut board = Board::qubic(Player::X);
+        for (x, o) in [(1, 2), (22, 3), (43, 4)] {
+            board
+                .make_move(Move::create(x, Player::X).unwrap())
+                .unwrap();
+            board
+                .make_move(Move::create(o, Player::O).unwrap())
+                .unwrap();
+        }
+        assert_eq!(
+            board.make_move(Move::create(64, Player::X).unwrap()),
+            Ok(BoardState::Ended(Some(Player::X)))
+        );
+        let lines = board.winning_lines();
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].positions(), [1, 22, 43, 64]);
+
+        // O fills a column running straight down through the layers.
+        let board =
+            Board::from_position_str("4/1O2/4/4|4/1O2/X3/4|4/1O2/4/X3|XX2/1O2/4/4 X").unwrap();
+        assert_eq!(board.get_state(), BoardState::Ended(Some(Player::O)));
+        assert_eq!(board.winning_lines()[0].positions(), [6, 22, 38, 54]);
+    }
+
+    #[test]
+    fn test_coordinates() {
+        let cube = Board::qubic(Player::X);
+        assert_eq!(cube.coordinates(1), Some((1, 1, 1)));
+        assert_eq!(cube.coordinates(22), Some((2, 2, 2)));
+        assert_eq!(cube.coordinates(64), Some((4, 4, 4)));
+        assert_eq!(cube.coordinates(65), None);
+        assert_eq!(cube.position_at(2, 1, 4), Some(20));
+        assert_eq!(cube.position_at(5, 1, 1), None);
+
+        assert_eq!(cube.parse_position("2 1 4"), Some(20));
+        assert_eq!(cube.parse_position("2,1,4"), Some(20));
+        assert_eq!(cube.parse_position(" 20 "), Some(20));
+        assert_eq!(cube.parse_position("1 4"), None);
+        assert_eq!(cube.parse_position("0 1 1"), None);
+        assert_eq!(cube.parse_position("two"), None);
+
+        let board = Board::new(Player::X);
+        assert_eq!(board.coordinates(6), Some((1, 2, 3)));
+        assert_eq!(board.parse_position("2 3"), Some(6));
+        assert_eq!(board.parse_position("1 2 3"), Some(6));
+        assert_eq!(board.parse_position("10"), None);
+    }
+
+    #[test]
+    fn test_position_str_with_layers() {
+        let mut board = Board::qubic(Player::X);
+        board
+            .make_move(Move::create(22, Player::X).unwrap())
+            .unwrap();
+        let position = board.to_position_str();
+        assert_eq!(position, "4/4/4/4|4/1X2/4/4|4/4/4/4|4/4/4/4 O 4");
+
+        let loaded = Board::from_position_str(&position).unwrap();
+        assert_eq!(loaded.layers(), 4);
+        assert_eq!(loaded.win_length(), 4);
+        assert_eq!(loaded.get_player_at(22), Some(Player::X));
+        assert_eq!(loaded.zobrist_hash(), board.zobrist_hash());
+
+        assert_eq!(
+            Board::from_position_str("3/3/3|3/3 X").unwrap_err(),
+            PositionError::LayerHeightMismatch {
+                layer: 2,
+                expected: 3,
+                found: 2
+            }
+        );
+    }
+
+    #[test]
+    fn test_display_with_layers() {
+        colored::control::set_override(false);
+        let board = Board::from_position_str("X1/2|2/1O X").unwrap();
+
+        assert_eq!(
+            board.display().to_string(),
+            "Layer 1\n\
+             -------------\n\
+             |  X  |  2  |\n\
+             -------------\n\
+             |  3  |  4  |\n\
+             -------------\n\
+             Layer 2\n\
+             -------------\n\
+             |  5  |  6  |\n\
+             -------------\n\
+             |  7  |  O  |\n\
+             -------------\n\
+             Total open slots: 6"
+        );
+    }
+
+    #[test]
+    fn test_cached_winner_matches_full_check() {
+        use rand::seq::SliceRandom;
+
+        let mut rng = crate::random::seeded_rng(12);
+        for _ in 0..50 {
+            let mut board = Board::with_size(Player::X, 5, 5, 4).unwrap();
+            let mut positions: Vec<usize> = (1..=board.get_number_of_slots()).collect();
+            positions.shuffle(&mut rng);
+
+            for position in positions {
+                let player_move = Move::create(position, board.get_next_player()).unwrap();
+                let state = board.make_move(player_move).unwrap();
+                assert_eq!(board.winner, board.game_winner());
+                assert_eq!(
+                    board.get_number_of_open_slots(),
+                    board
+                        .cells
+                        .iter()
+                        .flatten()
+                        .filter(|cell| cell.is_none())
+                        .count()
+                );
+                if state != BoardState::Ongoing {
+                    break;
+                }
+            }
+
+            while board.undo().is_some() {
+                assert_eq!(board.winner, board.game_winner());
+            }
+            assert_eq!(board.get_number_of_open_slots(), 25);
+        }
+    }
+
+    #[test]
+    fn test_cached_winner_matches_full_check_on_cube() {
+        use rand::seq::SliceRandom;
+
+        let mut rng = crate::random::seeded_rng(13);
+        for _ in 0..50 {
+            let mut board = Board::with_layers(Player::X, 3, 3, 3, 3).unwrap();
+            let mut positions: Vec<usize> = (1..=board.get_number_of_slots()).collect();
+            positions.shuffle(&mut rng);
+
+            for position in positions {
+                let player_move = Move::create(position, board.get_next_player()).unwrap();
+                let state = board.make_move(player_move).unwrap();
+                assert_eq!(board.winner, board.game_winner());
+                if state != BoardState::Ongoing {
+                    break;
+                }
+            }
+
+            while board.undo().is_some() {
+                assert_eq!(board.winner, board.game_winner());
+            }
+        }
+    }
+
+    #[test]
+    fn test_zobrist_hash_ignores_move_order() {
+        let mut board_1 = Board::new(Player::X);
+        let mut board_2 = Board::new(Player::X);
+        let empty_hash = board_1.zobrist_hash();
+
+        for position in [1, 5, 9] {
+            board_1
+                .make_move(Move::create(position, board_1.get_next_player()).unwrap())
+                .unwrap();
+        }
+        for position in [9, 5, 1] {
+            board_2
+                .make_move(Move::create(position, board_2.get_next_player()).unwrap())
+                .unwrap();
+        }
+
+        assert_eq!(board_1.zobrist_hash(), board_2.zobrist_hash());
+        assert_eq!(
+            board_1.zobrist_hash(),
+            Board::from_position_str("X2/1O1/2X O")
+                .unwrap()
+                .zobrist_hash()
+        );
+
+        while board_1.undo().is_some() {}
+        assert_eq!(board_1.zobrist_hash(), empty_hash);
+        board_1.redo();
+        assert_eq!(
+            board_1.zobrist_hash(),
+            Board::from_position_str("X2/3/3 O").unwrap().zobrist_hash()
+        );
+    }
+
+    #[test]
+    fn test_zobrist_hash_depends_on_board() {
+        let board = Board::from_position_str("X2/3/3 O").unwrap();
+        assert_ne!(
+            board.zobrist_hash(),
+            Board::from_position_str("1X1/3/3 O")
+                .unwrap()
+                .zobrist_hash()
+        );
+        assert_ne!(
+            board.zobrist_hash(),
+            Board::from_position_str("X3/4/4/4 O")
+                .unwrap()
+                .zobrist_hash()
+        );
+        assert_ne!(
+            Board::new(Player::X).zobrist_hash(),
+            Board::new(Player::O).zobrist_hash()
+        );
+        assert_ne!(
+            Board::new(Player::X).zobrist_hash(),
+            Board::new(Player::X).with_misere(true).zobrist_hash()
+        );
+    }
+
+    #[test]
+    fn test_canonical_hash_folds_symmetries() {
+        // The same shape turned and flipped into each of its eight orientations.
+        let positions = [
+            "XO1/3/3 X",
+            "2X/2O/3 X",
+            "3/3/1OX X",
+            "3/O2/X2 X",
+            "1OX/3/3 X",
+            "3/3/XO1 X",
+            "X2/O2/3 X",
+            "3/2O/2X X",
+        ];
+        let canonical = Board::from_position_str(positions[0])
+            .unwrap()
+            .canonical_form();
+
+        for position in positions {
+            let board = Board::from_position_str(position).unwrap();
+            assert_eq!(board.canonical_hash(), canonical.zobrist_hash());
+            assert_eq!(
+                board.canonical_form().to_position_str(),
+                canonical.to_position_str()
+            );
+        }
+
+        let different = Board::from_position_str("X1O/3/3 X").unwrap();
+        assert_ne!(different.canonical_hash(), canonical.zobrist_hash());
+    }
+
+    #[test]
+    fn test_canonical_hash_on_rectangular_board() {
+        let board = Board::from_position_str("X3/4/4 O 3").unwrap();
+        let mirrored = Board::from_position_str("4/4/3X O 3").unwrap();
+        // Turning a 4x3 board a quarter turn does not give a 4x3 board.
+        let column = Board::from_position_str("X2/3/3/3 O 3").unwrap();
+
+        assert_eq!(board.canonical_hash(), mirrored.canonical_hash());
+        assert_ne!(board.canonical_hash(), column.canonical_hash());
+    }
 }
@@ -0,0 +1,583 @@
+// tournament.rs
+
+//! Tournaments between many computer players.
+//!
+//! A `Tournament` holds a roster of brains and a pairing system. Each round, every pairing
+//! plays a short match (see `arena::play_match`), taking turns to go first. Results are
+//! counted as points (1 for a win, 0.5 for a draw) and as Elo ratings, which are updated
+//! after every game.
+//!
+//! ```
+//! use tic_tac_toe::tournament::{Pairing, Tournament};
+//! use tic_tac_toe::{Board, BrainLevelOne, BrainLevelTwo, Player};
+//!
+//! let mut tournament = Tournament::new(Board::new(Player::X), Pairing::RoundRobin, 2);
+//! tournament.add("Minimax", Box::new(BrainLevelTwo));
+//! tournament.add("Random", Box::new(BrainLevelOne::new(7)));
+//!
+//! let results = tournament.run()?;
+//! assert_eq!(results.standings[0].name, "Minimax");
+//! println!("{}", results.standings_table());
+//! # Ok::<(), tic_tac_toe::GameError>(())
+//! ```
+use crate::arena::{play_match, GameRecord, Outcome};
+use crate::brains::TicTacToeBrain;
+use crate::error::GameError;
+use crate::tic_tac_toe::{Board, BoardState, Player};
+
+/// Rating every entrant starts with.
+pub const INITIAL_RATING: f64 = 1500.0;
+
+/// How far one game can move a rating.
+const ELO_K_FACTOR: f64 = 32.0;
+
+/// How entrants are paired up each round.
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub enum Pairing {
+    /// Every entrant plays every other entrant once. With `n` entrants this takes `n - 1`
+    /// rounds, or `n` if `n` is odd.
+    RoundRobin,
+    /// Entrants with similar points play each other, and nobody plays the same opponent
+    /// twice if it can be avoided. Suits big rosters where a round robin takes too long.
+    Swiss { rounds: usize },
+}
+
+/// A brain taking part in a tournament.
+pub struct Entrant {
+    pub name: String,
+    pub brain: Box<dyn TicTacToeBrain>,
+}
+
+/// A game played in a tournament.
+#[derive(Debug, Clone)]
+pub struct TournamentGame {
+    /// The round the game was played in, starting from 1.
+    pub round: usize,
+    /// The index in the roster of the entrant counted as brain A in `record`.
+    pub a: usize,
+    /// The index in the roster of the other entrant.
+    pub b: usize,
+    pub record: GameRecord,
+}
+
+impl TournamentGame {
+    /// Returns the roster index of the entrant who played `player`.
+    pub fn entrant_for(&self, player: Player) -> usize {
+        if self.record.a_player == player {
+            self.a
+        } else {
+            self.b
+        }
+    }
+}
+
+/// One line of the standings table.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Standing {
+    pub name: String,
+    pub games: usize,
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+    /// 1 for a win, 0.5 for a draw. Byes are worth a win's points without counting as a game.
+    pub points: f64,
+    pub rating: f64,
+}
+
+/// The results of a tournament.
+#[derive(Debug, Clone)]
+pub struct TournamentResults {
+    /// One line per entrant, best first: by points, then rating.
+    pub standings: Vec<Standing>,
+    /// Every game, in the order it was played.
+    pub games: Vec<TournamentGame>,
+    /// The entrant's name for each roster index, to look up `TournamentGame::a` and `b`.
+    pub names: Vec<String>,
+}
+
+impl TournamentResults {
+    /// Returns the standings as a text table.
+    pub fn standings_table(&self) -> String {
+        let name_width = self
+            .standings
+            .iter()
+            .map(|standing| standing.name.len())
+            .chain(std::iter::once(4))
+            .max()
+            .unwrap_or(4);
+
+        let mut table = format!(
+            "{:>3}  {:<name_width$}  {:>5}  {:>4}  {:>4}  {:>4}  {:>6}  {:>6}\n",
+            "#", "Name", "Games", "W", "D", "L", "Points", "Elo"
+        );
+        for (rank, standing) in self.standings.iter().enumerate() {
+            table += &format!(
+                "{:>3}  {:<name_width$}  {:>5}  {:>4}  {:>4}  {:>4}  {:>6.1}  {:>6.0}\n",
+                rank + 1,
+                standing.name,
+                standing.games,
+                standing.wins,
+                standing.draws,
+                standing.losses,
+                standing.points,
+                standing.rating
+            );
+        }
+
+        table
+    }
+
+    /// Returns one line per game: the round, who played X, who played O, the result
+    /// (`X`, `O` or `Draw`), the position string the game started from and the moves,
+    /// separated by tabs. Each game can be replayed by loading its start position with
+    /// `Board::from_position_str` and playing the moves.
+    pub fn game_records(&self) -> String {
+        let mut records = "Round\tX\tO\tResult\tStart\tMoves\n".to_string();
+        for game in &self.games {
+            let result = match game.record.board.get_state() {
+                BoardState::Ended(Some(winner)) => {
+                    Player::get_player_char_from_enum(&winner).to_string()
+                }
+                _ => "Draw".to_string(),
+            };
+            let moves: Vec<String> = game
+                .record
+                .board
+                .history()
+                .iter()
+                .map(|player_move| player_move.position().to_string())
+                .collect();
+
+            records += &format!(
+                "{}\t{}\t{}\t{}\t{}\t{}\n",
+                game.round,
+                self.names[game.entrant_for(Player::X)],
+                self.names[game.entrant_for(Player::O)],
+                result,
+                game.record.board.start_board().to_position_str(),
+                moves.join(" ")
+            );
+        }
+
+        records
+    }
+}
+
+/// A tournament between a roster of brains.
+pub struct Tournament {
+    entrants: Vec<Entrant>,
+    start: Board,
+    pairing: Pairing,
+    games_per_pairing: usize,
+}
+
+impl Tournament {
+    /// Creates a tournament with no entrants.
+    ///
+    /// # Parameters
+    ///
+    /// - `start`: The board every game starts from, usually an empty one.
+    /// - `pairing`: How entrants are paired each round.
+    /// - `games_per_pairing`: How many games each pairing plays per round. Use an even
+    ///   number so both entrants go first equally often.
+    pub fn new(start: Board, pairing: Pairing, games_per_pairing: usize) -> Self {
+        Tournament {
+            entrants: Vec::new(),
+            start,
+            pairing,
+            games_per_pairing,
+        }
+    }
+
+    /// Adds a brain to the roster.
+    pub fn add(&mut self, name: &str, brain: Box<dyn TicTacToeBrain>) {
+        self.entrants.push(Entrant {
+            name: name.to_string(),
+            brain,
+        });
+    }
+
+    /// Returns the roster.
+    pub fn entrants(&self) -> &[Entrant] {
+        &self.entrants
+    }
+
+    /// Plays every round of the tournament.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(TournamentResults)` with the standings and every game played.
+    /// - `Err(GameError::BrainFailure)` naming the entrant if a brain is too slow to play on
+    ///   the start board, checked before any game is played.
+    /// - `Err(GameError)` if the start board has already ended, or a brain fails to choose a
+    ///   move or chooses an illegal one.
+    pub fn run(&self) -> Result<TournamentResults, GameError> {
+        if self.start.get_state() != BoardState::Ongoing {
+            return Err(GameError::GameOver);
+        }
+        if let Some(entrant) = self
+            .entrants
+            .iter()
+            .find(|entrant| !entrant.brain.can_play(&self.start))
+        {
+            return Err(GameError::BrainFailure(format!(
+                "{} is too slow to play on a {} board",
+                entrant.name,
+                self.start.size_text()
+            )));
+        }
+
+        let mut standings: Vec<Standing> = self
+            .entrants
+            .iter()
+            .map(|entrant| Standing {
+                name: entrant.name.clone(),
+                games: 0,
+                wins: 0,
+                draws: 0,
+                losses: 0,
+                points: 0.0,
+                rating: INITIAL_RATING,
+            })
+            .collect();
+        let mut games: Vec<TournamentGame> = Vec::new();
+        let mut played: Vec<(usize, usize)> = Vec::new();
+
+        let rounds = match self.pairing {
+            Pairing::RoundRobin => round_robin_rounds(self.entrants.len()),
+            Pairing::Swiss { rounds } => rounds,
+        };
+
+        for round in 1..=rounds {
+            let (pairs, bye) = match self.pairing {
+                Pairing::RoundRobin => round_robin_pairs(self.entrants.len(), round - 1),
+                Pairing::Swiss { .. } => swiss_pairs(&standings, &played),
+            };
+
+            if let Some(bye) = bye {
+                standings[bye].points += self.games_per_pairing as f64;
+            }
+
+            for (a, b) in pairs {
+                played.push((a, b));
+                let report = play_match(
+                    self.entrants[a].brain.as_ref(),
+                    self.entrants[b].brain.as_ref(),
+                    &self.start,
+                    self.games_per_pairing,
+                )?;
+
+                for record in report.records {
+                    record_result(&mut standings, a, b, record.outcome());
+                    games.push(TournamentGame {
+                        round,
+                        a,
+                        b,
+                        record,
+                    });
+                }
+            }
+        }
+
+        standings.sort_by(|first, second| {
+            second
+                .points
+                .total_cmp(&first.points)
+                .then(second.rating.total_cmp(&first.rating))
+        });
+
+        Ok(TournamentResults {
+            standings,
+            games,
+            names: self
+                .entrants
+                .iter()
+                .map(|entrant| entrant.name.clone())
+                .collect(),
+        })
+    }
+}
+
+/// Returns the number of rounds a round robin between `entrants` takes.
+fn round_robin_rounds(entrants: usize) -> usize {
+    match entrants {
+        0 | 1 => 0,
+        entrants if entrants.is_multiple_of(2) => entrants - 1,
+        entrants => entrants,
+    }
+}
+
+/// Returns the pairs for one round of a round robin, and the entrant with a bye, if any.
+///
+/// Uses the circle method: entrant 0 stays in place while the others rotate one seat each
+/// round, so over all rounds everyone meets everyone exactly once.
+fn round_robin_pairs(entrants: usize, round: usize) -> (Vec<(usize, usize)>, Option<usize>) {
+    // An odd roster gets an empty seat, and whoever sits opposite it has a bye.
+    let seats = entrants + entrants % 2;
+    let rotating = seats - 1;
+    let seat = |index: usize| -> usize {
+        if index == 0 {
+            0
+        } else {
+            (index - 1 + round) % rotating + 1
+        }
+    };
+
+    let mut pairs = Vec::new();
+    let mut bye = None;
+    for index in 0..seats / 2 {
+        let (a, b) = (seat(index), seat(seats - 1 - index));
+        match (a < entrants, b < entrants) {
+            // Swap sides every round so entrant 0 is not always brain A.
+            (true, true) if round.is_multiple_of(2) => pairs.push((a, b)),
+            (true, true) => pairs.push((b, a)),
+            (true, false) => bye = Some(a),
+            (false, true) => bye = Some(b),
+            (false, false) => {}
+        }
+    }
+
+    (pairs, bye)
+}
+
+/// Returns the pairs for a Swiss round, and the entrant with a bye, if any.
+///
+/// Entrants are ranked by points and rating, and each one is paired with the next highest
+/// ranked entrant they have not played yet. If everyone left has already been played, the
+/// next highest ranked entrant is used. With an odd roster, the lowest ranked entrant who
+/// has not had a bye sits out.
+fn swiss_pairs(
+    standings: &[Standing],
+    played: &[(usize, usize)],
+) -> (Vec<(usize, usize)>, Option<usize>) {
+    let mut ranked: Vec<usize> = (0..standings.len()).collect();
+    ranked.sort_by(|&first, &second| {
+        standings[second]
+            .points
+            .total_cmp(&standings[first].points)
+            .then(standings[second].rating.total_cmp(&standings[first].rating))
+            .then(first.cmp(&second))
+    });
+
+    let mut bye = None;
+    if ranked.len() % 2 == 1 {
+        // Entrants who had a bye have more points than games can explain.
+        let had_bye = |entrant: usize| {
+            let standing = &standings[entrant];
+            standing.points
+                > standing.games as f64 - standing.losses as f64 - standing.draws as f64 / 2.0
+        };
+        let index = ranked
+            .iter()
+            .rposition(|&entrant| !had_bye(entrant))
+            .unwrap_or(ranked.len() - 1);
+        bye = Some(ranked.remove(index));
+    }
+
+    let have_played =
+        |a: usize, b: usize| played.iter().any(|&pair| pair == (a, b) || pair == (b, a));
+
+    let mut pairs = Vec::new();
+    while !ranked.is_empty() {
+        let a = ranked.remove(0);
+        let index = ranked.iter().position(|&b| !have_played(a, b)).unwrap_or(0);
+        let b = ranked.remove(index);
+        pairs.push((a, b));
+    }
+
+    (pairs, bye)
+}
+
+/// Updates the standings of entrants `a` and `b` after a game that ended in `outcome` for `a`.
+fn record_result(standings: &mut [Standing], a: usize, b: usize, outcome: Outcome) {
+    let expected = 1.0 / (1.0 + 10f64.powf((standings[b].rating - standings[a].rating) / 400.0));
+    let change = ELO_K_FACTOR * (outcome.score() - expected);
+    standings[a].rating += change;
+    standings[b].rating -= change;
+
+    standings[a].points += outcome.score();
+    standings[b].points += 1.0 - outcome.score();
+    standings[a].games += 1;
+    standings[b].games += 1;
+
+    match outcome {
+        Outcome::Win => {
+            standings[a].wins += 1;
+            standings[b].losses += 1;
+        }
+        Outcome::Draw => {
+            standings[a].draws += 1;
+            standings[b].draws += 1;
+        }
+        Outcome::Loss => {
+            standings[a].losses += 1;
+            standings[b].wins += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::brains::{BrainLevelOne, BrainLevelTwo};
+    use crate::tic_tac_toe::Move;
+
+    fn roster(tournament: &mut Tournament) {
+        tournament.add("Minimax", Box::new(BrainLevelTwo));
+        tournament.add("Random 1", Box::new(BrainLevelOne::new(1)));
+        tournament.add("Random 2", Box::new(BrainLevelOne::new(2)));
+    }
+
+    #[test]
+    fn test_round_robin_pairs_everyone_once() {
+        for entrants in 2..=7 {
+            let mut pairs: Vec<(usize, usize)> = Vec::new();
+            for round in 0..round_robin_rounds(entrants) {
+                let (round_pairs, bye) = round_robin_pairs(entrants, round);
+                assert_eq!(bye.is_some(), entrants % 2 == 1);
+                pairs.extend(round_pairs.iter().map(|&(a, b)| (a.min(b), a.max(b))));
+            }
+
+            pairs.sort();
+            pairs.dedup();
+            assert_eq!(pairs.len(), entrants * (entrants - 1) / 2);
+        }
+    }
+
+    #[test]
+    fn test_round_robin_tournament() {
+        let mut tournament = Tournament::new(Board::new(Player::X), Pairing::RoundRobin, 4);
+        roster(&mut tournament);
+        let results = tournament.run().unwrap();
+
+        // 3 pairings of 4 games each.
+        assert_eq!(results.games.len(), 12);
+        assert_eq!(results.standings[0].name, "Minimax");
+        assert_eq!(results.standings[0].losses, 0);
+        assert!(results.standings[0].rating > INITIAL_RATING);
+        assert!(results.standings.iter().all(|standing| standing.games == 8));
+
+        // Ratings only move between entrants.
+        let total: f64 = results
+            .standings
+            .iter()
+            .map(|standing| standing.rating)
+            .sum();
+        assert!((total - 3.0 * INITIAL_RATING).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_swiss_tournament() {
+        let mut tournament =
+            Tournament::new(Board::new(Player::X), Pairing::Swiss { rounds: 3 }, 2);
+        roster(&mut tournament);
+        tournament.add("Random 3", Box::new(BrainLevelOne::new(3)));
+        let results = tournament.run().unwrap();
+
+        // 2 pairings of 2 games in each of 3 rounds, and no repeated pairings.
+        assert_eq!(results.games.len(), 12);
+        let mut pairs: Vec<(usize, usize)> = results
+            .games
+            .iter()
+            .map(|game| (game.a.min(game.b), game.a.max(game.b)))
+            .collect();
+        pairs.dedup();
+        assert_eq!(pairs.len(), 6);
+        pairs.sort();
+        pairs.dedup();
+        assert_eq!(pairs.len(), 6);
+    }
+
+    #[test]
+    fn test_swiss_bye() {
+        let mut tournament =
+            Tournament::new(Board::new(Player::X), Pairing::Swiss { rounds: 3 }, 2);
+        roster(&mut tournament);
+        let results = tournament.run().unwrap();
+
+        // One pairing per round, and each entrant sits out once.
+        assert_eq!(results.games.len(), 6);
+        assert!(results.standings.iter().all(|standing| standing.games == 4));
+    }
+
+    #[test]
+    fn test_output() {
+        let mut tournament = Tournament::new(Board::new(Player::X), Pairing::RoundRobin, 2);
+        roster(&mut tournament);
+        let results = tournament.run().unwrap();
+
+        let table = results.standings_table();
+        assert!(table.starts_with("  #  Name"));
+        assert!(table.lines().nth(1).unwrap().contains("Minimax"));
+        assert_eq!(table.lines().count(), 4);
+
+        let records = results.game_records();
+        assert_eq!(records.lines().count(), 7);
+        assert!(records.lines().nth(1).unwrap().starts_with("1\t"));
+    }
+
+    #[test]
+    fn test_game_records_replay_from_the_start_position() {
+        let start = Board::from_position_str("X2/1O1/3 X").unwrap();
+        let mut tournament = Tournament::new(start, Pairing::RoundRobin, 2);
+        roster(&mut tournament);
+        let results = tournament.run().unwrap();
+
+        for record in results.game_records().lines().skip(1) {
+            let fields: Vec<&str> = record.split('\t').collect();
+            assert_eq!(fields[4], "X2/1O1/3 X 3");
+
+            let mut board = Board::from_position_str(fields[4]).unwrap();
+            let mut state = BoardState::Ongoing;
+            for position in fields[5].split_whitespace() {
+                let player_move =
+                    Move::create(position.parse().unwrap(), board.get_next_player()).unwrap();
+                state = board.make_move(player_move).unwrap();
+            }
+            let result = match state {
+                BoardState::Ended(Some(winner)) => {
+                    Player::get_player_char_from_enum(&winner).to_string()
+                }
+                _ => "Draw".to_string(),
+            };
+            assert_eq!(fields[3], result);
+        }
+    }
+
+    #[test]
+    fn test_rejects_slow_entrants() {
+        let start = Board::with_size(Player::X, 4, 4, 4).unwrap();
+        let mut tournament = Tournament::new(start, Pairing::RoundRobin, 2);
+        roster(&mut tournament);
+        assert_eq!(
+            tournament.run().unwrap_err(),
+            GameError::BrainFailure("Minimax is too slow to play on a 4x4 board".to_string())
+        );
+    }
+
+    #[test]
+    fn test_elo_update() {
+        let mut standings = vec![
+            Standing {
+                name: "A".to_string(),
+                games: 0,
+                wins: 0,
+                draws: 0,
+                losses: 0,
+                points: 0.0,
+                rating: INITIAL_RATING,
+            };
+            2
+        ];
+
+        record_result(&mut standings, 0, 1, Outcome::Win);
+        assert_eq!(standings[0].rating, INITIAL_RATING + 16.0);
+        assert_eq!(standings[1].rating, INITIAL_RATING - 16.0);
+
+        // A draw against a weaker opponent costs the favourite rating.
+        record_result(&mut standings, 0, 1, Outcome::Draw);
+        assert!(standings[0].rating < INITIAL_RATING + 16.0);
+        assert_eq!(standings[0].points, 1.5);
+        assert_eq!((standings[1].losses, standings[1].draws), (1, 1));
+    }
+}
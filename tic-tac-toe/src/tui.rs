@@ -0,0 +1,426 @@
+// tui.rs
+
+//! A full-screen terminal UI, as an alternative to typing slot numbers at a prompt.
+//!
+//! The board is drawn with a cursor on one cell. Players move
+//! the cursor with the arrow keys or `h`, `j`, `k` and `l`, and play the cell under it with
+//! Enter or Space. The last move and a winning line are highlighted, the moves played are
+//! listed next to the board, and a status bar shows whose turn it is and the keys.
+use crate::{is_human_turn, redo_turn, undo_turn, GameMode};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{
+    Attribute, Color, Print, ResetColor, SetAttribute, SetBackgroundColor, SetForegroundColor,
+};
+use crossterm::terminal::{self, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{cursor, execute, queue};
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+use tic_tac_toe::{Board, BoardState, Move, Player};
+
+/// How long to wait for a key before checking whether a computer should move.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// The keys shown in the status bar.
+const KEY_HELP: &str = "arrows/hjkl move  Enter play  u undo  r redo  q quit";
+
+/// A way to move the cursor.
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+/// What a key press asks for.
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+enum Action {
+    Move(Direction),
+    Play,
+    Undo,
+    Redo,
+    Quit,
+}
+
+/// Returns the action for a key, or `None` if the key does nothing.
+fn action_for_key(key: KeyEvent) -> Option<Action> {
+    if key.kind != KeyEventKind::Press {
+        return None;
+    }
+
+    match key.code {
+        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => Some(Action::Quit),
+        KeyCode::Up | KeyCode::Char('k') => Some(Action::Move(Direction::Up)),
+        KeyCode::Down | KeyCode::Char('j') => Some(Action::Move(Direction::Down)),
+        KeyCode::Left | KeyCode::Char('h') => Some(Action::Move(Direction::Left)),
+        KeyCode::Right | KeyCode::Char('l') => Some(Action::Move(Direction::Right)),
+        KeyCode::Enter | KeyCode::Char(' ') => Some(Action::Play),
+        KeyCode::Char('u') => Some(Action::Undo),
+        KeyCode::Char('r') => Some(Action::Redo),
+        KeyCode::Char('q') | KeyCode::Esc => Some(Action::Quit),
+        _ => None,
+    }
+}
+
+/// Returns the cursor moved one cell in `direction`, staying on a board of `width` by
+/// `height` cells.
+fn move_cursor(
+    (row, col): (usize, usize),
+    direction: Direction,
+    width: usize,
+    height: usize,
+) -> (usize, usize) {
+    match direction {
+        Direction::Up => (row.saturating_sub(1), col),
+        Direction::Down => ((row + 1).min(height - 1), col),
+        Direction::Left => (row, col.saturating_sub(1)),
+        Direction::Right => (row, (col + 1).min(width - 1)),
+    }
+}
+
+/// Returns the text for the status bar.
+fn status(board: &Board, game_mode: &GameMode) -> String {
+    let name = |player: Player| Player::get_player_char_from_enum(&player);
+
+    match board.get_state() {
+        BoardState::Ended(Some(winner)) => format!("Player {} won the game!!", name(winner)),
+        BoardState::Ended(None) => "Tie Game!".to_string(),
+        BoardState::Ongoing if is_human_turn(board, game_mode) => {
+            format!("{} to move", name(board.get_next_player()))
+        }
+        BoardState::Ongoing => format!("Computer {} is thinking...", name(board.get_next_player())),
+    }
+}
+
+/// Returns the cursor's cell as a slot number.
+fn cursor_slot(board: &Board, (row, col): (usize, usize)) -> usize {
+    row * board.width() + col + 1
+}
+
+/// Puts the terminal back the way it was, even if the UI panics.
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn enter() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), EnterAlternateScreen, cursor::Hide)?;
+        Ok(TerminalGuard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), cursor::Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// Plays the game in the terminal UI until it ends or the player quits.
+///
+/// When the game ends the final board stays on screen until a key is pressed.
+///
+/// # Returns
+///
+/// - `Ok(())` when the game has ended or the player quit.
+/// - `Err(io::Error)` if a computer player is too slow to play on the board, checked before
+///   the terminal is touched, or if the terminal could not be used.
+pub fn run(board: &mut Board, game_mode: &GameMode) -> io::Result<()> {
+    check_brains(board, game_mode)?;
+    let _guard = TerminalGuard::enter()?;
+    let mut stdout = io::stdout();
+    let mut cursor = (board.height() / 2, board.width() / 2);
+    let mut message = String::new();
+    let mut redraw = true;
+    // When the computer may move next, so watch mode can pause between moves.
+    let mut next_computer_move = Instant::now();
+    // A computer that failed to move is not asked again until a move is undone or redone.
+    let mut computer_failed = false;
+
+    loop {
+        if redraw {
+            draw(&mut stdout, board, game_mode, cursor, &message)?;
+            redraw = false;
+        }
+
+        let computer_to_move = board.get_state() == BoardState::Ongoing
+            && !is_human_turn(board, game_mode)
+            && !computer_failed;
+        if computer_to_move && Instant::now() >= next_computer_move {
+            match computer_turn(board, game_mode) {
+                Ok(position) => {
+                    cursor = (
+                        (position - 1) / board.width(),
+                        (position - 1) % board.width(),
+                    );
+                }
+                Err(err) => {
+                    message = err;
+                    computer_failed = true;
+                }
+            }
+            if let GameMode::ComputerVsComputer(_, _, delay) = game_mode {
+                next_computer_move = Instant::now() + *delay;
+            }
+            redraw = true;
+            continue;
+        }
+
+        if !event::poll(POLL_INTERVAL)? {
+            continue;
+        }
+        let key = match event::read()? {
+            Event::Key(key) if key.kind == KeyEventKind::Press => key,
+            Event::Resize(..) => {
+                redraw = true;
+                continue;
+            }
+            _ => continue,
+        };
+        redraw = true;
+        message.clear();
+
+        let game_over = board.get_state() != BoardState::Ongoing;
+        match action_for_key(key) {
+            Some(Action::Quit) => return Ok(()),
+            Some(Action::Undo) => match undo_turn(board, game_mode) {
+                Ok(()) => computer_failed = false,
+                Err(err) => message = err.to_string(),
+            },
+            Some(Action::Redo) => match redo_turn(board, game_mode) {
+                Ok(()) => computer_failed = false,
+                Err(err) => message = err.to_string(),
+            },
+            // Any other key leaves a finished game.
+            _ if game_over => return Ok(()),
+            Some(Action::Move(direction)) => {
+                cursor = move_cursor(cursor, direction, board.width(), board.height());
+            }
+            Some(Action::Play) if is_human_turn(board, game_mode) => {
+                let player_move = Move::create(cursor_slot(board, cursor), board.get_next_player());
+                if let Err(err) = player_move.and_then(|player_move| board.make_move(player_move)) {
+                    message = err.to_string();
+                }
+            }
+            Some(Action::Play) | None => {}
+        }
+    }
+}
+
+/// Returns an error if a computer player in `game_mode` is too slow to play on `board`.
+fn check_brains(board: &Board, game_mode: &GameMode) -> io::Result<()> {
+    let brains = match game_mode {
+        GameMode::AgainstComputer(_, brain) => vec![brain],
+        GameMode::ComputerVsComputer(x_brain, o_brain, _) => vec![x_brain, o_brain],
+        GameMode::AgainstHuman => Vec::new(),
+    };
+    if brains.iter().all(|brain| brain.can_play(board)) {
+        Ok(())
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "The computer player is too slow to play on a {} board",
+                board.size_text()
+            ),
+        ))
+    }
+}
+
+/// Lets the computer whose turn it is move.
+///
+/// Returns the slot it played, or a message if it could not move.
+fn computer_turn(board: &mut Board, game_mode: &GameMode) -> Result<usize, String> {
+    let brain = match game_mode {
+        GameMode::AgainstComputer(_, brain) => brain,
+        GameMode::ComputerVsComputer(x_brain, o_brain, _) => match board.get_next_player() {
+            Player::X => x_brain,
+            Player::O => o_brain,
+        },
+        GameMode::AgainstHuman => return Err("There is no computer player".to_string()),
+    };
+
+    let player_move = brain.make_move(board).map_err(|err| err.to_string())?;
+    board
+        .make_move(player_move)
+        .map_err(|err| err.to_string())?;
+    Ok(player_move.position())
+}
+
+/// Draws the whole screen.
+fn draw(
+    out: &mut impl Write,
+    board: &Board,
+    game_mode: &GameMode,
+    cursor: (usize, usize),
+    message: &str,
+) -> io::Result<()> {
+    let (columns, rows) = terminal::size()?;
+    let winning: Vec<usize> = board
+        .winning_lines()
+        .iter()
+        .flat_map(|line| line.positions().to_vec())
+        .collect();
+    let last_move = board.history().last().map(Move::position);
+    let show_cursor = board.get_state() == BoardState::Ongoing && is_human_turn(board, game_mode);
+
+    queue!(out, terminal::Clear(ClearType::All), cursor::MoveTo(1, 0))?;
+    queue!(
+        out,
+        SetAttribute(Attribute::Bold),
+        Print(format!(
+            "Tic Tac Toe: {}x{} board, get {} in a row to win",
+            board.width(),
+            board.height(),
+            board.win_length()
+        )),
+        SetAttribute(Attribute::Reset)
+    )?;
+
+    // Each cell is three characters wide with a line between cells.
+    let top = 2;
+    for row in 0..board.height() {
+        queue!(out, cursor::MoveTo(2, top + 2 * row as u16))?;
+        for col in 0..board.width() {
+            let position = row * board.width() + col + 1;
+            let mark = board
+                .get_player_at(position)
+                .map_or('·', |player| Player::get_player_char_from_enum(&player));
+
+            if show_cursor && (row, col) == cursor {
+                queue!(out, SetAttribute(Attribute::Reverse))?;
+            }
+            if winning.contains(&position) {
+                queue!(
+                    out,
+                    SetBackgroundColor(Color::Green),
+                    SetForegroundColor(Color::Black)
+                )?;
+            } else if last_move == Some(position) {
+                queue!(
+                    out,
+                    SetForegroundColor(Color::Yellow),
+                    SetAttribute(Attribute::Bold)
+                )?;
+            } else if mark == '·' {
+                queue!(out, SetForegroundColor(Color::DarkGrey))?;
+            }
+            queue!(
+                out,
+                Print(format!(" {} ", mark)),
+                SetAttribute(Attribute::Reset),
+                ResetColor
+            )?;
+
+            if col + 1 < board.width() {
+                queue!(out, Print("│"))?;
+            }
+        }
+
+        if row + 1 < board.height() {
+            let separator = vec!["───"; board.width()].join("┼");
+            queue!(
+                out,
+                cursor::MoveTo(2, top + 2 * row as u16 + 1),
+                Print(separator)
+            )?;
+        }
+    }
+
+    // The move list sits to the right of the board, showing the latest moves that fit.
+    let list_column = 2 + 4 * board.width() as u16 + 3;
+    let list_rows = rows.saturating_sub(top + 4).max(1) as usize;
+    queue!(
+        out,
+        cursor::MoveTo(list_column, top),
+        SetAttribute(Attribute::Underlined),
+        Print("Moves"),
+        SetAttribute(Attribute::Reset)
+    )?;
+    let history = board.history();
+    let first_shown = history.len().saturating_sub(list_rows - 1);
+    for (index, player_move) in history.iter().enumerate().skip(first_shown) {
+        queue!(
+            out,
+            cursor::MoveTo(list_column, top + 1 + (index - first_shown) as u16),
+            Print(format!(
+                "{:>3}. {} {}",
+                index + 1,
+                Player::get_player_char_from_enum(&player_move.player()),
+                player_move.position()
+            ))
+        )?;
+    }
+
+    // The status bar fills the last line, with any message above it.
+    let mut status_line = format!(" {}", status(board, game_mode));
+    if board.get_state() == BoardState::Ongoing {
+        status_line = format!("{}   {}", status_line, KEY_HELP);
+    } else {
+        status_line = format!("{}   Press any key to exit", status_line);
+    }
+    let status_line: String = format!("{:<width$}", status_line, width = columns as usize)
+        .chars()
+        .take(columns as usize)
+        .collect();
+
+    if !message.is_empty() {
+        queue!(
+            out,
+            cursor::MoveTo(1, rows.saturating_sub(2)),
+            SetForegroundColor(Color::Red),
+            Print(message),
+            ResetColor
+        )?;
+    }
+    queue!(
+        out,
+        cursor::MoveTo(0, rows.saturating_sub(1)),
+        SetAttribute(Attribute::Reverse),
+        Print(status_line),
+        SetAttribute(Attribute::Reset)
+    )?;
+
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tic_tac_toe::{BrainLevelThree, BrainLevelTwo};
+
+    #[test]
+    fn test_slow_brains_are_refused_before_the_ui_starts() {
+        let board = Board::with_size(Player::X, 5, 5, 4).unwrap();
+        let slow = GameMode::AgainstComputer(Player::O, Box::new(BrainLevelTwo));
+        assert!(check_brains(&board, &slow)
+            .unwrap_err()
+            .to_string()
+            .contains("too slow to play on a 5x5 board"));
+        assert!(check_brains(&Board::new(Player::X), &slow).is_ok());
+
+        let watch = GameMode::ComputerVsComputer(
+            Box::new(BrainLevelThree::default()),
+            Box::new(BrainLevelTwo),
+            Duration::ZERO,
+        );
+        assert!(check_brains(&board, &watch).is_err());
+        assert!(check_brains(&board, &GameMode::AgainstHuman).is_ok());
+    }
+
+    #[test]
+    fn test_cursor_stays_on_the_board() {
+        assert_eq!(move_cursor((0, 0), Direction::Up, 3, 3), (0, 0));
+        assert_eq!(move_cursor((0, 0), Direction::Left, 3, 3), (0, 0));
+        assert_eq!(move_cursor((0, 0), Direction::Down, 3, 3), (1, 0));
+        assert_eq!(move_cursor((2, 4), Direction::Right, 5, 3), (2, 4));
+        assert_eq!(move_cursor((2, 3), Direction::Right, 5, 3), (2, 4));
+
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        assert_eq!(
+            action_for_key(key(KeyCode::Char('k'))),
+            Some(Action::Move(Direction::Up))
+        );
+        assert_eq!(action_for_key(key(KeyCode::Enter)), Some(Action::Play));
+        assert_eq!(action_for_key(key(KeyCode::Char('x'))), None);
+    }
+}
@@ -0,0 +1,496 @@
+// ultimate.rs
+
+//! Ultimate Tic Tac Toe, played on a 3x3 meta-board of 3x3 `Board`s.
+//!
+//! Players take turns putting a mark on one of the nine small boards. The cell a player picks
+//! decides the board the other player must play on next: playing in the top right cell of any
+//! board sends the opponent to the top right board. Winning a small board claims that cell of
+//! the meta-board, and three claimed cells in a row win the game. A player sent to a board that
+//! has already been won or tied may play on any open board instead.
+//!
+//! Boards and cells are both numbered 1 to 9, row by row, like the positions of a `Board`.
+//!
+//! ```
+//! use tic_tac_toe::ultimate::{UltimateBoard, UltimateMove};
+//! use tic_tac_toe::{BoardState, Player};
+//!
+//! let mut game = UltimateBoard::new(Player::X);
+//! // X plays the top right cell of the middle board, so O must play on the top right board.
+//! game.make_move(UltimateMove::create(5, 3, Player::X)?)?;
+//! assert_eq!(game.required_board(), Some(3));
+//!
+//! assert_eq!(game.make_move(UltimateMove::create(3, 5, Player::O)?)?, BoardState::Ongoing);
+//! println!("{}", game.display());
+//! # Ok::<(), tic_tac_toe::GameError>(())
+//! ```
+use crate::error::GameError;
+use crate::tic_tac_toe::{Board, BoardState, Move, Player, BOARD_SIZE};
+use colored::{ColoredString, Colorize};
+use std::fmt;
+
+/// Number of small boards, and of cells in each of them.
+const BOARDS: usize = BOARD_SIZE * BOARD_SIZE;
+
+/// A move in Ultimate Tic Tac Toe: a cell on one of the small boards.
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub struct UltimateMove {
+    /// The small board the move is made on (1-based, row by row).
+    board: usize,
+    /// The cell on that board and the player making the move.
+    cell: Move,
+}
+
+impl UltimateMove {
+    /// Creates a new move on cell `position` of small board `board`.
+    /// Returns `GameError::PositionOutOfRange` if either number is 0. The upper bounds are
+    /// checked by `UltimateBoard::make_move`.
+    pub fn create(board: usize, position: usize, player: Player) -> Result<Self, GameError> {
+        if board == 0 {
+            return Err(GameError::PositionOutOfRange(board));
+        }
+        Ok(UltimateMove {
+            board,
+            cell: Move::create(position, player)?,
+        })
+    }
+
+    /// Returns the small board the move is made on (1-based, row by row).
+    pub fn board(&self) -> usize {
+        self.board
+    }
+
+    /// Returns the cell on the small board (1-based, row by row).
+    pub fn position(&self) -> usize {
+        self.cell.position()
+    }
+
+    /// Returns the player making the move.
+    pub fn player(&self) -> Player {
+        self.cell.player()
+    }
+}
+
+/// An Ultimate Tic Tac Toe game: nine small boards and the meta-board they are played for.
+#[derive(Debug, Clone)]
+pub struct UltimateBoard {
+    /// The small boards, row by row.
+    boards: Vec<Board>,
+    /// The meta-board. Each cell holds the mark of the player who won that small board.
+    meta: Board,
+    player_1: Player,
+    /// Every move made so far, in the order it was played.
+    history: Vec<UltimateMove>,
+    /// Moves taken back with `undo`, most recently undone last.
+    undone: Vec<UltimateMove>,
+}
+
+impl UltimateBoard {
+    /// Creates a new game with empty boards, where `player_1` moves first.
+    pub fn new(player_1: Player) -> Self {
+        UltimateBoard {
+            boards: vec![Board::new(player_1); BOARDS],
+            meta: Board::new(player_1),
+            player_1,
+            history: Vec::new(),
+            undone: Vec::new(),
+        }
+    }
+
+    /// Returns the player who moved first.
+    pub fn first_player(&self) -> Player {
+        self.player_1
+    }
+
+    /// Returns the player whose turn it is.
+    pub fn get_next_player(&self) -> Player {
+        if self.history.len().is_multiple_of(2) {
+            self.player_1
+        } else {
+            self.player_1.opponent()
+        }
+    }
+
+    /// Returns small board `board` (1-based, row by row), or `None` if there is no such board.
+    pub fn board(&self, board: usize) -> Option<&Board> {
+        board
+            .checked_sub(1)
+            .and_then(|index| self.boards.get(index))
+    }
+
+    /// Returns the meta-board, where each cell holds the mark of the player who won that
+    /// small board.
+    pub fn meta_board(&self) -> &Board {
+        &self.meta
+    }
+
+    /// Returns the board the next player has been sent to.
+    ///
+    /// # Returns
+    ///
+    /// - `Some(board)` if the next move must be made on `board`.
+    /// - `None` if the next player may choose any open board, because this is the first move
+    ///   or they were sent to a board that is already finished.
+    pub fn required_board(&self) -> Option<usize> {
+        let target = self.history.last()?.position();
+        (self.boards[target - 1].get_state() == BoardState::Ongoing).then_some(target)
+    }
+
+    /// Returns the boards the next move can be made on, in order. The list is empty once the
+    /// game has ended.
+    pub fn playable_boards(&self) -> Vec<usize> {
+        if self.get_state() != BoardState::Ongoing {
+            return Vec::new();
+        }
+        match self.required_board() {
+            Some(board) => vec![board],
+            None => (1..=BOARDS)
+                .filter(|&board| self.boards[board - 1].get_state() == BoardState::Ongoing)
+                .collect(),
+        }
+    }
+
+    /// Makes a move.
+    ///
+    /// Winning a small board claims its cell on the meta-board.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(BoardState::Ended(Some(player)))` if the move won the meta-board.
+    /// - `Ok(BoardState::Ended(None))` if every small board is finished and nobody has won.
+    /// - `Ok(BoardState::Ongoing)` if the game continues after the move.
+    /// - `Err(GameError::GameOver)` if the game has already ended.
+    /// - `Err(GameError::WrongPlayer)` if it is the other player's turn.
+    /// - `Err(GameError::PositionOutOfRange)` if the board or the cell does not exist.
+    /// - `Err(GameError::WrongBoard)` if the player was sent to another board.
+    /// - `Err(GameError::BoardFinished)` if the board has already been won or tied.
+    /// - `Err(GameError::SlotOccupied)` if the cell already holds a mark.
+    pub fn make_move(&mut self, player_move: UltimateMove) -> Result<BoardState, GameError> {
+        if self.get_state() != BoardState::Ongoing {
+            return Err(GameError::GameOver);
+        }
+        if player_move.player() != self.get_next_player() {
+            return Err(GameError::WrongPlayer {
+                expected: self.get_next_player(),
+                found: player_move.player(),
+            });
+        }
+        if player_move.board > BOARDS {
+            return Err(GameError::PositionOutOfRange(player_move.board));
+        }
+        if let Some(expected) = self.required_board() {
+            if expected != player_move.board {
+                return Err(GameError::WrongBoard {
+                    expected,
+                    found: player_move.board,
+                });
+            }
+        }
+
+        let board = &mut self.boards[player_move.board - 1];
+        if board.get_state() != BoardState::Ongoing {
+            return Err(GameError::BoardFinished(player_move.board));
+        }
+        if let BoardState::Ended(Some(winner)) = board.make_move_out_of_turn(player_move.cell)? {
+            let claim = Move::create(player_move.board, winner)?;
+            self.meta.make_move_out_of_turn(claim)?;
+        }
+
+        self.undone.clear();
+        self.history.push(player_move);
+        Ok(self.get_state())
+    }
+
+    /// Returns the current state of the game.
+    ///
+    /// # Returns
+    ///
+    /// - `BoardState::Ended(Some(player))` if `player` has claimed three boards in a row.
+    /// - `BoardState::Ended(None)` if every small board is finished and nobody has won.
+    /// - `BoardState::Ongoing` if the game is still being played.
+    pub fn get_state(&self) -> BoardState {
+        match self.meta.get_state() {
+            BoardState::Ended(Some(winner)) => BoardState::Ended(Some(winner)),
+            _ if self
+                .boards
+                .iter()
+                .all(|board| board.get_state() != BoardState::Ongoing) =>
+            {
+                BoardState::Ended(None)
+            }
+            _ => BoardState::Ongoing,
+        }
+    }
+
+    /// Returns every move made so far, in the order it was played.
+    pub fn history(&self) -> &[UltimateMove] {
+        &self.history
+    }
+
+    /// Takes back the last move.
+    ///
+    /// # Returns
+    ///
+    /// - `Some(UltimateMove)` with the move that was taken back.
+    /// - `None` if no moves have been made.
+    pub fn undo(&mut self) -> Option<UltimateMove> {
+        let player_move = self.history.pop()?;
+        let board = &mut self.boards[player_move.board - 1];
+        let won_board = matches!(board.get_state(), BoardState::Ended(Some(_)));
+        board.undo();
+
+        // A board is only claimed by the move that wins it, so it was the last claim made.
+        if won_board {
+            self.meta.undo();
+        }
+        self.undone.push(player_move);
+        Some(player_move)
+    }
+
+    /// Plays again the last move taken back with `undo`.
+    ///
+    /// # Returns
+    ///
+    /// - `Some(BoardState)` with the state of the game after the move is replayed.
+    /// - `None` if there is no move to redo.
+    pub fn redo(&mut self) -> Option<BoardState> {
+        let player_move = self.undone.pop()?;
+        let undone = std::mem::take(&mut self.undone);
+        let state = self
+            .make_move(player_move)
+            .expect("Undone moves can always be replayed");
+        self.undone = undone;
+        Some(state)
+    }
+
+    /// Returns a value that draws the game in the terminal with `{}`.
+    ///
+    /// Boards won by a player are coloured in that player's colour. On the boards the next
+    /// move can be made on, empty cells show their number.
+    pub fn display(&self) -> UltimateBoardDisplay<'_> {
+        UltimateBoardDisplay { game: self }
+    }
+}
+
+/// Draws an Ultimate Tic Tac Toe game in the terminal. Created by `UltimateBoard::display`.
+pub struct UltimateBoardDisplay<'a> {
+    game: &'a UltimateBoard,
+}
+
+impl fmt::Display for UltimateBoardDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let game = self.game;
+        let playable = game.playable_boards();
+        let separator = vec!["-".repeat(BOARD_SIZE * 2 + 1); BOARD_SIZE].join("+");
+
+        for board_row in 0..BOARD_SIZE {
+            if board_row > 0 {
+                writeln!(f, "{}", separator)?;
+            }
+            for cell_row in 0..BOARD_SIZE {
+                let mut boards = Vec::new();
+                for board_col in 0..BOARD_SIZE {
+                    let number = board_row * BOARD_SIZE + board_col + 1;
+                    let board = &game.boards[number - 1];
+                    let mut cells = String::from(" ");
+                    for cell_col in 0..BOARD_SIZE {
+                        let position = cell_row * BOARD_SIZE + cell_col + 1;
+                        let text = match board.get_player_at(position) {
+                            Some(Player::X) => "X".red(),
+                            Some(Player::O) => "O".blue(),
+                            None if playable.contains(&number) => position.to_string().white(),
+                            None => ".".white(),
+                        };
+                        cells.push_str(&format!("{} ", claimed(text, board)));
+                    }
+                    boards.push(cells);
+                }
+                writeln!(f, "{}", boards.join("|"))?;
+            }
+        }
+
+        for player in [Player::X, Player::O] {
+            let won: Vec<String> = (1..=BOARDS)
+                .filter(|&number| game.meta.get_player_at(number) == Some(player))
+                .map(|number| number.to_string())
+                .collect();
+            if !won.is_empty() {
+                writeln!(
+                    f,
+                    "Boards won by {}: {}",
+                    Player::get_player_char_from_enum(&player),
+                    won.join(", ")
+                )?;
+            }
+        }
+
+        match (game.get_state(), game.required_board()) {
+            (BoardState::Ongoing, Some(board)) => write!(f, "Play on board {}", board),
+            (BoardState::Ongoing, None) => write!(f, "Play on any open board"),
+            _ => write!(f, "Game over"),
+        }
+    }
+}
+
+/// Colours the text of a cell in the colour of the player who won its board, if any.
+fn claimed(text: ColoredString, board: &Board) -> ColoredString {
+    match board.get_state() {
+        BoardState::Ended(Some(Player::X)) => text.on_red(),
+        BoardState::Ended(Some(Player::O)) => text.on_blue(),
+        _ => text,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Plays `(board, position)` moves in order, alternating players from X.
+    fn play(game: &mut UltimateBoard, moves: &[(usize, usize)]) -> BoardState {
+        let mut state = BoardState::Ongoing;
+        for &(board, position) in moves {
+            let player_move =
+                UltimateMove::create(board, position, game.get_next_player()).unwrap();
+            state = game.make_move(player_move).unwrap();
+        }
+        state
+    }
+
+    #[test]
+    fn test_cell_sends_opponent_to_board() {
+        let mut game = UltimateBoard::new(Player::X);
+        assert_eq!(game.required_board(), None);
+        assert_eq!(game.playable_boards().len(), 9);
+
+        play(&mut game, &[(5, 3)]);
+        assert_eq!(game.required_board(), Some(3));
+        assert_eq!(game.playable_boards(), vec![3]);
+        assert_eq!(
+            game.make_move(UltimateMove::create(4, 1, Player::O).unwrap()),
+            Err(GameError::WrongBoard {
+                expected: 3,
+                found: 4
+            })
+        );
+        assert_eq!(
+            game.make_move(UltimateMove::create(3, 1, Player::X).unwrap()),
+            Err(GameError::WrongPlayer {
+                expected: Player::O,
+                found: Player::X
+            })
+        );
+
+        // The same player can move twice in a row on one small board.
+        play(&mut game, &[(3, 3), (3, 1)]);
+        assert_eq!(game.board(3).unwrap().get_player_at(1), Some(Player::X));
+        assert_eq!(game.board(3).unwrap().get_player_at(3), Some(Player::O));
+        assert_eq!(game.required_board(), Some(1));
+    }
+
+    #[test]
+    fn test_winning_a_board_claims_it() {
+        let mut game = UltimateBoard::new(Player::X);
+        // X takes the top row of board 1, and O's replies send X back to it.
+        play(
+            &mut game,
+            &[
+                (1, 1),
+                (1, 5),
+                (5, 2),
+                (2, 1),
+                (1, 2),
+                (2, 9),
+                (9, 3),
+                (3, 1),
+            ],
+        );
+        assert_eq!(game.meta_board().get_player_at(1), None);
+        assert_eq!(play(&mut game, &[(1, 3)]), BoardState::Ongoing);
+
+        assert_eq!(
+            game.board(1).unwrap().get_state(),
+            BoardState::Ended(Some(Player::X))
+        );
+        assert_eq!(game.meta_board().get_player_at(1), Some(Player::X));
+
+        // O sends X to board 5, and X sends O to board 1, which is finished.
+        play(&mut game, &[(3, 5), (5, 1)]);
+        assert_eq!(game.required_board(), None);
+        assert!(!game.playable_boards().contains(&1));
+        assert_eq!(
+            game.make_move(UltimateMove::create(1, 4, Player::O).unwrap()),
+            Err(GameError::BoardFinished(1))
+        );
+        play(&mut game, &[(6, 6)]);
+
+        // Undoing the winning move gives the board back.
+        for _ in 0..4 {
+            game.undo();
+        }
+        assert_eq!(game.meta_board().get_player_at(1), None);
+        assert_eq!(game.board(1).unwrap().get_state(), BoardState::Ongoing);
+        assert_eq!(game.required_board(), Some(1));
+        assert_eq!(game.redo(), Some(BoardState::Ongoing));
+        assert_eq!(game.meta_board().get_player_at(1), Some(Player::X));
+        assert_eq!(game.history().len(), 9);
+    }
+
+    #[test]
+    fn test_three_boards_in_a_row_win_the_game() {
+        let mut game = UltimateBoard::new(Player::X);
+        let state = play(
+            &mut game,
+            &[
+                (7, 3),
+                (3, 8),
+                (8, 4),
+                (4, 7),
+                (7, 9),
+                (9, 7),
+                (7, 6),
+                (6, 9),
+                (9, 2),
+                (2, 7),
+                (8, 7),
+                (8, 9),
+                (9, 5),
+                (5, 8),
+                (8, 1),
+                (1, 9),
+            ],
+        );
+        assert_eq!(state, BoardState::Ongoing);
+        assert_eq!(game.required_board(), Some(9));
+
+        // X has won boards 7 and 8, and winning board 9 completes the bottom row.
+        let state = play(&mut game, &[(9, 8)]);
+        assert_eq!(state, BoardState::Ended(Some(Player::X)));
+        assert!(game.playable_boards().is_empty());
+        assert_eq!(
+            game.make_move(UltimateMove::create(8, 2, Player::O).unwrap()),
+            Err(GameError::GameOver)
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        colored::control::set_override(false);
+        let mut game = UltimateBoard::new(Player::X);
+        play(&mut game, &[(5, 3), (3, 5)]);
+
+        assert_eq!(
+            game.display().to_string(),
+            " . . . | . . . | . . . \n\
+             \x20. . . | . . . | . O . \n\
+             \x20. . . | . . . | . . . \n\
+             -------+-------+-------\n\
+             \x20. . . | 1 2 X | . . . \n\
+             \x20. . . | 4 5 6 | . . . \n\
+             \x20. . . | 7 8 9 | . . . \n\
+             -------+-------+-------\n\
+             \x20. . . | . . . | . . . \n\
+             \x20. . . | . . . | . . . \n\
+             \x20. . . | . . . | . . . \n\
+             Play on board 5"
+        );
+    }
+}
@@ -0,0 +1,823 @@
+// web.rs
+
+//! An HTTP server with a JSON API for the game engine, for browsers and other services.
+//!
+//! Games are kept on the server and every move goes through `Board::make_move`, so clients
+//! never need their own copy of the rules. The endpoints are:
+//!
+//! - `GET /brains`: The names of the computer players that can be asked for a move.
+//! - `GET /games`: Every game.
+//! - `POST /games`: Creates a game. The body is optional, e.g.
+//!   `{"width": 4, "height": 4, "win_length": 3, "first_player": "O"}`. Boards larger than
+//!   `MAX_BOARD_SIDE` on a side are refused.
+//! - `GET /games/{id}`: One game.
+//! - `POST /games/{id}/moves`: Plays a move, e.g. `{"slot": 5}`. A `"player"` can be given
+//!   to make sure the move is not played for the wrong side.
+//! - `POST /games/{id}/ai-move`: Lets a computer player move, e.g. `{"brain": "level2"}`.
+//! - `GET /games/{id}/ws`: A WebSocket that sends the game as a text message when it opens
+//!   and again after every move.
+//!
+//! Games are sent as a `GameView`, for example:
+//!
+//! ```json
+//! {"id": 1, "width": 3, "height": 3, "win_length": 3, "cells": ["X", null, null, ...],
+//!  "next_player": "O", "state": "ongoing", "winner": null, "moves": [1],
+//!  "position": "X2/3/3 O 3"}
+//! ```
+//!
+//! Errors are sent as `{"error": "..."}` with status 400 for a request that cannot be read,
+//! 404 for an unknown game, brain or endpoint, and 409 for a move the board does not allow
+//! or a brain too slow to play on the board.
+use crate::brains::TicTacToeBrain;
+use crate::error::GameError;
+use crate::network::parse_player;
+use crate::tic_tac_toe::{Board, BoardState, Move, Player, BOARD_SIZE};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+
+/// Request bodies larger than this are refused.
+const MAX_BODY_LENGTH: usize = 64 * 1024;
+
+/// Added to a client's WebSocket key to prove the server speaks WebSocket (RFC 6455).
+const WEBSOCKET_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+type SharedBrain = Arc<dyn TicTacToeBrain + Send + Sync>;
+
+/// A game as sent to clients.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct GameView {
+    pub id: u32,
+    pub width: usize,
+    pub height: usize,
+    pub win_length: usize,
+    /// The mark in each slot, `"X"`, `"O"` or `null`, row by row. Slot 1 comes first.
+    pub cells: Vec<Option<String>>,
+    pub next_player: String,
+    /// `"ongoing"`, `"won"` or `"draw"`.
+    pub state: String,
+    pub winner: Option<String>,
+    /// The slots played, in order.
+    pub moves: Vec<usize>,
+    /// The position string of the board (see `Board::to_position_str`).
+    pub position: String,
+}
+
+impl GameView {
+    fn new(id: u32, board: &Board) -> Self {
+        let player_name = |player: Player| Player::get_player_char_from_enum(&player).to_string();
+        let (state, winner) = match board.get_state() {
+            BoardState::Ongoing => ("ongoing", None),
+            BoardState::Ended(Some(winner)) => ("won", Some(player_name(winner))),
+            BoardState::Ended(None) => ("draw", None),
+        };
+
+        GameView {
+            id,
+            width: board.width(),
+            height: board.height(),
+            win_length: board.win_length(),
+            cells: (1..=board.get_number_of_slots())
+                .map(|position| board.get_player_at(position).map(player_name))
+                .collect(),
+            next_player: player_name(board.get_next_player()),
+            state: state.to_string(),
+            winner,
+            moves: board
+                .history()
+                .iter()
+                .map(|player_move| player_move.position())
+                .collect(),
+            position: board.to_position_str(),
+        }
+    }
+}
+
+/// The body of `POST /games`.
+#[derive(Deserialize, Default)]
+struct NewGame {
+    width: Option<usize>,
+    height: Option<usize>,
+    win_length: Option<usize>,
+    first_player: Option<String>,
+}
+
+/// The body of `POST /games/{id}/moves`.
+#[derive(Deserialize)]
+struct NewMove {
+    slot: usize,
+    player: Option<String>,
+}
+
+/// The body of `POST /games/{id}/ai-move`.
+#[derive(Deserialize)]
+struct AiMove {
+    brain: String,
+}
+
+/// An HTTP response: a status code and a JSON body.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Response {
+    pub status: u16,
+    pub body: String,
+}
+
+impl Response {
+    fn json<T: Serialize>(status: u16, value: &T) -> Self {
+        Response {
+            status,
+            body: serde_json::to_string(value).expect("Views always serialize"),
+        }
+    }
+
+    fn error(status: u16, message: &str) -> Self {
+        Response::json(status, &serde_json::json!({ "error": message }))
+    }
+
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            201 => "Created",
+            204 => "No Content",
+            400 => "Bad Request",
+            404 => "Not Found",
+            409 => "Conflict",
+            413 => "Payload Too Large",
+            _ => "Internal Server Error",
+        }
+    }
+}
+
+/// Returns the status code for an error from the engine.
+fn error_status(err: &GameError) -> u16 {
+    match err {
+        GameError::PositionOutOfRange(_)
+        | GameError::SlotOccupied(_)
+        | GameError::WrongPlayer { .. }
+        | GameError::GameOver
+        | GameError::BrainFailure(_) => 409,
+        _ => 400,
+    }
+}
+
+struct WebGame {
+    board: Board,
+    /// The WebSockets watching the game.
+    subscribers: Vec<Sender<String>>,
+}
+
+#[derive(Default)]
+struct Games {
+    games: BTreeMap<u32, WebGame>,
+    next_id: u32,
+}
+
+/// The HTTP server. Register the computer players with `register`, then `serve`.
+///
+/// ```
+/// use tic_tac_toe::web::WebServer;
+/// use tic_tac_toe::BrainLevelTwo;
+///
+/// let mut server = WebServer::new();
+/// server.register("perfect", Box::new(BrainLevelTwo));
+///
+/// let created = server.handle_request("POST", "/games", "");
+/// assert_eq!(created.status, 201);
+/// let moved = server.handle_request("POST", "/games/1/ai-move", r#"{"brain": "perfect"}"#);
+/// assert_eq!(moved.status, 200);
+/// ```
+#[derive(Default)]
+pub struct WebServer {
+    brains: BTreeMap<String, SharedBrain>,
+    games: Mutex<Games>,
+}
+
+impl WebServer {
+    /// Creates a server with no games and no computer players.
+    pub fn new() -> Self {
+        WebServer::default()
+    }
+
+    /// Adds a computer player that `POST /games/{id}/ai-move` can ask for by `name`.
+    pub fn register(&mut self, name: &str, brain: Box<dyn TicTacToeBrain + Send + Sync>) {
+        self.brains
+            .insert(name.to_string(), SharedBrain::from(brain));
+    }
+
+    /// Serves clients connecting to `listener`, each on its own thread. Only returns if the
+    /// listener fails.
+    pub fn serve(self, listener: TcpListener) -> Result<(), GameError> {
+        let server = Arc::new(self);
+        for stream in listener.incoming() {
+            let stream = stream.map_err(|err| GameError::Network(err.to_string()))?;
+            let server = server.clone();
+            thread::spawn(move || server.handle_connection(stream));
+        }
+        Ok(())
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Games> {
+        self.games.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Answers one request to the JSON API. This is what `serve` does for every request
+    /// except WebSockets, so it can also be used without a network.
+    ///
+    /// # Parameters
+    ///
+    /// - `method`: The HTTP method, e.g. `GET`.
+    /// - `path`: The path, e.g. `/games/1`. A query string is ignored.
+    /// - `body`: The request body, which can be empty.
+    pub fn handle_request(&self, method: &str, path: &str, body: &str) -> Response {
+        let path = path.split('?').next().unwrap_or_default();
+        let segments: Vec<&str> = path.split('/').filter(|part| !part.is_empty()).collect();
+
+        match (method, segments.as_slice()) {
+            ("GET", ["brains"]) => Response::json(200, &self.brains.keys().collect::<Vec<_>>()),
+            ("GET", ["games"]) => {
+                let games = self.lock();
+                let views: Vec<GameView> = games
+                    .games
+                    .iter()
+                    .map(|(&id, game)| GameView::new(id, &game.board))
+                    .collect();
+                Response::json(200, &views)
+            }
+            ("POST", ["games"]) => self.create_game(body),
+            ("GET", ["games", id]) => match id.parse().ok().and_then(|id| self.view(id)) {
+                Some(view) => Response::json(200, &view),
+                None => Response::error(404, &format!("There is no game {}", id)),
+            },
+            ("POST", ["games", id, "moves"]) => self.with_game_id(id, |id| self.play(id, body)),
+            ("POST", ["games", id, "ai-move"]) => {
+                self.with_game_id(id, |id| self.play_ai(id, body))
+            }
+            _ => Response::error(404, &format!("No endpoint {} {}", method, path)),
+        }
+    }
+
+    fn with_game_id(&self, id: &str, answer: impl FnOnce(u32) -> Response) -> Response {
+        match id.parse() {
+            Ok(id) => answer(id),
+            Err(_) => Response::error(404, &format!("There is no game {}", id)),
+        }
+    }
+
+    fn view(&self, id: u32) -> Option<GameView> {
+        let games = self.lock();
+        games
+            .games
+            .get(&id)
+            .map(|game| GameView::new(id, &game.board))
+    }
+
+    fn create_game(&self, body: &str) -> Response {
+        let new_game: NewGame = if body.trim().is_empty() {
+            NewGame::default()
+        } else {
+            match serde_json::from_str(body) {
+                Ok(new_game) => new_game,
+                Err(err) => return Response::error(400, &err.to_string()),
+            }
+        };
+
+        let first_player = match new_game.first_player.as_deref().map(parse_player) {
+            None => Player::X,
+            Some(Some(player)) => player,
+            Some(None) => return Response::error(400, "first_player should be X or O"),
+        };
+        let width = new_game.width.unwrap_or(BOARD_SIZE);
+        let height = new_game.height.unwrap_or(width);
+        let win_length = new_game.win_length.unwrap_or(width.min(height));
+        let board = match Board::with_size(first_player, width, height, win_length) {
+            Ok(board) => board,
+            Err(err) => return Response::error(400, &err.to_string()),
+        };
+
+        let mut games = self.lock();
+        games.next_id += 1;
+        let id = games.next_id;
+        let view = GameView::new(id, &board);
+        games.games.insert(
+            id,
+            WebGame {
+                board,
+                subscribers: Vec::new(),
+            },
+        );
+        Response::json(201, &view)
+    }
+
+    fn play(&self, id: u32, body: &str) -> Response {
+        let new_move: NewMove = match serde_json::from_str(body) {
+            Ok(new_move) => new_move,
+            Err(err) => return Response::error(400, &err.to_string()),
+        };
+
+        let mut games = self.lock();
+        let Some(game) = games.games.get_mut(&id) else {
+            return Response::error(404, &format!("There is no game {}", id));
+        };
+        let player = match new_move.player.as_deref().map(parse_player) {
+            None => game.board.get_next_player(),
+            Some(Some(player)) => player,
+            Some(None) => return Response::error(400, "player should be X or O"),
+        };
+
+        match Move::create(new_move.slot, player)
+            .and_then(|player_move| game.board.make_move(player_move))
+        {
+            Ok(_) => Response::json(200, &game.publish(id)),
+            Err(err) => Response::error(error_status(&err), &err.to_string()),
+        }
+    }
+
+    /// Lets a computer player move. The games are not locked while it thinks.
+    fn play_ai(&self, id: u32, body: &str) -> Response {
+        let ai_move: AiMove = match serde_json::from_str(body) {
+            Ok(ai_move) => ai_move,
+            Err(err) => return Response::error(400, &err.to_string()),
+        };
+        let Some(brain) = self.brains.get(&ai_move.brain) else {
+            return Response::error(404, &format!("There is no brain {}", ai_move.brain));
+        };
+        let Some(board) = self.lock().games.get(&id).map(|game| game.board.clone()) else {
+            return Response::error(404, &format!("There is no game {}", id));
+        };
+        if !brain.can_play(&board) {
+            return Response::error(
+                409,
+                &format!(
+                    "The brain {} is too slow to play on a {} board",
+                    ai_move.brain,
+                    board.size_text()
+                ),
+            );
+        }
+
+        let result = brain.make_move(&board);
+
+        let mut games = self.lock();
+        let Some(game) = games.games.get_mut(&id) else {
+            return Response::error(404, &format!("There is no game {}", id));
+        };
+        if game.board.history() != board.history() {
+            return Response::error(409, "The game changed while the computer was thinking");
+        }
+        match result.and_then(|player_move| game.board.make_move(player_move)) {
+            Ok(_) => Response::json(200, &game.publish(id)),
+            Err(err) => Response::error(error_status(&err), &err.to_string()),
+        }
+    }
+
+    fn handle_connection(&self, stream: TcpStream) {
+        let Ok(writer) = stream.try_clone() else {
+            return;
+        };
+        let mut writer = writer;
+        let mut reader = BufReader::new(stream);
+
+        let response = match read_request(&mut reader) {
+            Ok(request) => match websocket_game(&request) {
+                Some((id, key)) => return self.serve_websocket(writer, id, key),
+                None => {
+                    if request.method == "OPTIONS" {
+                        Response {
+                            status: 204,
+                            body: String::new(),
+                        }
+                    } else {
+                        self.handle_request(&request.method, &request.path, &request.body)
+                    }
+                }
+            },
+            Err(response) => response,
+        };
+
+        let _ = write!(
+            writer,
+            "HTTP/1.1 {} {}\r\n\
+             Content-Type: application/json\r\n\
+             Content-Length: {}\r\n\
+             Access-Control-Allow-Origin: *\r\n\
+             Access-Control-Allow-Methods: GET, POST, OPTIONS\r\n\
+             Access-Control-Allow-Headers: Content-Type\r\n\
+             Connection: close\r\n\r\n{}",
+            response.status,
+            response.reason(),
+            response.body.len(),
+            response.body
+        );
+    }
+
+    /// Completes the WebSocket handshake, then sends the game every time it changes.
+    fn serve_websocket(&self, mut stream: TcpStream, id: u32, key: String) {
+        let (sender, receiver) = mpsc::channel();
+        {
+            let mut games = self.lock();
+            let Some(game) = games.games.get_mut(&id) else {
+                let body = serde_json::json!({ "error": format!("There is no game {}", id) });
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 404 Not Found\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.to_string().len(),
+                    body
+                );
+                return;
+            };
+            let view = serde_json::to_string(&GameView::new(id, &game.board))
+                .expect("Views always serialize");
+            let _ = sender.send(view);
+            game.subscribers.push(sender);
+        }
+
+        let accept = websocket_accept(&key);
+        if write!(
+            stream,
+            "HTTP/1.1 101 Switching Protocols\r\n\
+             Upgrade: websocket\r\n\
+             Connection: Upgrade\r\n\
+             Sec-WebSocket-Accept: {}\r\n\r\n",
+            accept
+        )
+        .is_err()
+        {
+            return;
+        }
+
+        // The client never needs to send anything, so its frames are not read. A closed
+        // socket shows up as a failed write, which ends the subscription.
+        for view in receiver {
+            if stream.write_all(&websocket_frame(&view)).is_err() {
+                break;
+            }
+        }
+    }
+}
+
+impl WebGame {
+    /// Sends the game to its WebSockets, and returns it.
+    fn publish(&mut self, id: u32) -> GameView {
+        let view = GameView::new(id, &self.board);
+        let text = serde_json::to_string(&view).expect("Views always serialize");
+        self.subscribers
+            .retain(|subscriber| subscriber.send(text.clone()).is_ok());
+        view
+    }
+}
+
+struct Request {
+    method: String,
+    path: String,
+    headers: Vec<(String, String)>,
+    body: String,
+}
+
+impl Request {
+    /// Returns the value of a header. Header names are not case sensitive.
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Reads an HTTP request, or returns the error response to send instead.
+fn read_request(reader: &mut impl BufRead) -> Result<Request, Response> {
+    let bad_request = |message: &str| Response::error(400, message);
+
+    let mut request_line = String::new();
+    reader
+        .read_line(&mut request_line)
+        .map_err(|err| bad_request(&err.to_string()))?;
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(path)) = (parts.next(), parts.next()) else {
+        return Err(bad_request("Invalid request line"));
+    };
+
+    let mut headers = Vec::new();
+    loop {
+        let mut line = String::new();
+        reader
+            .read_line(&mut line)
+            .map_err(|err| bad_request(&err.to_string()))?;
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        let (name, value) = line
+            .split_once(':')
+            .ok_or_else(|| bad_request("Invalid header"))?;
+        headers.push((name.trim().to_string(), value.trim().to_string()));
+    }
+
+    let mut request = Request {
+        method: method.to_ascii_uppercase(),
+        path: path.to_string(),
+        headers,
+        body: String::new(),
+    };
+
+    let length: usize = match request.header("Content-Length") {
+        Some(length) => length
+            .parse()
+            .map_err(|_| bad_request("Invalid Content-Length"))?,
+        None => 0,
+    };
+    if length > MAX_BODY_LENGTH {
+        return Err(Response::error(413, "The request body is too large"));
+    }
+
+    let mut body = vec![0; length];
+    reader
+        .read_exact(&mut body)
+        .map_err(|err| bad_request(&err.to_string()))?;
+    request.body = String::from_utf8(body).map_err(|_| bad_request("The body is not UTF-8"))?;
+    Ok(request)
+}
+
+/// Returns the game and the client's key if the request opens a game's WebSocket.
+fn websocket_game(request: &Request) -> Option<(u32, String)> {
+    let upgrade = request.header("Upgrade")?;
+    if request.method != "GET" || !upgrade.eq_ignore_ascii_case("websocket") {
+        return None;
+    }
+
+    let segments: Vec<&str> = request
+        .path
+        .split('/')
+        .filter(|part| !part.is_empty())
+        .collect();
+    match segments.as_slice() {
+        ["games", id, "ws"] => Some((
+            id.parse().ok()?,
+            request.header("Sec-WebSocket-Key")?.to_string(),
+        )),
+        _ => None,
+    }
+}
+
+/// Returns the `Sec-WebSocket-Accept` answer to a client's key.
+fn websocket_accept(key: &str) -> String {
+    let digest = sha1_smol::Sha1::from(format!("{}{}", key, WEBSOCKET_GUID)).digest();
+    base64(&digest.bytes())
+}
+
+/// Wraps text in a single unmasked WebSocket text frame, as servers send them.
+fn websocket_frame(text: &str) -> Vec<u8> {
+    let payload = text.as_bytes();
+    let mut frame = vec![0x81];
+
+    match payload.len() {
+        length if length < 126 => frame.push(length as u8),
+        length if length <= u16::MAX as usize => {
+            frame.push(126);
+            frame.extend_from_slice(&(length as u16).to_be_bytes());
+        }
+        length => {
+            frame.push(127);
+            frame.extend_from_slice(&(length as u64).to_be_bytes());
+        }
+    }
+
+    frame.extend_from_slice(payload);
+    frame
+}
+
+/// Encodes bytes as standard base64 with padding.
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::new();
+
+    for chunk in bytes.chunks(3) {
+        let group = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |group, (index, &byte)| {
+                group | (byte as u32) << (16 - 8 * index)
+            });
+        for index in 0..4 {
+            if index <= chunk.len() {
+                encoded.push(ALPHABET[(group >> (18 - 6 * index) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::brains::BrainLevelTwo;
+    use std::io::Read;
+
+    fn server() -> WebServer {
+        let mut server = WebServer::new();
+        server.register("perfect", Box::new(BrainLevelTwo));
+        server
+    }
+
+    fn view(response: &Response) -> GameView {
+        serde_json::from_str(&response.body).unwrap()
+    }
+
+    #[test]
+    fn test_websocket_accept() {
+        // The example from RFC 6455.
+        assert_eq!(
+            websocket_accept("dGhlIHNhbXBsZSBub25jZQ=="),
+            "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+        );
+        assert_eq!(base64(b"ab"), "YWI=");
+        assert_eq!(base64(b"a"), "YQ==");
+        assert_eq!(websocket_frame("hi"), vec![0x81, 2, b'h', b'i']);
+        assert_eq!(&websocket_frame(&"x".repeat(300))[..4], &[0x81, 126, 1, 44]);
+    }
+
+    #[test]
+    fn test_create_and_play() {
+        let server = server();
+
+        let created =
+            server.handle_request("POST", "/games", r#"{"width": 4, "first_player": "O"}"#);
+        assert_eq!(created.status, 201);
+        let game = view(&created);
+        assert_eq!((game.width, game.height, game.win_length), (4, 4, 4));
+        assert_eq!(game.next_player, "O");
+        assert_eq!(game.cells.len(), 16);
+
+        let moved = server.handle_request("POST", "/games/1/moves", r#"{"slot": 6}"#);
+        assert_eq!(moved.status, 200);
+        let game = view(&moved);
+        assert_eq!(game.cells[5], Some("O".to_string()));
+        assert_eq!(game.moves, vec![6]);
+        assert_eq!(game.next_player, "X");
+
+        let fetched = server.handle_request("GET", "/games/1", "");
+        assert_eq!(view(&fetched), game);
+        let listed = server.handle_request("GET", "/games", "");
+        assert_eq!(listed.status, 200);
+        assert!(listed.body.starts_with("[{\"id\":1,"));
+    }
+
+    #[test]
+    fn test_errors() {
+        let server = server();
+        server.handle_request("POST", "/games", "");
+
+        let cases = [
+            ("POST", "/games/1/moves", r#"{"slot": 10}"#, 409),
+            (
+                "POST",
+                "/games/1/moves",
+                r#"{"slot": 1, "player": "O"}"#,
+                409,
+            ),
+            ("POST", "/games/1/moves", r#"{"slot": "one"}"#, 400),
+            (
+                "POST",
+                "/games/1/moves",
+                r#"{"slot": 1, "player": "Z"}"#,
+                400,
+            ),
+            ("POST", "/games/2/moves", r#"{"slot": 1}"#, 404),
+            ("POST", "/games/1/ai-move", r#"{"brain": "nobody"}"#, 404),
+            ("POST", "/games", r#"{"width": 0}"#, 400),
+            (
+                "POST",
+                "/games",
+                r#"{"width": 100000, "height": 100000}"#,
+                400,
+            ),
+            ("POST", "/games", r#"{"width": 1025, "height": 1}"#, 400),
+            ("GET", "/games/abc", "", 404),
+            ("DELETE", "/games/1", "", 404),
+        ];
+        for (method, path, body, status) in cases {
+            let response = server.handle_request(method, path, body);
+            assert_eq!(response.status, status, "{} {} {}", method, path, body);
+            assert!(response.body.starts_with("{\"error\":"));
+        }
+
+        server.handle_request("POST", "/games/1/moves", r#"{"slot": 1}"#);
+        let taken = server.handle_request("POST", "/games/1/moves", r#"{"slot": 1}"#);
+        assert_eq!(taken.status, 409);
+        assert!(taken.body.contains("already taken"));
+    }
+
+    #[test]
+    fn test_ai_moves_until_the_end() {
+        let server = server();
+        server.handle_request("POST", "/games", "");
+        assert_eq!(
+            server.handle_request("GET", "/brains", "").body,
+            r#"["perfect"]"#
+        );
+
+        // Two perfect players always draw.
+        let mut game = view(&server.handle_request("GET", "/games/1", ""));
+        while game.state == "ongoing" {
+            let response =
+                server.handle_request("POST", "/games/1/ai-move", r#"{"brain": "perfect"}"#);
+            assert_eq!(response.status, 200);
+            game = view(&response);
+        }
+        assert_eq!(game.state, "draw");
+        assert_eq!(game.moves.len(), 9);
+
+        let over = server.handle_request("POST", "/games/1/ai-move", r#"{"brain": "perfect"}"#);
+        assert_eq!(over.status, 409);
+    }
+
+    #[test]
+    fn test_refuses_slow_brains_on_large_boards() {
+        let server = server();
+        server.handle_request("POST", "/games", r#"{"width": 5}"#);
+
+        let refused = server.handle_request("POST", "/games/1/ai-move", r#"{"brain": "perfect"}"#);
+        assert_eq!(refused.status, 409);
+        assert!(refused.body.contains("too slow to play on a 5x5 board"));
+        assert!(view(&server.handle_request("GET", "/games/1", ""))
+            .moves
+            .is_empty());
+    }
+
+    /// Reads one unmasked text frame sent by the server.
+    fn read_frame(reader: &mut impl Read) -> String {
+        let mut header = [0; 2];
+        reader.read_exact(&mut header).unwrap();
+        assert_eq!(header[0], 0x81);
+        let length = match header[1] {
+            126 => {
+                let mut length = [0; 2];
+                reader.read_exact(&mut length).unwrap();
+                u16::from_be_bytes(length) as usize
+            }
+            length => length as usize,
+        };
+        let mut payload = vec![0; length];
+        reader.read_exact(&mut payload).unwrap();
+        String::from_utf8(payload).unwrap()
+    }
+
+    fn http(address: &str, request: &str) -> String {
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream.write_all(request.as_bytes()).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn test_http_and_websocket() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        thread::spawn(move || server().serve(listener));
+
+        let created = http(
+            &address,
+            "POST /games HTTP/1.1\r\nContent-Length: 12\r\n\r\n{\"width\": 3}",
+        );
+        assert!(created.starts_with("HTTP/1.1 201 Created\r\n"));
+        assert!(created.ends_with("\"position\":\"3/3/3 X 3\"}"));
+
+        let mut socket = TcpStream::connect(&address).unwrap();
+        write!(
+            socket,
+            "GET /games/1/ws HTTP/1.1\r\nHost: localhost\r\nUpgrade: websocket\r\n\
+             Connection: Upgrade\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\
+             Sec-WebSocket-Version: 13\r\n\r\n"
+        )
+        .unwrap();
+        let mut reader = BufReader::new(socket);
+        let mut handshake = String::new();
+        while !handshake.ends_with("\r\n\r\n") {
+            reader.read_line(&mut handshake).unwrap();
+        }
+        assert!(handshake.starts_with("HTTP/1.1 101"));
+        assert!(handshake.contains("Sec-WebSocket-Accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo="));
+
+        let first: GameView = serde_json::from_str(&read_frame(&mut reader)).unwrap();
+        assert!(first.moves.is_empty());
+
+        let moved = http(
+            &address,
+            "POST /games/1/moves HTTP/1.1\r\nContent-Length: 11\r\n\r\n{\"slot\": 5}",
+        );
+        assert!(moved.starts_with("HTTP/1.1 200 OK\r\n"));
+        let pushed: GameView = serde_json::from_str(&read_frame(&mut reader)).unwrap();
+        assert_eq!(pushed.moves, vec![5]);
+        assert_eq!(pushed.next_player, "O");
+
+        let missing = http(&address, "GET /nowhere HTTP/1.1\r\n\r\n");
+        assert!(missing.starts_with("HTTP/1.1 404 Not Found\r\n"));
+    }
+}
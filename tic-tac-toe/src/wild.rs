@@ -0,0 +1,292 @@
+// wild.rs
+
+//! Wild Tic Tac Toe, where each player chooses which mark to place on their turn.
+//!
+//! Players still take turns, but on every turn the player can put down either an `X` or an
+//! `O`. Whoever completes a line wins, whichever mark the line is made of. The marks are
+//! placed on an ordinary `Board`, so every board size and win length works.
+//!
+//! ```
+//! use tic_tac_toe::wild::{WildBoard, WildMove};
+//! use tic_tac_toe::{BoardState, Player};
+//!
+//! let mut game = WildBoard::new(Player::X);
+//! // The first player puts an O in the centre.
+//! game.make_move(WildMove::create(5, Player::O, Player::X)?)?;
+//! assert_eq!(game.board().get_player_at(5), Some(Player::O));
+//!
+//! assert_eq!(game.make_move(WildMove::create(1, Player::X, Player::O)?)?, BoardState::Ongoing);
+//! println!("{}", game.display());
+//! # Ok::<(), tic_tac_toe::GameError>(())
+//! ```
+use crate::error::GameError;
+use crate::tic_tac_toe::{Board, BoardDisplay, BoardState, Move, Player};
+
+/// A move in Wild Tic Tac Toe: a mark placed in a cell, and the player placing it.
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub struct WildMove {
+    /// The cell and the mark placed in it.
+    cell: Move,
+    /// The player making the move, who may place either mark.
+    player: Player,
+}
+
+impl WildMove {
+    /// Creates a new move where `player` puts `mark` in cell `position`.
+    /// Returns `GameError::PositionOutOfRange` if the position is 0. The upper bound is
+    /// checked by `WildBoard::make_move`.
+    pub fn create(position: usize, mark: Player, player: Player) -> Result<Self, GameError> {
+        Ok(WildMove {
+            cell: Move::create(position, mark)?,
+            player,
+        })
+    }
+
+    /// Returns the position of the cell (1-based, row by row).
+    pub fn position(&self) -> usize {
+        self.cell.position()
+    }
+
+    /// Returns the mark placed in the cell.
+    pub fn mark(&self) -> Player {
+        self.cell.player()
+    }
+
+    /// Returns the player making the move.
+    pub fn player(&self) -> Player {
+        self.player
+    }
+}
+
+/// A Wild Tic Tac Toe game: a board and the players taking turns on it.
+#[derive(Debug, Clone)]
+pub struct WildBoard {
+    /// The marks placed so far. Its winner is the mark of the completed line, not the player
+    /// who completed it.
+    board: Board,
+    player_1: Player,
+    /// Every move made so far, in the order it was played.
+    history: Vec<WildMove>,
+    /// Moves taken back with `undo`, most recently undone last.
+    undone: Vec<WildMove>,
+}
+
+impl WildBoard {
+    /// Creates a new game on the classic 3x3 board, where `player_1` moves first.
+    pub fn new(player_1: Player) -> Self {
+        WildBoard {
+            board: Board::new(player_1),
+            player_1,
+            history: Vec::new(),
+            undone: Vec::new(),
+        }
+    }
+
+    /// Creates a new game on a board of any size.
+    ///
+    /// # Parameters
+    ///
+    /// - `player_1`: The player who moves first.
+    /// - `width`: The number of columns.
+    /// - `height`: The number of rows.
+    /// - `win_length`: How many marks in a row are needed to win.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(WildBoard)` if the size is valid.
+    /// - `Err(GameError::InvalidBoardSize)` if the board has no cells, or if `win_length`
+    ///   is 0 or does not fit on the board.
+    pub fn with_size(
+        player_1: Player,
+        width: usize,
+        height: usize,
+        win_length: usize,
+    ) -> Result<Self, GameError> {
+        Ok(WildBoard {
+            board: Board::with_size(player_1, width, height, win_length)?,
+            player_1,
+            history: Vec::new(),
+            undone: Vec::new(),
+        })
+    }
+
+    /// Returns the board with the marks placed so far.
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// Returns the player who moved first.
+    pub fn first_player(&self) -> Player {
+        self.player_1
+    }
+
+    /// Returns the player whose turn it is.
+    pub fn get_next_player(&self) -> Player {
+        if self.history.len().is_multiple_of(2) {
+            self.player_1
+        } else {
+            self.player_1.opponent()
+        }
+    }
+
+    /// Makes a move.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(BoardState::Ended(Some(player)))` if the move completed a line.
+    /// - `Ok(BoardState::Ended(None))` if the board is full and nobody has won.
+    /// - `Ok(BoardState::Ongoing)` if the game continues after the move.
+    /// - `Err(GameError::GameOver)` if the game has already ended.
+    /// - `Err(GameError::WrongPlayer)` if it is the other player's turn.
+    /// - `Err(GameError::PositionOutOfRange)` if the cell does not exist.
+    /// - `Err(GameError::SlotOccupied)` if the cell already holds a mark.
+    pub fn make_move(&mut self, player_move: WildMove) -> Result<BoardState, GameError> {
+        if self.get_state() != BoardState::Ongoing {
+            return Err(GameError::GameOver);
+        }
+        if player_move.player != self.get_next_player() {
+            return Err(GameError::WrongPlayer {
+                expected: self.get_next_player(),
+                found: player_move.player,
+            });
+        }
+
+        self.board.make_move_out_of_turn(player_move.cell)?;
+        self.undone.clear();
+        self.history.push(player_move);
+        Ok(self.get_state())
+    }
+
+    /// Returns the current state of the game.
+    ///
+    /// # Returns
+    ///
+    /// - `BoardState::Ended(Some(player))` if `player` completed a line.
+    /// - `BoardState::Ended(None)` if the board is full and nobody has won.
+    /// - `BoardState::Ongoing` if the game is still being played.
+    pub fn get_state(&self) -> BoardState {
+        match self.board.get_state() {
+            // The game stops at the first completed line, so the last move made it.
+            BoardState::Ended(Some(_)) => {
+                BoardState::Ended(self.history.last().map(WildMove::player))
+            }
+            state => state,
+        }
+    }
+
+    /// Returns every move made so far, in the order it was played.
+    pub fn history(&self) -> &[WildMove] {
+        &self.history
+    }
+
+    /// Takes back the last move.
+    ///
+    /// # Returns
+    ///
+    /// - `Some(WildMove)` with the move that was taken back.
+    /// - `None` if no moves have been made.
+    pub fn undo(&mut self) -> Option<WildMove> {
+        let player_move = self.history.pop()?;
+        self.board.undo();
+        self.undone.push(player_move);
+        Some(player_move)
+    }
+
+    /// Plays again the last move taken back with `undo`.
+    ///
+    /// # Returns
+    ///
+    /// - `Some(BoardState)` with the state of the game after the move is replayed.
+    /// - `None` if there is no move to redo.
+    pub fn redo(&mut self) -> Option<BoardState> {
+        let player_move = self.undone.pop()?;
+        self.board.redo();
+        self.history.push(player_move);
+        Some(self.get_state())
+    }
+
+    /// Returns a value that draws the board in the terminal with `{}`, with the completed
+    /// line highlighted.
+    pub fn display(&self) -> BoardDisplay<'_> {
+        self.board.display()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Plays `(position, mark)` moves in order, alternating players from X.
+    fn play(game: &mut WildBoard, moves: &[(usize, Player)]) -> BoardState {
+        let mut state = BoardState::Ongoing;
+        for &(position, mark) in moves {
+            let player_move = WildMove::create(position, mark, game.get_next_player()).unwrap();
+            state = game.make_move(player_move).unwrap();
+        }
+        state
+    }
+
+    #[test]
+    fn test_players_choose_their_mark() {
+        let mut game = WildBoard::new(Player::X);
+        play(&mut game, &[(1, Player::O), (2, Player::O)]);
+
+        assert_eq!(game.board().get_player_at(1), Some(Player::O));
+        assert_eq!(game.board().get_player_at(2), Some(Player::O));
+        assert_eq!(game.get_next_player(), Player::X);
+        assert_eq!(
+            game.make_move(WildMove::create(3, Player::X, Player::O).unwrap()),
+            Err(GameError::WrongPlayer {
+                expected: Player::X,
+                found: Player::O
+            })
+        );
+        assert_eq!(
+            game.make_move(WildMove::create(1, Player::X, Player::X).unwrap()),
+            Err(GameError::SlotOccupied(1))
+        );
+    }
+
+    #[test]
+    fn test_completing_any_line_wins() {
+        let mut game = WildBoard::new(Player::X);
+        // X completes a line of O marks.
+        let state = play(&mut game, &[(1, Player::O), (5, Player::X), (2, Player::O)]);
+        assert_eq!(state, BoardState::Ongoing);
+        assert_eq!(play(&mut game, &[(9, Player::X)]), BoardState::Ongoing);
+        assert_eq!(
+            play(&mut game, &[(3, Player::O)]),
+            BoardState::Ended(Some(Player::X))
+        );
+        assert_eq!(
+            game.make_move(WildMove::create(4, Player::X, Player::O).unwrap()),
+            Err(GameError::GameOver)
+        );
+
+        game.undo();
+        assert_eq!(game.get_state(), BoardState::Ongoing);
+        assert_eq!(game.board().get_player_at(3), None);
+        assert_eq!(game.redo(), Some(BoardState::Ended(Some(Player::X))));
+        assert_eq!(game.history().len(), 5);
+    }
+
+    #[test]
+    fn test_full_board_is_a_tie() {
+        let mut game = WildBoard::new(Player::O);
+        let state = play(
+            &mut game,
+            &[
+                (1, Player::X),
+                (2, Player::O),
+                (3, Player::X),
+                (4, Player::X),
+                (5, Player::O),
+                (6, Player::O),
+                (7, Player::O),
+                (8, Player::X),
+                (9, Player::X),
+            ],
+        );
+        assert_eq!(state, BoardState::Ended(None));
+    }
+}
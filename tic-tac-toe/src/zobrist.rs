@@ -0,0 +1,174 @@
+// zobrist.rs
+
+//! Zobrist hashing for boards.
+//!
+//! Every (cell, player) pair has a fixed random 64 bit key, and a board's hash is the XOR of
+//! the keys of its marks. Placing or removing a mark XORs its key in or out, so `Board` can
+//! keep its hash up to date in constant time as moves are made and undone.
+//!
+//! The keys come from a fixed seed, so the same position always has the same hash, even
+//! across runs. Boards of different sizes, win lengths, first players or rules start from
+//! different keys so their hashes do not collide in a shared table.
+use crate::tic_tac_toe::Player;
+
+/// The ways a board can be turned or flipped onto itself.
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub(crate) enum Symmetry {
+    Identity,
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    /// Swaps the left and right sides.
+    FlipHorizontal,
+    /// Swaps the top and bottom.
+    FlipVertical,
+    /// Reflects across the diagonal from the top left corner.
+    FlipDiagonal,
+    /// Reflects across the diagonal from the top right corner.
+    FlipAntiDiagonal,
+}
+
+/// The symmetries of a square board.
+const SQUARE_SYMMETRIES: [Symmetry; 8] = [
+    Symmetry::Identity,
+    Symmetry::Rotate90,
+    Symmetry::Rotate180,
+    Symmetry::Rotate270,
+    Symmetry::FlipHorizontal,
+    Symmetry::FlipVertical,
+    Symmetry::FlipDiagonal,
+    Symmetry::FlipAntiDiagonal,
+];
+
+/// The symmetries of a board that is not square. Turning it by 90 degrees would change its
+/// shape.
+const RECTANGLE_SYMMETRIES: [Symmetry; 4] = [
+    Symmetry::Identity,
+    Symmetry::Rotate180,
+    Symmetry::FlipHorizontal,
+    Symmetry::FlipVertical,
+];
+
+impl Symmetry {
+    /// Returns the symmetries of a `width` x `height` board. `Symmetry::Identity` is always first.
+    pub(crate) fn all(width: usize, height: usize) -> &'static [Symmetry] {
+        if width == height {
+            &SQUARE_SYMMETRIES
+        } else {
+            &RECTANGLE_SYMMETRIES
+        }
+    }
+
+    /// Returns where the cell at `(row, col)` ends up after applying the symmetry to a
+    /// `width` x `height` board. Rotations are clockwise.
+    pub(crate) fn apply(
+        &self,
+        row: usize,
+        col: usize,
+        width: usize,
+        height: usize,
+    ) -> (usize, usize) {
+        let (last_row, last_col) = (height - 1, width - 1);
+        match self {
+            Symmetry::Identity => (row, col),
+            Symmetry::Rotate90 => (col, last_row - row),
+            Symmetry::Rotate180 => (last_row - row, last_col - col),
+            Symmetry::Rotate270 => (last_col - col, row),
+            Symmetry::FlipHorizontal => (row, last_col - col),
+            Symmetry::FlipVertical => (last_row - row, col),
+            Symmetry::FlipDiagonal => (col, row),
+            Symmetry::FlipAntiDiagonal => (last_col - col, last_row - row),
+        }
+    }
+}
+
+/// Returns the key XORed into the hash for `player`'s mark in the cell with `index`, counted
+/// from 0 in reading order.
+pub(crate) fn mark_key(index: usize, player: Player) -> u64 {
+    let player = match player {
+        Player::X => 0,
+        Player::O => 1,
+    };
+    splitmix64(index as u64 * 2 + player)
+}
+
+/// Returns the hash of an empty board, which depends on its size, win length and first player.
+pub(crate) fn board_key(
+    width: usize,
+    height: usize,
+    layers: usize,
+    win_length: usize,
+    player_1: Player,
+) -> u64 {
+    let first = match player_1 {
+        Player::X => 0,
+        Player::O => 1,
+    };
+    // Offset so these keys never reuse a mark key's input.
+    splitmix64(u64::MAX - first)
+        ^ splitmix64((width as u64) << 42 | (height as u64) << 21 | win_length as u64)
+            .rotate_left(17)
+        ^ splitmix64(layers as u64).rotate_left(41)
+}
+
+/// Returns the key XORed into the hash of a board played by the misère rule, so its positions
+/// are never mistaken for the same positions under the standard rule.
+pub(crate) fn misere_key() -> u64 {
+    // Offset past the first player keys of `board_key`.
+    splitmix64(u64::MAX - 2)
+}
+
+/// Mixes `value` into a well spread 64 bit number (the SplitMix64 generator's output step).
+fn splitmix64(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_symmetries_stay_on_board() {
+        let (width, height) = (4, 2);
+        for symmetry in Symmetry::all(width, height) {
+            let mut cells: Vec<(usize, usize)> = (0..height)
+                .flat_map(|row| (0..width).map(move |col| symmetry.apply(row, col, width, height)))
+                .collect();
+            cells.sort();
+            cells.dedup();
+            assert_eq!(cells.len(), width * height);
+            assert!(cells.iter().all(|&(row, col)| row < height && col < width));
+        }
+    }
+
+    #[test]
+    fn test_rotations() {
+        // The top left corner of a 3x3 board turns to the top right, then bottom right.
+        assert_eq!(Symmetry::Rotate90.apply(0, 0, 3, 3), (0, 2));
+        assert_eq!(Symmetry::Rotate180.apply(0, 0, 3, 3), (2, 2));
+        assert_eq!(Symmetry::Rotate270.apply(0, 0, 3, 3), (2, 0));
+        assert_eq!(Symmetry::FlipAntiDiagonal.apply(0, 1, 3, 3), (1, 2));
+    }
+
+    #[test]
+    fn test_keys_differ() {
+        assert_ne!(mark_key(0, Player::X), mark_key(0, Player::O));
+        assert_ne!(mark_key(0, Player::O), mark_key(1, Player::X));
+        assert_ne!(
+            board_key(3, 3, 1, 3, Player::X),
+            board_key(3, 3, 1, 3, Player::O)
+        );
+        assert_ne!(
+            board_key(3, 3, 1, 3, Player::X),
+            board_key(4, 4, 1, 3, Player::X)
+        );
+        assert_ne!(
+            board_key(4, 4, 1, 4, Player::X),
+            board_key(4, 4, 4, 4, Player::X)
+        );
+        assert_ne!(misere_key(), board_key(3, 3, 1, 3, Player::X));
+    }
+}